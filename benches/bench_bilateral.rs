@@ -13,7 +13,7 @@ fn criterion_benchmark(c: &mut Criterion) {
             .into_ndarray2();
 
         image.iter_mut().for_each(|v| {
-            *v /= std::u16::MAX / 5000;
+            *v /= u16::MAX / 5000;
         });
         image
     };
@@ -50,7 +50,7 @@ fn main() {
     ) {
         let current_frame = range_processing.build(dataset.get(i).unwrap());
         let icp = MultiscaleAlign::new(icp_params.clone(), &last_frame).unwrap();
-        let transform = icp.align(&current_frame);
+        let transform = icp.align_with_initial(&current_frame, &trajectory_build.predict_next());
         trajectory_build.accumulate(&transform, Some(i as f32));
         last_frame = current_frame;
     }
@@ -5,8 +5,8 @@ use align3d::{
 
 pub fn load_dataset(format: String, path: String) -> Result<Box<dyn RgbdDataset + Send>, A3dError> {
     match format.as_str() {
-        "ilrgbd" => Ok(Box::new(IndoorLidarDataset::load(&path).unwrap())),
-        "tum" => Ok(Box::new(TumRgbdDataset::load(&path).unwrap())),
+        "ilrgbd" => Ok(Box::new(IndoorLidarDataset::load(&path)?)),
+        "tum" => Ok(Box::new(TumRgbdDataset::load(&path)?)),
         _ => Err(A3dError::invalid_parameter(format!(
             "Invalid dataset format: {format}"
         ))),
@@ -0,0 +1,20 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in uint rgb;
+
+layout(set = 0, binding = 0) uniform Data {
+  mat4 projection_worldview;
+}
+uniforms;
+
+layout(location = 0) out vec3 v_color;
+
+void main() {
+  gl_Position = uniforms.projection_worldview * vec4(position, 1.0);
+
+  float r = float((rgb >> 16) & 0xff);
+  float g = float((rgb >> 8) & 0xff);
+  float b = float(rgb & 0xff);
+  v_color = vec3(r, g, b) / 255.0;
+}
@@ -1,5 +1,20 @@
 #version 450
 
+// Shading modes, see `PointCloudShading`.
+const uint SHADING_COLOR = 0;
+const uint SHADING_FLAT = 1;
+const uint SHADING_NORMAL = 2;
+
+layout(set = 0, binding = 0) uniform Data {
+  mat4 worldview;
+  mat3 normal_worldview;
+  mat4 projection_worldview;
+  vec4 flat_color;
+  float point_size;
+  uint shading;
+}
+uniforms;
+
 layout(location = 0) in vec3 v_normal;
 layout(location = 1) in vec3 v_color;
 layout(location = 2) in vec2 v_quad_coords;
@@ -7,7 +22,13 @@ layout(location = 2) in vec2 v_quad_coords;
 layout(location = 0) out vec4 f_color;
 
 void main() {
-  f_color = vec4(v_color.xyz, 1.0);
+  if (uniforms.shading == SHADING_FLAT) {
+    f_color = uniforms.flat_color;
+  } else if (uniforms.shading == SHADING_NORMAL) {
+    f_color = vec4(v_normal, 1.0);
+  } else {
+    f_color = vec4(v_color.xyz, 1.0);
+  }
   if (dot(v_quad_coords, v_quad_coords) > 1.0) {
     discard;
   }
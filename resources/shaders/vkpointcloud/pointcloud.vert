@@ -8,6 +8,9 @@ layout(set = 0, binding = 0) uniform Data {
   mat4 worldview;
   mat3 normal_worldview;
   mat4 projection_worldview;
+  vec4 flat_color;
+  float point_size;
+  uint shading;
 }
 uniforms;
 
@@ -19,10 +22,10 @@ layout(location = 3) out float gs_radius;
 void main() {
   gs_position = position;
   gs_normal = normal;
-  gs_radius = 0.0005;
+  gs_radius = uniforms.point_size;
 
   float r = float((rgb >> 16) & 0xff);
   float g = float((rgb >> 8) & 0xff);
   float b = float(rgb & 0xff);
-  gs_color = vec3(b, g, r) / 255.0;
+  gs_color = vec3(r, g, b) / 255.0;
 }
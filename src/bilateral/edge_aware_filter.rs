@@ -1,6 +1,6 @@
 use std::{marker::PhantomData, mem::swap};
 
-use ndarray::{Array2, Array4, Axis};
+use ndarray::{Array2, Array4, Axis, Zip};
 use num::ToPrimitive;
 
 use super::BilateralGrid;
@@ -20,12 +20,13 @@ pub struct BilateralFilter<I> {
 impl<I> Default for BilateralFilter<I>
 where
     I: num::Bounded
-        + Ord
+        + PartialOrd
         + Copy
         + std::ops::Sub
         + ToPrimitive
         + std::convert::From<<I as std::ops::Sub>::Output>
-        + num::NumCast,
+        + num::NumCast
+        + Sync,
 {
     fn default() -> Self {
         BilateralFilter {
@@ -39,12 +40,13 @@ where
 impl<I> BilateralFilter<I>
 where
     I: num::Bounded
-        + Ord
+        + PartialOrd
         + Copy
         + std::ops::Sub
         + ToPrimitive
         + std::convert::From<<I as std::ops::Sub>::Output>
-        + num::NumCast,
+        + num::NumCast
+        + Sync,
 {
     pub fn new(sigma_space: f64, sigma_color: f64) -> Self {
         Self {
@@ -134,6 +136,69 @@ where
         grid.slice(image)
     }
 
+    /// Filters the image and estimates a per-pixel confidence from how much each pixel
+    /// was changed by the filter. Pixels that are consistent with their neighborhood keep
+    /// their value and get a high confidence, while noisy pixels get a low one.
+    ///
+    /// # Arguments:
+    ///
+    /// * `image`: Input image.
+    /// * `change_sigma`: Change, in the units of `I`, that reduces the confidence to about 0.6.
+    ///
+    /// # Returns:
+    ///
+    /// * The filtered image and the confidence map in [0, 1], computed as
+    ///   `exp(-0.5 * (change / change_sigma)^2)`. Ignored pixels (with the minimum value of `I`)
+    ///   have zero confidence.
+    pub fn filter_with_confidence(
+        &self,
+        image: &Array2<I>,
+        change_sigma: f64,
+    ) -> (Array2<I>, Array2<f32>)
+    where
+        I: num::Zero,
+    {
+        let filtered = self.filter(image);
+        let mut confidence = Array2::zeros(image.dim());
+        Zip::from(&mut confidence)
+            .and(image)
+            .and(&filtered)
+            .par_for_each(|confidence, original, filtered| {
+                if *original == I::min_value() {
+                    return;
+                }
+                let change =
+                    (original.to_f64().unwrap() - filtered.to_f64().unwrap()) / change_sigma;
+                *confidence = (-0.5 * change * change).exp() as f32;
+            });
+        (filtered, confidence)
+    }
+
+    /// Joint (cross) bilateral filtering. Filters `target` while preserving the edges
+    /// of `guidance`, e.g., filtering a depth map guided by its registered luma image.
+    ///
+    /// # Arguments:
+    ///
+    /// * `guidance`: Guidance image.
+    /// * `target`: Image to filter. Must have the same shape as `guidance`.
+    ///
+    /// # Returns:
+    ///
+    /// * The filtered target image.
+    pub fn filter_joint(&self, guidance: &Array2<I>, target: &Array2<I>) -> Array2<I>
+    where
+        I: num::Zero,
+    {
+        let mut grid =
+            BilateralGrid::from_joint_images(guidance, target, self.sigma_space, self.sigma_color);
+        BilateralFilter::convolution(&mut grid);
+
+        grid.normalize();
+        let mut dst = Array2::<I>::zeros(target.dim());
+        grid.slice_joint(guidance, target, &mut dst);
+        dst
+    }
+
     pub fn scale_down(&self, image: &Array2<I>) -> Array2<I>
     where
         I: num::Zero,
@@ -146,3 +211,184 @@ where
         })
     }
 }
+
+/// Filters an image using the bilateral grid. Shortcut for [`BilateralFilter::filter`]
+/// that works with any pixel type supported by [`BilateralGrid`], like `u8`, `u16` or `f32`.
+/// Pixels with the minimum value of `I` (e.g., 0 for unsigned types) are ignored.
+///
+/// # Arguments:
+///
+/// * `image`: Input image.
+/// * `sigma_space`: The space (XY) down sample factor.
+/// * `sigma_color`: The intensity down sample factor, in the units of `I`.
+///
+/// # Returns:
+///
+/// * The filtered image.
+pub fn bilateral_filter<I>(image: &Array2<I>, sigma_space: f64, sigma_color: f64) -> Array2<I>
+where
+    I: num::Bounded
+        + PartialOrd
+        + Copy
+        + std::ops::Sub
+        + ToPrimitive
+        + std::convert::From<<I as std::ops::Sub>::Output>
+        + num::NumCast
+        + Sync
+        + num::Zero,
+{
+    BilateralFilter::new(sigma_space, sigma_color).filter(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::unit_test::bloei_luma16;
+    use ndarray::{s, Array2};
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use rstest::rstest;
+
+    use super::{bilateral_filter, BilateralFilter};
+
+    /// Noisy vertical step edge, with `low` on the left and `high` on the right half.
+    fn noisy_step(low: f32, high: f32, noise: f32) -> Array2<f32> {
+        let mut rng = SmallRng::seed_from_u64(7);
+        Array2::from_shape_fn((64, 64), |(_, col)| {
+            let value = if col < 32 { low } else { high };
+            value + rng.gen_range(-noise..=noise)
+        })
+    }
+
+    /// Separable Gaussian blur with clamped borders.
+    fn gaussian_blur(image: &Array2<f64>, sigma: f64) -> Array2<f64> {
+        let radius = (3.0 * sigma).ceil() as i64;
+        let kernel = (-radius..=radius)
+            .map(|i| (-(i * i) as f64 / (2.0 * sigma * sigma)).exp())
+            .collect::<Vec<_>>();
+        let kernel_sum = kernel.iter().sum::<f64>();
+        let (height, width) = image.dim();
+        let convolve = |image: &Array2<f64>, horizontal: bool| {
+            Array2::from_shape_fn((height, width), |(row, col)| {
+                (-radius..=radius)
+                    .zip(kernel.iter())
+                    .map(|(offset, weight)| {
+                        let (row, col) = if horizontal {
+                            (
+                                row,
+                                (col as i64 + offset).clamp(0, width as i64 - 1) as usize,
+                            )
+                        } else {
+                            (
+                                (row as i64 + offset).clamp(0, height as i64 - 1) as usize,
+                                col,
+                            )
+                        };
+                        image[(row, col)] * weight
+                    })
+                    .sum::<f64>()
+                    / kernel_sum
+            })
+        };
+        convolve(&convolve(image, true), false)
+    }
+
+    /// Mean absolute difference between the columns at both sides of the step.
+    fn step_contrast(image: &Array2<f64>) -> f64 {
+        image
+            .rows()
+            .into_iter()
+            .map(|row| (row[33] - row[30]).abs())
+            .sum::<f64>()
+            / image.nrows() as f64
+    }
+
+    #[test]
+    fn verify_bilateral_filter_u8() {
+        let image = noisy_step(50.0, 200.0, 10.0).map(|v| *v as u8);
+        let filtered = bilateral_filter(&image, 4.5, 30.0);
+        let image = image.map(|v| *v as f64);
+
+        let bilateral_contrast = step_contrast(&filtered.map(|v| *v as f64));
+        let gaussian_contrast = step_contrast(&gaussian_blur(&image, 4.5));
+        assert!(bilateral_contrast > 140.0);
+        assert!(bilateral_contrast > 2.0 * gaussian_contrast);
+    }
+
+    #[test]
+    fn verify_bilateral_filter_f32() {
+        let image = noisy_step(1.0, 2.0, 0.05);
+        let filtered = bilateral_filter(&image, 4.5, 0.2);
+        let image = image.map(|v| *v as f64);
+
+        let bilateral_contrast = step_contrast(&filtered.map(|v| *v as f64));
+        let gaussian_contrast = step_contrast(&gaussian_blur(&image, 4.5));
+        assert!(bilateral_contrast > 0.9);
+        assert!(bilateral_contrast > 2.0 * gaussian_contrast);
+    }
+
+    #[test]
+    fn verify_filter_with_confidence() {
+        // Smooth on the left half and noisy on the right half, with a hole.
+        let mut rng = SmallRng::seed_from_u64(13);
+        let mut image = Array2::from_shape_fn((64, 64), |(_, col)| {
+            if col < 32 {
+                1000u16
+            } else {
+                (1000 + rng.gen_range(-45..=45)) as u16
+            }
+        });
+        image[(10, 10)] = 0;
+
+        let filter = BilateralFilter::new(4.5, 30.0);
+        let (filtered, confidence) = filter.filter_with_confidence(&image, 10.0);
+        assert_eq!(filtered, filter.filter(&image));
+        assert_eq!(confidence.dim(), image.dim());
+        assert!(confidence.iter().all(|c| (0.0..=1.0).contains(c)));
+        assert_eq!(confidence[(10, 10)], 0.0);
+
+        let mean_confidence = |cols: std::ops::Range<usize>| {
+            let region = confidence.slice(s![16..48, cols]);
+            region.sum() / region.len() as f32
+        };
+        let smooth_confidence = mean_confidence(8..24);
+        let noisy_confidence = mean_confidence(40..56);
+        assert!(smooth_confidence > 0.95, "{smooth_confidence}");
+        assert!(
+            noisy_confidence < smooth_confidence - 0.2,
+            "{noisy_confidence} vs {smooth_confidence}"
+        );
+    }
+
+    #[rstest]
+    fn verify_filter_joint(bloei_luma16: Array2<u16>) {
+        // A depth map with the same edges as the luma image, but noisy and with holes.
+        let mut rng = SmallRng::seed_from_u64(21);
+        let clean_depth = bloei_luma16.map(|v| v + 1000);
+        let noisy_depth = clean_depth.map(|v| {
+            if rng.gen_bool(0.05) {
+                0
+            } else {
+                (*v as i32 + rng.gen_range(-60..=60)) as u16
+            }
+        });
+
+        let filter = BilateralFilter::new(4.5, 30.0);
+        let filtered_depth = filter.filter_joint(&bloei_luma16, &noisy_depth);
+
+        let mean_error = |depth: &Array2<u16>| {
+            let (sum, count) = depth
+                .iter()
+                .zip(clean_depth.iter())
+                .filter(|(d, _)| **d > 0)
+                .fold((0.0, 0), |(sum, count), (d, c)| {
+                    (sum + (*d as f64 - *c as f64).abs(), count + 1)
+                });
+            sum / count as f64
+        };
+
+        assert!(filtered_depth
+            .iter()
+            .zip(noisy_depth.iter())
+            .all(|(f, n)| (*f == 0) == (*n == 0)));
+        assert!(mean_error(&filtered_depth) < 0.5 * mean_error(&noisy_depth));
+    }
+}
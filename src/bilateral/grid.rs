@@ -1,6 +1,6 @@
+use ndarray::parallel::prelude::*;
 use ndarray::{Array2, Array4, Axis};
 use num::{clamp, ToPrimitive};
-use std::cmp::{max, min};
 
 /// Bilateral grid. A data structure for representing images
 /// within its intensity space.
@@ -22,18 +22,44 @@ pub struct BilateralGrid<I> {
 impl<I> BilateralGrid<I>
 where
     I: num::Bounded
-        + Ord
+        + PartialOrd
         + Copy
         + std::ops::Sub
         + ToPrimitive
         + std::convert::From<<I as std::ops::Sub>::Output>
-        + num::NumCast,
+        + num::NumCast
+        + Sync,
 {
     pub fn from_image(image: &Array2<I>, sigma_space: f64, sigma_color: f64) -> Self {
+        Self::from_joint_images(image, image, sigma_space, sigma_color)
+    }
+
+    /// Creates a grid for joint (cross) bilateral filtering. The grid cells are
+    /// indexed by the `guidance` intensities, but accumulate the `target` values.
+    /// Use [`Self::slice_joint`] to apply it.
+    ///
+    /// # Arguments
+    ///
+    /// * `guidance` - Image whose edges should be preserved, e.g., a luma image.
+    /// * `target` - Image to filter, e.g., a depth map. Must have the same shape as `guidance`.
+    ///   Pixels with the minimum value of `I` are considered invalid and are skipped.
+    /// * `sigma_space` - The space (XY) down sample factor.
+    /// * `sigma_color` - The intensity down sample factor.
+    pub fn from_joint_images(
+        guidance: &Array2<I>,
+        target: &Array2<I>,
+        sigma_space: f64,
+        sigma_color: f64,
+    ) -> Self {
+        assert_eq!(
+            guidance.dim(),
+            target.dim(),
+            "Guidance and target images must have the same shape"
+        );
         let space_pad = 2;
         let color_pad = 2;
 
-        let (image_height, image_width) = image.dim();
+        let (image_height, image_width) = guidance.dim();
 
         let grid_height = ((image_height - 1) as f64 / sigma_space) as usize + 1 + 2 * space_pad;
         let grid_width = ((image_width - 1) as f64 / sigma_space) as usize + 1 + 2 * space_pad;
@@ -41,9 +67,13 @@ where
         let (color_min, color_max) = {
             let mut mi = I::max_value();
             let mut ma = I::min_value();
-            image.iter().for_each(|v| {
-                mi = min(mi, *v);
-                ma = max(ma, *v);
+            guidance.iter().for_each(|v| {
+                if *v < mi {
+                    mi = *v;
+                }
+                if *v > ma {
+                    ma = *v;
+                }
             });
             (mi, ma)
         };
@@ -56,26 +86,40 @@ where
         let inv_sigma_space = 1.0 / sigma_space;
         let inv_sigma_color = 1.0 / sigma_color;
 
+        // Image rows are scattered in parallel by grid row, so each thread owns a
+        // disjoint slice of the grid and the accumulation order matches the serial one.
+        let grid_rows = (0..image_height)
+            .map(|row| (row as f64 * inv_sigma_space + 0.5) as usize + space_pad)
+            .collect::<Vec<_>>();
+
         let mut grid = Array4::<f64>::zeros((grid_height, grid_width, grid_depth, 2));
-        for row in 0..image_height {
-            let grid_row = (row as f64 * inv_sigma_space + 0.5) as usize + space_pad;
+        grid.axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(grid_row, mut grid_slice)| {
+                let rows = grid_rows
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, image_grid_row)| **image_grid_row == grid_row)
+                    .map(|(row, _)| row);
+                for row in rows {
+                    for col in 0..image_width {
+                        let grid_col = (col as f64 * inv_sigma_space + 0.5) as usize + space_pad;
 
-            for col in 0..image_width {
-                let grid_col = (col as f64 * inv_sigma_space + 0.5) as usize + space_pad;
+                        let value = target[(row, col)];
+                        if value <= I::min_value() {
+                            continue;
+                        }
 
-                let color = image[(row, col)];
-                if color <= I::min_value() {
-                    continue;
+                        let channel = {
+                            let diff: I = (guidance[(row, col)] - color_min).into();
+                            (diff.to_f64().unwrap() * inv_sigma_color + 0.5) as usize + color_pad
+                        };
+                        grid_slice[(grid_col, channel, 0)] += value.to_f64().unwrap();
+                        grid_slice[(grid_col, channel, 1)] += 1.0;
+                    }
                 }
-
-                let channel = {
-                    let diff: I = (color - color_min).into();
-                    (diff.to_f64().unwrap() * inv_sigma_color + 0.5) as usize + color_pad
-                };
-                grid[(grid_row, grid_col, channel, 0)] += color.to_f64().unwrap();
-                grid[(grid_row, grid_col, channel, 1)] += 1.0;
-            }
-        }
+            });
 
         Self {
             data: grid,
@@ -131,6 +175,55 @@ where
         dst_image
     }
 
+    /// Slices a grid created by [`Self::from_joint_images`], i.e., looks up the filtered
+    /// target values using the guidance intensities. The grid should be normalized first.
+    ///
+    /// # Arguments
+    ///
+    /// * `guidance` - The guidance image used to create the grid.
+    /// * `target` - The target image used to create the grid. Its invalid pixels
+    ///   (minimum value of `I`) are kept invalid in `dst`.
+    /// * `dst` - The output image. `guidance`, `target` and `dst` must have the same shape.
+    pub fn slice_joint(&self, guidance: &Array2<I>, target: &Array2<I>, dst: &mut Array2<I>) {
+        assert_eq!(
+            guidance.dim(),
+            target.dim(),
+            "Guidance and target images must have the same shape"
+        );
+        assert_eq!(
+            guidance.dim(),
+            dst.dim(),
+            "Guidance and destination images must have the same shape"
+        );
+
+        let inv_sigma_space = 1.0 / self.sigma_space;
+        let inv_sigma_color = 1.0 / self.sigma_color;
+        let space_pad = self.space_pad as f64;
+        let color_pad = self.color_pad as f64;
+
+        guidance
+            .iter()
+            .zip(target.iter())
+            .zip(dst.indexed_iter_mut())
+            .for_each(|((color, value), ((row, col), dst))| {
+                if *value <= I::min_value() {
+                    *dst = *value;
+                    return;
+                }
+
+                let trilinear = self.trilinear(
+                    row as f64 * inv_sigma_space + space_pad,
+                    col as f64 * inv_sigma_space + space_pad,
+                    {
+                        let diff: I = (*color - self.color_min).into();
+                        diff.to_f64().unwrap() * inv_sigma_color + color_pad
+                    },
+                );
+
+                *dst = num::cast::cast(trilinear).unwrap();
+            });
+    }
+
     pub fn trilinear(&self, row: f64, col: f64, channel: f64) -> f64 {
         let (height, width, depth, _) = self.data.dim();
 
@@ -168,8 +261,10 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::time::Instant;
+
     use crate::unit_test::bloei_luma16;
-    use ndarray::Array2;
+    use ndarray::{Array2, Array4};
     use rstest::{fixture, rstest};
 
     use super::BilateralGrid;
@@ -192,4 +287,46 @@ mod tests {
         assert_eq!(dest_image.dim(), (600, 450));
         assert_eq!(dest_image[(421, 123)], 2266);
     }
+
+    /// Serial reference of the grid construction.
+    fn serial_grid_data(image: &Array2<u16>, grid: &BilateralGrid<u16>) -> Array4<f64> {
+        let mut data = Array4::<f64>::zeros(grid.dim());
+        let inv_sigma_space = 1.0 / grid.sigma_space;
+        let inv_sigma_color = 1.0 / grid.sigma_color;
+        for ((row, col), color) in image.indexed_iter() {
+            if *color == 0 {
+                continue;
+            }
+            let grid_row = (row as f64 * inv_sigma_space + 0.5) as usize + grid.space_pad;
+            let grid_col = (col as f64 * inv_sigma_space + 0.5) as usize + grid.space_pad;
+            let channel =
+                ((color - grid.color_min) as f64 * inv_sigma_color + 0.5) as usize + grid.color_pad;
+            data[(grid_row, grid_col, channel, 0)] += *color as f64;
+            data[(grid_row, grid_col, channel, 1)] += 1.0;
+        }
+        data
+    }
+
+    #[rstest]
+    fn verify_parallel_equals_serial(
+        bloei_luma16: Array2<u16>,
+        bilateral_grid: BilateralGrid<u16>,
+    ) {
+        assert_eq!(
+            bilateral_grid.data,
+            serial_grid_data(&bloei_luma16, &bilateral_grid)
+        );
+    }
+
+    #[rstest]
+    #[ignore]
+    fn bench_grid_creation(bloei_luma16: Array2<u16>) {
+        let now = Instant::now();
+        let grid = BilateralGrid::from_image(&bloei_luma16, 4.5, 30.0);
+        println!("Parallel grid creation: {:?}", now.elapsed());
+
+        let now = Instant::now();
+        serial_grid_data(&bloei_luma16, &grid);
+        println!("Serial grid creation: {:?}", now.elapsed());
+    }
 }
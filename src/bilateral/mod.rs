@@ -1,5 +1,5 @@
 mod edge_aware_filter;
 mod grid;
 
-pub use edge_aware_filter::BilateralFilter;
+pub use edge_aware_filter::{bilateral_filter, BilateralFilter};
 pub use grid::BilateralGrid;
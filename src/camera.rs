@@ -1,9 +1,15 @@
-use nalgebra::Vector3;
+use nalgebra::{Matrix3, Vector3};
+use ndarray::parallel::prelude::*;
+use ndarray::{Array2, ArrayView2, Axis};
 
 use super::transform::Transform;
 
 /// Camera intrinsic parameters.
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
 pub struct CameraIntrinsics {
     /// Focal length and pixel scale in the X-axis.
     pub fx: f64,
@@ -17,6 +23,9 @@ pub struct CameraIntrinsics {
     pub width: usize,
     /// Image height in pixels.
     pub height: usize,
+    /// Brown-Conrady lens distortion coefficients, in the order (k1, k2, p1, p2, k3).
+    /// `None` for an ideal pinhole.
+    pub distortion: Option<[f32; 5]>,
 }
 
 impl CameraIntrinsics {
@@ -49,9 +58,126 @@ impl CameraIntrinsics {
             cy,
             width,
             height,
+            distortion: None,
         }
     }
 
+    /// Create the camera intrinsic parameters from a 3x3 intrinsic matrix (K).
+    ///
+    /// # Arguments
+    ///
+    /// * k: The intrinsic matrix, i.e., [[fx, 0, cx], [0, fy, cy], [0, 0, 1]].
+    /// * width: Image width in pixels.
+    /// * height: Image height in pixels.
+    pub fn from_matrix3(k: &Matrix3<f32>, width: usize, height: usize) -> Self {
+        Self::from_simple_intrinsic(
+            k[(0, 0)] as f64,
+            k[(1, 1)] as f64,
+            k[(0, 2)] as f64,
+            k[(1, 2)] as f64,
+            width,
+            height,
+        )
+    }
+
+    /// Returns the 3x3 intrinsic matrix (K).
+    pub fn to_matrix3(&self) -> Matrix3<f32> {
+        Matrix3::new(
+            self.fx as f32,
+            0.0,
+            self.cx as f32,
+            0.0,
+            self.fy as f32,
+            self.cy as f32,
+            0.0,
+            0.0,
+            1.0,
+        )
+    }
+
+    /// Sets the lens distortion coefficients.
+    ///
+    /// # Arguments
+    ///
+    /// * distortion: Brown-Conrady coefficients (k1, k2, p1, p2, k3).
+    pub fn with_distortion(mut self, distortion: [f32; 5]) -> Self {
+        self.distortion = Some(distortion);
+        self
+    }
+
+    /// Applies the distortion model to normalized image coordinates.
+    fn distort_normalized(distortion: &[f32; 5], x: f32, y: f32) -> (f32, f32) {
+        let [k1, k2, p1, p2, k3] = *distortion;
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+        (
+            x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x),
+            y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y,
+        )
+    }
+
+    /// Maps an undistorted (ideal pinhole) pixel into its distorted location in the image.
+    /// Returns the same pixel if the camera has no distortion.
+    ///
+    /// # Arguments
+    ///
+    /// * u: The x coordinate.
+    /// * v: The y coordinate.
+    ///
+    /// # Returns
+    ///
+    /// * The distorted (x and y) coordinates.
+    pub fn distort(&self, u: f32, v: f32) -> (f32, f32) {
+        let distortion = match &self.distortion {
+            Some(distortion) => distortion,
+            None => return (u, v),
+        };
+
+        let (fx, fy, cx, cy) = (
+            self.fx as f32,
+            self.fy as f32,
+            self.cx as f32,
+            self.cy as f32,
+        );
+        let (x, y) = Self::distort_normalized(distortion, (u - cx) / fx, (v - cy) / fy);
+        (x * fx + cx, y * fy + cy)
+    }
+
+    /// Maps a distorted image pixel into its undistorted (ideal pinhole) location.
+    /// The distortion model isn't invertible in closed form, so it uses fixed point iterations.
+    /// Returns the same pixel if the camera has no distortion.
+    ///
+    /// # Arguments
+    ///
+    /// * u: The x coordinate.
+    /// * v: The y coordinate.
+    ///
+    /// # Returns
+    ///
+    /// * The undistorted (x and y) coordinates.
+    pub fn undistort(&self, u: f32, v: f32) -> (f32, f32) {
+        let distortion = match &self.distortion {
+            Some(distortion) => distortion,
+            None => return (u, v),
+        };
+
+        let (fx, fy, cx, cy) = (
+            self.fx as f32,
+            self.fy as f32,
+            self.cx as f32,
+            self.cy as f32,
+        );
+        let (xd, yd) = ((u - cx) / fx, (v - cy) / fy);
+        let (mut x, mut y) = (xd, yd);
+        for _ in 0..20 {
+            let (dist_x, dist_y) = Self::distort_normalized(distortion, x, y);
+            x += xd - dist_x;
+            y += yd - dist_y;
+        }
+
+        (x * fx + cx, y * fy + cy)
+    }
+
     /// Project a 3D point into image space.
     ///
     /// # Arguments
@@ -107,6 +233,77 @@ impl CameraIntrinsics {
         )
     }
 
+    /// Backproject a whole depth image into 3D space. Pixels with zero depth are
+    /// marked invalid. Rows are processed in parallel.
+    ///
+    /// # Arguments
+    ///
+    /// * depth: The depth image, shape is (height, width).
+    /// * depth_scale: Scale multiplied to the depth values to convert them into 3D units.
+    ///
+    /// # Returns
+    ///
+    /// * The 3D points and the mask of valid points, both with the same shape of `depth`.
+    pub fn backproject_image(
+        &self,
+        depth: &ArrayView2<u16>,
+        depth_scale: f32,
+    ) -> (Array2<Vector3<f32>>, Array2<u8>) {
+        self.backproject_image_with(depth, depth_scale, 0.0, None)
+    }
+
+    /// Backproject a whole depth image into 3D space, with the depth computed as
+    /// `depth * depth_scale + depth_bias`. Pixels with zero depth, non-positive computed depth or
+    /// computed depth beyond `depth_max` are marked invalid. Rows are processed in parallel.
+    ///
+    /// # Arguments
+    ///
+    /// * depth: The depth image, shape is (height, width).
+    /// * depth_scale: Scale multiplied to the depth values to convert them into 3D units.
+    /// * depth_bias: Offset added to the scaled depth, in 3D units.
+    /// * depth_max: Maximum valid depth, in 3D units. Far depth readings are usually noisy.
+    ///
+    /// # Returns
+    ///
+    /// * The 3D points and the mask of valid points, both with the same shape of `depth`.
+    pub fn backproject_image_with(
+        &self,
+        depth: &ArrayView2<u16>,
+        depth_scale: f32,
+        depth_bias: f32,
+        depth_max: Option<f32>,
+    ) -> (Array2<Vector3<f32>>, Array2<u8>) {
+        let depth_max = depth_max.unwrap_or(f32::INFINITY);
+        let mut points = Array2::<Vector3<f32>>::zeros(depth.dim());
+        let mut mask = Array2::<u8>::zeros(depth.dim());
+
+        points
+            .axis_iter_mut(Axis(0))
+            .into_par_iter()
+            .zip(mask.axis_iter_mut(Axis(0)).into_par_iter())
+            .enumerate()
+            .for_each(|(y, (mut point_row, mut mask_row))| {
+                for (x, ((point, mask), z)) in point_row
+                    .iter_mut()
+                    .zip(mask_row.iter_mut())
+                    .zip(depth.row(y).iter())
+                    .enumerate()
+                {
+                    if *z == 0 {
+                        continue;
+                    }
+                    let z = *z as f32 * depth_scale + depth_bias;
+                    if z > 0.0 && z <= depth_max {
+                        let (u, v) = self.undistort(x as f32, y as f32);
+                        *point = self.backproject(u, v, z);
+                        *mask = 1;
+                    }
+                }
+            });
+
+        (points, mask)
+    }
+
     /// Scale the camera parameters according to the given scale.
     ///
     /// # Arguments
@@ -124,6 +321,35 @@ impl CameraIntrinsics {
             cy: self.cy * scale,
             width: self.width,
             height: self.height,
+            distortion: self.distortion,
+        }
+    }
+
+    /// Returns the camera parameters of a cropped region of the image.
+    ///
+    /// # Arguments
+    ///
+    /// * x_offset: Left column of the crop.
+    /// * y_offset: Top row of the crop.
+    /// * new_width: Width of the crop.
+    /// * new_height: Height of the crop.
+    ///
+    /// # Returns
+    ///
+    /// * A new camera with the center shifted by the offset and the crop size.
+    pub fn crop(
+        &self,
+        x_offset: usize,
+        y_offset: usize,
+        new_width: usize,
+        new_height: usize,
+    ) -> Self {
+        Self {
+            cx: self.cx - x_offset as f64,
+            cy: self.cy - y_offset as f64,
+            width: new_width,
+            height: new_height,
+            ..self.clone()
         }
     }
 
@@ -134,6 +360,11 @@ impl CameraIntrinsics {
 }
 /// A pinhole camera. It is defined by its intrinsic parameters and its pose in the world.
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(from = "PinholeCameraSerde", into = "PinholeCameraSerde")
+)]
 pub struct PinholeCamera {
     pub intrinsics: CameraIntrinsics,
     pub camera_to_world: Transform,
@@ -142,6 +373,31 @@ pub struct PinholeCamera {
     height_f32: f32,
 }
 
+/// Serialized form of `PinholeCamera`, the other fields are derived from these.
+#[cfg(feature = "serde")]
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct PinholeCameraSerde {
+    intrinsics: CameraIntrinsics,
+    camera_to_world: Transform,
+}
+
+#[cfg(feature = "serde")]
+impl From<PinholeCamera> for PinholeCameraSerde {
+    fn from(camera: PinholeCamera) -> Self {
+        Self {
+            intrinsics: camera.intrinsics,
+            camera_to_world: camera.camera_to_world,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PinholeCameraSerde> for PinholeCamera {
+    fn from(camera: PinholeCameraSerde) -> Self {
+        PinholeCamera::new(camera.intrinsics, camera.camera_to_world)
+    }
+}
+
 impl PinholeCamera {
     /// Create a new pinhole camera.
     ///
@@ -200,12 +456,84 @@ impl PinholeCamera {
             None
         }
     }
+
+    /// Projects a batch of 3D points into image space in parallel.
+    /// See [`Self::project_to_image`].
+    ///
+    /// # Arguments
+    ///
+    /// * points: The 3D points.
+    ///
+    /// # Returns
+    ///
+    /// * The (x, y, z) coordinates of each point, None for points that aren't visible.
+    pub fn project_points(&self, points: &[Vector3<f32>]) -> Vec<Option<(f32, f32, f32)>> {
+        points
+            .par_iter()
+            .map(|point| self.project_to_image(point))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use nalgebra::{Quaternion, Vector3};
+
     use crate::transform::Transform;
 
+    use super::{CameraIntrinsics, PinholeCamera};
+
+    #[test]
+    pub fn test_matrix3() {
+        let k = nalgebra::Matrix3::new(544.0, 0.0, 320.0, 0.0, 540.0, 240.0, 0.0, 0.0, 1.0);
+        let camera = CameraIntrinsics::from_matrix3(&k, 640, 480);
+        assert_eq!(
+            (camera.fx, camera.fy, camera.cx, camera.cy),
+            (544.0, 540.0, 320.0, 240.0)
+        );
+        assert_eq!(camera.to_matrix3(), k);
+    }
+
+    #[test]
+    pub fn test_crop() {
+        let camera = CameraIntrinsics::from_simple_intrinsic(544.0, 544.0, 320.0, 240.0, 640, 480);
+        let cropped = camera.crop(100, 50, 200, 150);
+        assert_eq!((cropped.width, cropped.height), (200, 150));
+
+        // The crop's top-left corner is the pixel (100, 50) of the full image.
+        assert_eq!(
+            cropped.backproject(0.0, 0.0, 2.0),
+            camera.backproject(100.0, 50.0, 2.0)
+        );
+    }
+
+    #[test]
+    pub fn test_undistort() {
+        let camera = CameraIntrinsics::from_simple_intrinsic(544.0, 544.0, 320.0, 240.0, 640, 480)
+            .with_distortion([0.12, -0.25, 0.001, -0.002, 0.05]);
+
+        for v in (0..480).step_by(40) {
+            for u in (0..640).step_by(40) {
+                let (u, v) = (u as f32, v as f32);
+                let (ud, vd) = camera.distort(u, v);
+                let (uu, vu) = camera.undistort(ud, vd);
+                assert!((uu - u).abs() < 1e-2, "{uu} != {u}");
+                assert!((vu - v).abs() < 1e-2, "{vu} != {v}");
+            }
+        }
+
+        let zero_camera =
+            CameraIntrinsics::from_simple_intrinsic(544.0, 544.0, 320.0, 240.0, 640, 480)
+                .with_distortion([0.0; 5]);
+        let pinhole = CameraIntrinsics::from_simple_intrinsic(544.0, 544.0, 320.0, 240.0, 640, 480);
+        let (u, v) = zero_camera.undistort(13.0, 421.0);
+        assert_eq!(
+            zero_camera.backproject(u, v, 1.5),
+            pinhole.backproject(13.0, 421.0, 1.5)
+        );
+        assert_eq!(zero_camera.distort(13.0, 421.0), (13.0, 421.0));
+    }
+
     #[test]
     pub fn test_project() {
         let camera = super::PinholeCamera::new(
@@ -241,4 +569,58 @@ mod tests {
         assert_eq!(x, 50.0);
         assert_eq!(y, 75.0);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_serde() {
+        let camera = super::PinholeCamera::new(
+            CameraIntrinsics::from_simple_intrinsic(544.0, 544.0, 320.0, 240.0, 640, 480)
+                .with_distortion([0.12, -0.25, 0.001, -0.002, 0.05]),
+            Transform::new(
+                &nalgebra::Vector3::new(1.0, 2.0, 3.0),
+                &nalgebra::Quaternion::new(0.9, 0.1, 0.2, 0.3),
+            ),
+        );
+
+        let json = serde_json::to_string(&camera.intrinsics).unwrap();
+        let intrinsics: CameraIntrinsics = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            (intrinsics.fx, intrinsics.fy, intrinsics.cx, intrinsics.cy),
+            (544.0, 544.0, 320.0, 240.0)
+        );
+        assert_eq!((intrinsics.width, intrinsics.height), (640, 480));
+        assert_eq!(intrinsics.distortion, camera.intrinsics.distortion);
+
+        let json = serde_json::to_string(&camera).unwrap();
+        let restored: super::PinholeCamera = serde_json::from_str(&json).unwrap();
+        let point = nalgebra::Vector3::new(1.5, 2.5, 6.0);
+        let (x, y, z) = camera.project(&point);
+        let (rx, ry, rz) = restored.project(&point);
+        assert!((x - rx).abs() < 1e-3 && (y - ry).abs() < 1e-3 && (z - rz).abs() < 1e-5);
+    }
+
+    #[test]
+    pub fn test_project_points() {
+        let camera = PinholeCamera::new(
+            CameraIntrinsics::from_simple_intrinsic(544.0, 544.0, 320.0, 240.0, 640, 480),
+            Transform::new(
+                &Vector3::new(0.1, -0.2, 0.3),
+                &Quaternion::new(0.9, 0.1, 0.2, 0.1),
+            ),
+        );
+        let points = (0..1000)
+            .map(|i| {
+                let i = i as f32;
+                Vector3::new((i * 0.37).sin(), (i * 0.11).cos(), (i * 0.05).sin() + 1.5)
+            })
+            .collect::<Vec<_>>();
+
+        let projections = camera.project_points(&points);
+        assert_eq!(projections.len(), points.len());
+        for (point, projection) in points.iter().zip(projections.iter()) {
+            assert_eq!(*projection, camera.project_to_image(point));
+        }
+        assert!(projections.iter().any(|projection| projection.is_some()));
+        assert!(projections.iter().any(|projection| projection.is_none()));
+    }
 }
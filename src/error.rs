@@ -6,6 +6,8 @@ pub enum A3dError {
     Io(std::io::Error),
     Parser(String),
     Assertion(String),
+    /// Used when Vulkan can't be initialized, e.g., no suitable GPU is available.
+    Vulkan(String),
 }
 
 impl std::fmt::Display for A3dError {
@@ -15,6 +17,7 @@ impl std::fmt::Display for A3dError {
             A3dError::Parser(err) => write!(f, "Parser error: {err}"),
             A3dError::InvalidParameter(err) => write!(f, "Parameter error: {err}"),
             A3dError::Assertion(err) => write!(f, "Assertion err,or: {err}"),
+            A3dError::Vulkan(err) => write!(f, "Vulkan error: {err}"),
         }
     }
 }
@@ -26,6 +29,13 @@ impl A3dError {
     pub fn invalid_parameter<T: ToString>(msg: T) -> Self {
         A3dError::InvalidParameter(msg.to_string())
     }
+
+    /// Create a error with the kind `Vulkan`.
+    /// # Arguments
+    /// * `msg` - The error message.
+    pub fn vulkan<T: ToString>(msg: T) -> Self {
+        A3dError::Vulkan(msg.to_string())
+    }
 }
 
 impl std::error::Error for A3dError {
@@ -35,6 +45,7 @@ impl std::error::Error for A3dError {
             A3dError::Parser(_) => None,
             A3dError::InvalidParameter(_) => None,
             A3dError::Assertion(_) => None,
+            A3dError::Vulkan(_) => None,
         }
     }
 }
@@ -13,3 +13,21 @@ use nalgebra::{RealField, Vector3};
 pub fn angle_between_normals<T: RealField>(lfs: &Vector3<T>, rfs: &Vector3<T>) -> T {
     lfs.dot(rfs).acos().abs()
 }
+
+/// Returns the axis-aligned bounding box of a set of points.
+///
+/// # Arguments
+///
+/// * points - Iterator over the points.
+///
+/// # Returns
+///
+/// * The (min, max) corners, or None if there are no points.
+pub fn bounding_box<'a>(
+    points: impl IntoIterator<Item = &'a Vector3<f32>>,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    points.into_iter().fold(None, |bbox, point| match bbox {
+        None => Some((*point, *point)),
+        Some((min, max)) => Some((min.inf(point), max.sup(point))),
+    })
+}
@@ -0,0 +1,2 @@
+mod tsdf;
+pub use tsdf::TsdfVolume;
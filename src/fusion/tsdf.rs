@@ -0,0 +1,435 @@
+use std::collections::{HashMap, HashSet};
+
+use nalgebra::Vector3;
+use ndarray::{Array1, Array2};
+use rayon::prelude::{IntoParallelRefMutIterator, ParallelIterator};
+
+use crate::{
+    camera::PinholeCamera,
+    io::{Geometry, GeometryBuilder},
+    range_image::RangeImage,
+};
+
+/// Number of voxels along each side of a block.
+const BLOCK_SIZE: i32 = 8;
+const BLOCK_VOLUME: usize = (BLOCK_SIZE * BLOCK_SIZE * BLOCK_SIZE) as usize;
+
+/// Corner offsets of a cube, corner `i` has offset `(i & 1, (i >> 1) & 1, (i >> 2) & 1)`.
+const CUBE_CORNERS: [[i32; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [0, 1, 0],
+    [1, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [0, 1, 1],
+    [1, 1, 1],
+];
+
+/// Splits a cube into 6 tetrahedra sharing the diagonal from corner 0 to 7 (Kuhn triangulation).
+/// Neighbor cubes split their shared faces in the same way, so the surface has no cracks.
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 1, 3, 7],
+    [0, 1, 5, 7],
+    [0, 2, 3, 7],
+    [0, 2, 6, 7],
+    [0, 4, 5, 7],
+    [0, 4, 6, 7],
+];
+
+#[derive(Clone, Copy)]
+struct Voxel {
+    tsdf: f32,
+    weight: f32,
+    color: Vector3<f32>,
+}
+
+impl Default for Voxel {
+    fn default() -> Self {
+        Self {
+            tsdf: 1.0,
+            weight: 0.0,
+            color: Vector3::zeros(),
+        }
+    }
+}
+
+/// Local index of a voxel inside its block.
+fn local_index(local: &Vector3<i32>) -> usize {
+    (local[0] + BLOCK_SIZE * (local[1] + BLOCK_SIZE * local[2])) as usize
+}
+
+/// Inverse of [`local_index`].
+fn local_coord(index: usize) -> Vector3<i32> {
+    let index = index as i32;
+    Vector3::new(
+        index % BLOCK_SIZE,
+        (index / BLOCK_SIZE) % BLOCK_SIZE,
+        index / (BLOCK_SIZE * BLOCK_SIZE),
+    )
+}
+
+/// Truncated signed distance function (TSDF) volume. It's a fusion backend alternative to surfels
+/// that produces watertight meshes. Voxels are allocated in blocks of 8x8x8 stored in a hash map
+/// (voxel hashing), so only the space near the observed surfaces uses memory.
+///
+/// Distances are positive in front of the surface (free space) and negative behind it.
+pub struct TsdfVolume {
+    voxel_size: f32,
+    truncation: f32,
+    max_weight: f32,
+    has_colors: bool,
+    blocks: HashMap<Vector3<i32>, Vec<Voxel>>,
+}
+
+impl TsdfVolume {
+    /// Creates an empty volume. The truncation distance defaults to 4 voxels
+    /// and the maximum weight to 64 observations.
+    ///
+    /// # Arguments
+    ///
+    /// * `voxel_size` - Length of the voxels' side, in world units.
+    pub fn new(voxel_size: f32) -> Self {
+        Self {
+            voxel_size,
+            truncation: voxel_size * 4.0,
+            max_weight: 64.0,
+            has_colors: false,
+            blocks: HashMap::new(),
+        }
+    }
+
+    /// Sets the truncation distance, i.e., how far from the surface the distances are updated.
+    pub fn with_truncation(mut self, truncation: f32) -> Self {
+        self.truncation = truncation;
+        self
+    }
+
+    /// Sets the maximum weight of a voxel. Lower values adapt faster to changes in the scene.
+    pub fn with_max_weight(mut self, max_weight: f32) -> Self {
+        self.max_weight = max_weight;
+        self
+    }
+
+    /// Length of the voxels' side.
+    pub fn voxel_size(&self) -> f32 {
+        self.voxel_size
+    }
+
+    /// Number of allocated voxel blocks.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Fuses a range image into the volume. Blocks along the camera rays near the observed
+    /// points are allocated, then the voxels in them are updated with the projective distance
+    /// to the range image's points.
+    ///
+    /// # Arguments
+    ///
+    /// * `range_image` - The range image, with points in the camera frame. Colors are fused
+    ///   if present.
+    /// * `camera` - The camera of the range image, with its pose in the world.
+    pub fn integrate(&mut self, range_image: &RangeImage, camera: &PinholeCamera) {
+        let block_extent = self.voxel_size * BLOCK_SIZE as f32;
+        let origin = camera.camera_to_world.translation();
+        let num_steps = ((self.truncation * 4.0) / block_extent).ceil().max(1.0) as usize;
+
+        let mut touched_blocks = HashSet::new();
+        for (point, _) in range_image
+            .points
+            .iter()
+            .zip(range_image.mask.iter())
+            .filter(|(_, mask)| **mask != 0)
+        {
+            let point = camera.camera_to_world.transform_vector(point);
+            let ray = (point - origin).normalize();
+            if !ray.iter().all(|x| x.is_finite()) {
+                continue;
+            }
+
+            let start = point - ray * self.truncation;
+            for step in 0..=num_steps {
+                let sample = start + ray * (2.0 * self.truncation * step as f32 / num_steps as f32);
+                touched_blocks.insert((sample / block_extent).map(|x| x.floor() as i32));
+            }
+        }
+
+        for key in touched_blocks.iter() {
+            self.blocks
+                .entry(*key)
+                .or_insert_with(|| vec![Voxel::default(); BLOCK_VOLUME]);
+        }
+
+        let (height, width) = (range_image.height(), range_image.width());
+        let (voxel_size, truncation, max_weight) =
+            (self.voxel_size, self.truncation, self.max_weight);
+        let colors = range_image.colors.as_ref();
+        self.has_colors |= colors.is_some();
+
+        self.blocks
+            .par_iter_mut()
+            .filter(|(key, _)| touched_blocks.contains(*key))
+            .for_each(|(key, block)| {
+                for (index, voxel) in block.iter_mut().enumerate() {
+                    let coord = key * BLOCK_SIZE + local_coord(index);
+                    let (u, v, z) = camera.project(&(coord.cast::<f32>() * voxel_size));
+                    let (col, row) = (u.round(), v.round());
+                    if z <= 0.0 || col < 0.0 || row < 0.0 {
+                        continue;
+                    }
+                    let (col, row) = (col as usize, row as usize);
+                    if col >= width || row >= height || range_image.mask[(row, col)] == 0 {
+                        continue;
+                    }
+
+                    let sdf = range_image.points[(row, col)][2] - z;
+                    if sdf < -truncation {
+                        continue;
+                    }
+
+                    let weight = voxel.weight + 1.0;
+                    voxel.tsdf = (voxel.tsdf * voxel.weight + (sdf / truncation).min(1.0)) / weight;
+                    if let Some(colors) = colors {
+                        voxel.color = (voxel.color * voxel.weight
+                            + colors[(row, col)].cast::<f32>())
+                            / weight;
+                    }
+                    voxel.weight = weight.min(max_weight);
+                }
+            });
+    }
+
+    /// Returns the voxel at the given grid coordinate if it was observed.
+    fn voxel(&self, coord: &Vector3<i32>) -> Option<&Voxel> {
+        let key = coord.map(|x| x.div_euclid(BLOCK_SIZE));
+        let local = coord.map(|x| x.rem_euclid(BLOCK_SIZE));
+        self.blocks
+            .get(&key)
+            .map(|block| &block[local_index(&local)])
+            .filter(|voxel| voxel.weight > 0.0)
+    }
+
+    /// Extracts the zero level set of the volume as a triangle mesh. Each cube of voxels is
+    /// split into tetrahedra that are polygonized independently (marching tetrahedra), and
+    /// vertices are shared between adjacent triangles. Faces are oriented towards the free space,
+    /// i.e., towards the cameras.
+    ///
+    /// # Returns
+    ///
+    /// The mesh with vertex normals, and colors if the fused range images had colors.
+    pub fn extract_mesh(&self) -> Geometry {
+        let mut mesh = MeshExtraction::default();
+
+        let mut keys = self.blocks.keys().copied().collect::<Vec<_>>();
+        keys.sort_by_key(|key| (key[0], key[1], key[2]));
+        for key in keys {
+            for index in 0..BLOCK_VOLUME {
+                let coord = key * BLOCK_SIZE + local_coord(index);
+                let corners = CUBE_CORNERS.map(|offset| coord + Vector3::from(offset));
+                let voxels = corners
+                    .iter()
+                    .map(|corner| self.voxel(corner).copied())
+                    .collect::<Option<Vec<_>>>();
+                let voxels = match voxels {
+                    Some(voxels) => voxels,
+                    None => continue,
+                };
+                let num_inside = voxels.iter().filter(|voxel| voxel.tsdf < 0.0).count();
+                if num_inside == 0 || num_inside == 8 {
+                    continue;
+                }
+
+                for tetrahedron in TETRAHEDRA.iter() {
+                    mesh.polygonize(
+                        &tetrahedron.map(|i| (corners[i], voxels[i])),
+                        self.voxel_size,
+                    );
+                }
+            }
+        }
+
+        let num_faces = mesh.faces.len();
+        let mut builder = GeometryBuilder::new(Array1::from_vec(mesh.points))
+            .with_faces(Array2::from_shape_vec((num_faces, 3), mesh.faces.concat()).unwrap());
+        if self.has_colors {
+            builder = builder.with_colors(Array1::from_vec(mesh.colors));
+        }
+        let mut geometry = builder.build();
+        geometry.compute_vertex_normals();
+        geometry
+    }
+}
+
+/// Mesh under construction by [`TsdfVolume::extract_mesh`].
+#[derive(Default)]
+struct MeshExtraction {
+    points: Vec<Vector3<f32>>,
+    colors: Vec<Vector3<u8>>,
+    faces: Vec<[usize; 3]>,
+    /// Vertex index of each grid edge crossing the surface.
+    edge_vertices: HashMap<([i32; 3], [i32; 3]), usize>,
+}
+
+impl MeshExtraction {
+    /// Returns the vertex where the surface crosses the edge between two voxels, adding it
+    /// if it's the first time the edge is visited.
+    fn edge_vertex(
+        &mut self,
+        a: &(Vector3<i32>, Voxel),
+        b: &(Vector3<i32>, Voxel),
+        voxel_size: f32,
+    ) -> usize {
+        let (a, b) = if a.0.as_slice() < b.0.as_slice() {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        // Crossings at the voxels themselves are shared by all their edges.
+        let t = a.1.tsdf / (a.1.tsdf - b.1.tsdf);
+        let (key, t) = if t <= 1e-6 {
+            ((a.0.into(), a.0.into()), 0.0)
+        } else if t >= 1.0 - 1e-6 {
+            ((b.0.into(), b.0.into()), 1.0)
+        } else {
+            ((a.0.into(), b.0.into()), t)
+        };
+        if let Some(index) = self.edge_vertices.get(&key) {
+            return *index;
+        }
+
+        let (pa, pb) = (a.0.cast::<f32>(), b.0.cast::<f32>());
+        self.points.push((pa + (pb - pa) * t) * voxel_size);
+        self.colors.push(
+            (a.1.color + (b.1.color - a.1.color) * t).map(|c| c.round().clamp(0.0, 255.0) as u8),
+        );
+
+        let index = self.points.len() - 1;
+        self.edge_vertices.insert(key, index);
+        index
+    }
+
+    /// Adds the triangles of the surface crossing a tetrahedron.
+    fn polygonize(&mut self, corners: &[(Vector3<i32>, Voxel); 4], voxel_size: f32) {
+        let (inside, outside): (Vec<_>, Vec<_>) =
+            corners.iter().partition(|(_, voxel)| voxel.tsdf < 0.0);
+
+        let vertices = match (inside.len(), outside.len()) {
+            (1, 3) => outside
+                .iter()
+                .map(|corner| self.edge_vertex(inside[0], corner, voxel_size))
+                .collect::<Vec<_>>(),
+            (3, 1) => inside
+                .iter()
+                .map(|corner| self.edge_vertex(corner, outside[0], voxel_size))
+                .collect(),
+            (2, 2) => vec![
+                self.edge_vertex(inside[0], outside[0], voxel_size),
+                self.edge_vertex(inside[0], outside[1], voxel_size),
+                self.edge_vertex(inside[1], outside[1], voxel_size),
+                self.edge_vertex(inside[1], outside[0], voxel_size),
+            ],
+            _ => return,
+        };
+
+        // Orients the faces from the inside (negative) to the outside (positive) corners.
+        let centroid = |corners: &[&(Vector3<i32>, Voxel)]| {
+            corners
+                .iter()
+                .map(|(coord, _)| coord.cast::<f32>())
+                .sum::<Vector3<f32>>()
+                / corners.len() as f32
+        };
+        let outwards = centroid(&outside) - centroid(&inside);
+
+        for i in 1..vertices.len() - 1 {
+            let mut face = [vertices[0], vertices[i], vertices[i + 1]];
+            let (p0, p1, p2) = (
+                self.points[face[0]],
+                self.points[face[1]],
+                self.points[face[2]],
+            );
+            let normal = (p1 - p0).cross(&(p2 - p0));
+            if normal.norm_squared() < 1e-12 {
+                continue;
+            }
+            if normal.dot(&outwards) < 0.0 {
+                face.swap(1, 2);
+            }
+            self.faces.push(face);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Quaternion, Vector3};
+
+    use crate::{
+        camera::{CameraIntrinsics, PinholeCamera},
+        io::write_ply,
+        range_image::RangeImage,
+        transform::Transform,
+    };
+
+    use super::TsdfVolume;
+
+    #[test]
+    fn test_integrate_planes() {
+        const VOXEL_SIZE: f32 = 0.02;
+        let intrinsics = CameraIntrinsics::from_simple_intrinsic(50.0, 50.0, 32.0, 24.0, 64, 48);
+
+        // Views a plane at z = 1 from a few positions.
+        let mut volume = TsdfVolume::new(VOXEL_SIZE).with_truncation(VOXEL_SIZE * 3.0);
+        for i in 0..3 {
+            let camera = PinholeCamera::new(
+                intrinsics.clone(),
+                Transform::new(
+                    &Vector3::new(i as f32 * 0.05, 0.0, 0.0),
+                    &Quaternion::identity(),
+                ),
+            );
+            let range_image = RangeImage::from_intrinsics_fn(
+                &intrinsics,
+                |row, col| Some(intrinsics.backproject(col as f32, row as f32, 1.0)),
+                |_, _| None,
+                |_, _| Some(Vector3::new(200, 100, 50)),
+            );
+            volume.integrate(&range_image, &camera);
+        }
+        assert!(volume.num_blocks() > 0);
+
+        let mesh = volume.extract_mesh();
+        write_ply("tests/outputs/out-tsdf-planes.ply", &mesh).unwrap();
+
+        let faces = mesh.faces.as_ref().unwrap();
+        assert!(faces.nrows() > 1000);
+        assert!(mesh
+            .points
+            .iter()
+            .all(|point| (point[2] - 1.0).abs() < VOXEL_SIZE * 0.5));
+
+        // Faces point towards the cameras.
+        for face in faces.rows() {
+            let (p0, p1, p2) = (
+                mesh.points[face[0]],
+                mesh.points[face[1]],
+                mesh.points[face[2]],
+            );
+            let normal = (p1 - p0).cross(&(p2 - p0));
+            assert!(normal[2] < 0.0, "{normal:?}");
+        }
+        assert!(mesh
+            .normals
+            .as_ref()
+            .unwrap()
+            .iter()
+            .all(|normal| normal[2] < -0.99));
+        assert!(mesh
+            .colors
+            .as_ref()
+            .unwrap()
+            .iter()
+            .all(|color| *color == Vector3::new(200, 100, 50)));
+    }
+}
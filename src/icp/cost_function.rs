@@ -1,4 +1,4 @@
-use nalgebra::Vector3;
+use nalgebra::{Matrix3, Vector3};
 
 pub struct PointPlaneDistance {}
 
@@ -26,8 +26,8 @@ impl PointPlaneDistance {
     /// # Returns
     ///
     /// * $J^tr$ and $J^tJ$ of the point-plane distance,
-    /// where the first is a (6) vector and the former is a (6, 6)
-    /// matrix as a (36) vector.
+    ///   where the first is a (6) vector and the former is a (6, 6)
+    ///   matrix as a (36) vector.
     pub fn jacobian(
         &self,
         source_point: &Vector3<f32>,
@@ -39,6 +39,36 @@ impl PointPlaneDistance {
     }
 }
 
+pub struct GeneralizedDistance {}
+
+impl GeneralizedDistance {
+    /// Computes the residuals and the Jacobians of the Mahalanobis distance between
+    /// two corresponding points. The distance is whitened into three scalar residuals.
+    ///
+    /// # Arguments
+    ///
+    /// * source_point - 3D point in the source frame.
+    /// * target_point - 3D point in the target frame.
+    /// * weight_factor - Cholesky factor $L$ of the weighting matrix $W = L L^t$, where
+    ///   $W = (C_t + R C_s R^t)^{-1}$ is the inverse of the combined point covariances.
+    ///
+    /// # Returns
+    ///
+    /// * The residual and Jacobian of each whitened component.
+    pub fn jacobian(
+        &self,
+        source_point: &Vector3<f32>,
+        target_point: &Vector3<f32>,
+        weight_factor: &Matrix3<f32>,
+    ) -> [(f32, [f32; 6]); 3] {
+        let difference = target_point - source_point;
+        [0, 1, 2].map(|i| {
+            let axis = weight_factor.column(i).into_owned();
+            (difference.dot(&axis), se3_jacobian(source_point, &axis))
+        })
+    }
+}
+
 pub struct ColorDistance {}
 
 impl ColorDistance {
@@ -55,3 +85,29 @@ impl ColorDistance {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix3, Vector3};
+
+    use super::{GeneralizedDistance, PointPlaneDistance};
+
+    #[test]
+    fn test_generalized_distance() {
+        let source = Vector3::new(0.1, 0.2, 1.0);
+        let target = Vector3::new(0.3, -0.1, 1.2);
+
+        let terms = GeneralizedDistance {}.jacobian(&source, &target, &Matrix3::identity());
+        let squared_distance = terms.iter().map(|(r, _)| r * r).sum::<f32>();
+        assert!((squared_distance - (target - source).norm_squared()).abs() < 1e-6);
+
+        // A weighting matrix that only keeps the normal direction is the point-to-plane distance.
+        let normal = Vector3::z();
+        let terms =
+            GeneralizedDistance {}.jacobian(&source, &target, &(normal * normal.transpose()));
+        assert_eq!(
+            terms[2],
+            PointPlaneDistance {}.jacobian(&source, &target, &normal)
+        );
+    }
+}
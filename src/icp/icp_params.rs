@@ -3,6 +3,38 @@ use std::{
     ops::{Index, IndexMut},
 };
 
+/// Optimization method used to solve each iteration of the image ICP
+/// ([`super::ImageIcp`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcpOptimizer {
+    /// Solves the normal equations directly.
+    GaussNewton,
+    /// Damps the normal equations and rejects steps that increase the residual.
+    LevenbergMarquardt,
+}
+
+/// Geometric cost minimized by the point cloud ICP ([`super::Icp`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcpCost {
+    /// Distance between the source points and the tangent planes of the target points.
+    PointToPlane,
+    /// Generalized-ICP (plane-to-plane). Models each point with a covariance estimated
+    /// from its neighborhood and minimizes the Mahalanobis distance between correspondences.
+    /// Doesn't require normals and is more robust to noisy data.
+    Generalized,
+}
+
+/// Interpolation used for sampling the target intensities in the image ICP
+/// ([`super::ImageIcp`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorInterpolation {
+    /// Bilinear interpolation over the 2x2 neighborhood.
+    Bilinear,
+    /// Bicubic (Catmull-Rom) interpolation over the 4x4 neighborhood. Slower, but
+    /// its gradients are smoother, which helps near convergence.
+    Bicubic,
+}
+
 /// ICP parameters
 #[derive(Debug, Clone, Copy)]
 pub struct IcpParams {
@@ -20,6 +52,20 @@ pub struct IcpParams {
     pub max_normal_angle: f32,
 
     pub max_color_distance: f32,
+    /// Optimization method. Only used by the image ICP, the point cloud ICP always
+    /// uses Gauss-Newton.
+    pub optimizer: IcpOptimizer,
+    /// Geometric cost. Only used by the point cloud ICP.
+    pub cost: IcpCost,
+    /// Interpolation of the target intensities. Only used by the image ICP.
+    pub color_interpolation: ColorInterpolation,
+    /// Interpolates the target points at the subpixel projections of the source points,
+    /// instead of using the nearest pixel. Only used by the image ICP.
+    pub subpixel_points: bool,
+    /// Huber threshold of the color residuals. Larger residuals, e.g., from specular
+    /// highlights or shadows, are down-weighted. `None` weights all color residuals
+    /// equally. Only used by the image ICP.
+    pub color_huber_delta: Option<f32>,
 }
 
 impl Default for IcpParams {
@@ -39,6 +85,11 @@ impl Default for IcpParams {
             max_distance: 0.5,
             max_normal_angle: 18.0_f32.to_radians(),
             max_color_distance: 0.25,
+            optimizer: IcpOptimizer::GaussNewton,
+            cost: IcpCost::PointToPlane,
+            color_interpolation: ColorInterpolation::Bilinear,
+            subpixel_points: false,
+            color_huber_delta: None,
         }
     }
 }
@@ -55,16 +106,33 @@ impl IcpParams {
     }
 }
 
+/// Multiscale ICP parameters, one [`IcpParams`] per pyramid level.
+/// Level 0 is the finest one, i.e., the last level run.
 #[derive(Debug, Clone)]
 pub struct MsIcpParams {
     pyramid: Vec<IcpParams>,
 }
 
 impl MsIcpParams {
+    /// Creates from per-level parameters ordered from the finest to the coarsest level,
+    /// the same order of the range image pyramids.
     pub fn new(pyramid: Vec<IcpParams>) -> Self {
         Self { pyramid }
     }
 
+    /// Creates from per-level parameters ordered from the coarsest to the finest level,
+    /// i.e., in the order they are run. Coarse levels usually take larger distance
+    /// thresholds than the fine ones. A single level can be changed afterwards by
+    /// indexing, e.g., `params[0] = IcpParams { .. }` sets the finest one.
+    ///
+    /// # Arguments
+    ///
+    /// * `levels` - The ICP parameters of each level, the first one is the coarsest.
+    pub fn from_coarse_to_fine(mut levels: Vec<IcpParams>) -> Self {
+        levels.reverse();
+        Self { pyramid: levels }
+    }
+
     pub fn repeat(levels: usize, params: &IcpParams) -> Self {
         Self {
             pyramid: vec![*params; levels],
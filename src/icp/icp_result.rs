@@ -0,0 +1,43 @@
+use nalgebra::Matrix6;
+
+use crate::transform::Transform;
+
+/// Result of an ICP alignment.
+#[derive(Debug, Clone)]
+pub struct IcpResult {
+    /// The transformation that aligns the source to the target.
+    pub transform: Transform,
+    /// Mean squared residual of the best iteration.
+    pub residual: f32,
+    /// Information matrix of the pose, i.e., the inverse of its covariance.
+    /// Its layout follows the `[x, y, z, rx, ry, rz]` twist order. It's `None`
+    /// when the alignment is degenerate (e.g., a single plane).
+    pub information: Option<Matrix6<f32>>,
+    /// Source to target correspondences of the best iteration, as `(source index, target index, residual)`.
+    /// Only collected when requested, see `collect_correspondences` in [`crate::icp::Icp`]
+    /// and [`crate::icp::ImageIcp`]. The mean of the squared residuals is the alignment's
+    /// residual for the point-to-plane ICP.
+    pub correspondences: Option<Vec<(usize, usize, f32)>>,
+}
+
+/// State of the optimization after an ICP iteration. See the `on_iteration` callbacks
+/// of [`crate::icp::Icp`] and [`crate::icp::ImageIcp`].
+#[derive(Debug, Clone)]
+pub struct IcpIterationInfo {
+    /// Index of the iteration, starting at 0.
+    pub iteration: usize,
+    /// The transformation after the iteration's update.
+    pub transform: Transform,
+    /// Mean squared residual of the iteration's correspondences.
+    pub residual: f32,
+    /// Smallest residual so far, i.e., the one of the transformation that will be returned.
+    pub best_residual: f32,
+    /// Number of correspondences used by the iteration.
+    pub num_correspondences: usize,
+    /// Number of color residuals rejected for exceeding `max_color_distance`.
+    /// Always 0 for the point cloud ICP.
+    pub num_color_rejected: usize,
+}
+
+/// Callback invoked after each ICP iteration. It's a `FnMut`, so it can mutate external state.
+pub type IcpIterationCallback<'a> = Box<dyn FnMut(&IcpIterationInfo) + 'a>;
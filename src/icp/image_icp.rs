@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use itertools::izip;
 use nalgebra::Vector3;
 use ndarray::Axis;
@@ -5,21 +7,29 @@ use num::Float;
 use rayon::prelude::{ParallelBridge, ParallelIterator};
 
 use crate::{
+    error::A3dError,
     extra_math,
-    optim::GaussNewton,
+    optim::{GaussNewton, HuberEstimator, LevenbergMarquardt, RobustEstimator},
     range_image::RangeImage,
     transform::{LieGroup, Transform},
 };
 
 use super::{
     cost_function::{ColorDistance, PointPlaneDistance},
-    icp_params::IcpParams,
+    icp_params::{ColorInterpolation, IcpOptimizer, IcpParams},
+    IcpIterationCallback, IcpIterationInfo, IcpResult,
 };
 
 pub struct ImageIcp<'target_lt> {
     pub params: IcpParams,
     target: &'target_lt RangeImage,
     pub initial_transform: Transform,
+    /// Called after each iteration, e.g., for logging or visualizing the convergence.
+    /// It's in a `RefCell` so aligning doesn't need a mutable `ImageIcp`.
+    pub on_iteration: RefCell<Option<IcpIterationCallback<'target_lt>>>,
+    /// Whether to return the correspondences in [`IcpResult::correspondences`].
+    /// The source and target indices are the pixels' row-major indices.
+    pub collect_correspondences: bool,
 }
 
 impl<'target_lt> ImageIcp<'target_lt> {
@@ -28,6 +38,8 @@ impl<'target_lt> ImageIcp<'target_lt> {
             params,
             target,
             initial_transform: Transform::eye(),
+            on_iteration: RefCell::new(None),
+            collect_correspondences: false,
         }
     }
 
@@ -41,39 +53,84 @@ impl<'target_lt> ImageIcp<'target_lt> {
     ///
     /// * The transformation that aligns the source point cloud to the target point cloud.
     pub fn align(&self, source: &RangeImage) -> Transform {
-        let intensity_map = self
-            .target
-            .intensity_map
-            .as_ref()
-            .expect("Please, the target image should have a intensity map.");
-        let target_normals = self
-            .target
-            .normals
-            .as_ref()
-            .expect("Please, the target image should have normals.");
-        let source_colors = source
-            .intensities
-            .as_ref()
-            .expect("Please, the source image should have intensity colors.");
+        self.align_with_result(source).transform
+    }
+
+    /// Aligns the source point cloud to the target point cloud.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source point cloud.
+    ///
+    /// # Returns
+    ///
+    /// * The alignment result, with the transformation, the residual and the pose information matrix.
+    ///
+    /// # Panics
+    ///
+    /// If the images lack required attributes, see [`Self::try_align_with_result`].
+    pub fn align_with_result(&self, source: &RangeImage) -> IcpResult {
+        self.try_align_with_result(source)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Aligns the source point cloud to the target point cloud.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source point cloud.
+    ///
+    /// # Returns
+    ///
+    /// * The alignment result, or an `A3dError::InvalidParameter` if the images lack required
+    ///   attributes: the target needs normals and an intensity map, and the source needs intensities.
+    pub fn try_align_with_result(&self, source: &RangeImage) -> Result<IcpResult, A3dError> {
+        let intensity_map = self.target.intensity_map.as_ref().ok_or_else(|| {
+            A3dError::invalid_parameter(
+                "The target image needs an intensity map, see `RangeImage::compute_intensity_map`.",
+            )
+        })?;
+        let target_normals = self.target.normals.as_ref().ok_or_else(|| {
+            A3dError::invalid_parameter(
+                "The target image needs normals, see `RangeImage::compute_normals`.",
+            )
+        })?;
+        let source_colors = source.intensities.as_ref().ok_or_else(|| {
+            A3dError::invalid_parameter(
+                "The source image needs intensities, see `RangeImage::compute_intensity`.",
+            )
+        })?;
 
         let mut optim_transform = self.initial_transform.clone();
 
         let geometric_distance = PointPlaneDistance {};
         let color_distance = ColorDistance {};
+        let color_kernel = self
+            .params
+            .color_huber_delta
+            .map(|delta| HuberEstimator { delta });
 
         let max_color_distance_sqr =
             self.params.max_color_distance * self.params.max_color_distance;
         let max_distance_sqr = self.params.max_distance * self.params.max_distance;
 
-        let mut geom_optim = GaussNewton::<6>::new();
-        let mut color_optim = GaussNewton::<6>::new();
+        let mut lm_optim = LevenbergMarquardt::<6>::default();
+        let mut lm_accepted_transform = optim_transform.clone();
 
         let mut best_residual = Float::infinity();
         let mut best_transform = optim_transform.clone();
+        // Transform of the best iteration's correspondences, i.e., before its update.
+        let mut best_source_transform = optim_transform.clone();
+        let mut best_covariance = None;
 
         const BATCH_SIZE: usize = 4096;
+        // The batches only borrow these fields, as `on_iteration` can't be shared between threads.
+        let (params, target) = (&self.params, self.target);
 
-        for _ in 0..self.params.max_iterations {
+        // Accumulates the geometric and color residuals of the correspondences under `transform`.
+        // Returns their optimizers, the number of correspondences and of rejected color residuals,
+        // and the correspondences if `collect` is set.
+        let add_correspondences = |transform: &Transform, collect: bool| {
             let sub_gn_opts = izip!(
                 source
                     .mask
@@ -93,20 +150,35 @@ impl<'target_lt> ImageIcp<'target_lt> {
                     .unwrap()
                     .axis_chunks_iter(Axis(0), BATCH_SIZE)
             )
+            .enumerate()
             .par_bridge()
-            .map(|(mask_chunk, point_chunk, color_chunk)| {
+            .map(|(chunk_index, (mask_chunk, point_chunk, color_chunk))| {
                 let mut color_sub_opt = GaussNewton::<6>::new();
                 let mut geom_sub_opt = GaussNewton::<6>::new();
+                let mut num_correspondences = 0;
+                let mut num_color_rejected = 0;
+                let mut correspondences = Vec::new();
 
-                for (mask, point, color) in izip!(mask_chunk, point_chunk, color_chunk) {
+                for (index, (mask, point, color)) in
+                    izip!(mask_chunk, point_chunk, color_chunk).enumerate()
+                {
                     if *mask == 0 {
                         continue;
                     }
 
-                    let p = optim_transform.transform_vector(point);
-                    let (u, v) = self.target.intrinsics.project(&p);
-                    let (u_int, v_int) = ((u + 0.5) as i32, (v + 0.5) as i32);
-                    let target_point = self.target.get_point(v_int as usize, u_int as usize);
+                    let p = transform.transform_vector(point);
+                    let (u, v) = target.intrinsics.project(&p);
+                    if !(u.is_finite() && v.is_finite()) {
+                        continue;
+                    }
+                    let (u_int, v_int) = ((u + 0.5).floor() as i32, (v + 0.5).floor() as i32);
+                    // The nearest pixel must be valid, as its normal is used below.
+                    let nearest_point = target.get_point_checked(u_int, v_int);
+                    let target_point = if params.subpixel_points {
+                        nearest_point.and_then(|_| target.get_point_bilinear(u, v))
+                    } else {
+                        nearest_point
+                    };
                     if target_point.is_none() {
                         continue;
                     }
@@ -117,7 +189,7 @@ impl<'target_lt> ImageIcp<'target_lt> {
 
                     let target_normal = target_normals[(v_int as usize, u_int as usize)];
                     if extra_math::angle_between_normals(&p, &target_normal)
-                        >= self.params.max_normal_angle
+                        >= params.max_normal_angle
                     {
                         continue;
                     }
@@ -126,53 +198,153 @@ impl<'target_lt> ImageIcp<'target_lt> {
                         geometric_distance.jacobian(&p, &target_point, &target_normal);
 
                     geom_sub_opt.step(residual, &jacobian);
+                    num_correspondences += 1;
+                    if collect {
+                        correspondences.push((
+                            chunk_index * BATCH_SIZE + index,
+                            v_int as usize * target.width() + u_int as usize,
+                            residual,
+                        ));
+                    }
                     // Color part.
-                    let (target_color, du, dv) = intensity_map.bilinear_grad(u, v);
+                    let (target_color, du, dv) = match params.color_interpolation {
+                        ColorInterpolation::Bilinear => intensity_map.bilinear_grad(u, v),
+                        ColorInterpolation::Bicubic => intensity_map.bicubic_grad(u, v),
+                    };
                     let source_color = *color as f32 * 0.003_921_569; // / 255.0;
-                    let ((dfx, dcx), (dfy, dcy)) = self.target.intrinsics.project_grad(&p);
+                    let ((dfx, dcx), (dfy, dcy)) = target.intrinsics.project_grad(&p);
                     let color_gradient = Vector3::new(du * dfx, dv * dfy, du * dcx + dv * dcy);
                     let (color_residual, color_jacobian) =
                         color_distance.jacobian(&p, &color_gradient, source_color, target_color);
-                    if color_residual * color_residual <= max_color_distance_sqr {
-                        color_sub_opt.step(color_residual, &color_jacobian);
+                    if color_residual * color_residual > max_color_distance_sqr {
+                        num_color_rejected += 1;
+                        continue;
+                    }
+                    match color_kernel.as_ref() {
+                        Some(kernel) => {
+                            // Iteratively reweighted least squares.
+                            let weight = kernel.backward(color_residual);
+                            color_sub_opt.step(
+                                color_residual * weight,
+                                &color_jacobian.map(|value| value * weight),
+                            );
+                        }
+                        None => color_sub_opt.step(color_residual, &color_jacobian),
                     }
                 }
 
-                (color_sub_opt, geom_sub_opt)
+                (
+                    color_sub_opt,
+                    geom_sub_opt,
+                    num_correspondences,
+                    num_color_rejected,
+                    correspondences,
+                )
             })
             .collect::<Vec<_>>();
 
-            for sub_gn in sub_gn_opts.iter() {
+            let mut color_optim = GaussNewton::<6>::new();
+            let mut geom_optim = GaussNewton::<6>::new();
+            let mut num_correspondences = 0;
+            let mut num_color_rejected = 0;
+            let mut correspondences = Vec::new();
+            for sub_gn in sub_gn_opts.into_iter() {
                 color_optim.add(&sub_gn.0);
                 geom_optim.add(&sub_gn.1);
+                num_correspondences += sub_gn.2;
+                num_color_rejected += sub_gn.3;
+                correspondences.extend(sub_gn.4);
             }
+            (
+                color_optim,
+                geom_optim,
+                num_correspondences,
+                num_color_rejected,
+                correspondences,
+            )
+        };
 
-            geom_optim.add_weighted(&color_optim, self.params.weight, self.params.color_weight);
-            let residual = geom_optim.mean_squared_residual();
-            let update = geom_optim.solve().unwrap();
-            optim_transform = &Transform::exp(&LieGroup::Se3(update)) * &optim_transform;
-
-            geom_optim.reset();
-            color_optim.reset();
+        for iteration in 0..self.params.max_iterations {
+            let source_transform = optim_transform.clone();
+            let (color_optim, geom_optim, num_correspondences, num_color_rejected, _) =
+                add_correspondences(&source_transform, false);
+            let optim = GaussNewton::combine([
+                (&geom_optim, self.params.weight),
+                (&color_optim, self.params.color_weight),
+            ]);
+            let residual = optim.mean_squared_residual();
+            let covariance = optim.covariance();
+            let next_transform = match self.params.optimizer {
+                IcpOptimizer::GaussNewton => optim
+                    .solve()
+                    .map(|update| &Transform::exp(&LieGroup::Se3(update)) * &optim_transform),
+                IcpOptimizer::LevenbergMarquardt => {
+                    lm_optim.reset();
+                    lm_optim.add(&optim);
+                    if let Some(update) = lm_optim.try_step(residual) {
+                        lm_accepted_transform = optim_transform.clone();
+                        Some(&Transform::exp(&LieGroup::Se3(update)) * &optim_transform)
+                    } else {
+                        lm_optim.solve().map(|update| {
+                            &Transform::exp(&LieGroup::Se3(update)) * &lm_accepted_transform
+                        })
+                    }
+                }
+            };
+            let degenerate = next_transform.is_none();
+            if let Some(next_transform) = next_transform {
+                optim_transform = next_transform;
+            }
 
             if residual < best_residual {
                 best_residual = residual;
                 best_transform = optim_transform.clone();
+                best_source_transform = source_transform;
+                best_covariance = covariance;
+            }
+
+            if let Some(on_iteration) = self.on_iteration.borrow_mut().as_mut() {
+                on_iteration(&IcpIterationInfo {
+                    iteration,
+                    transform: optim_transform.clone(),
+                    residual,
+                    best_residual,
+                    num_correspondences,
+                    num_color_rejected,
+                });
+            }
+
+            if degenerate {
+                // Degenerate system, e.g., no correspondences were found.
+                break;
             }
         }
-        best_transform
+
+        // Found once, after converging, to not slow down the iterations.
+        let correspondences = self
+            .collect_correspondences
+            .then(|| add_correspondences(&best_source_transform, true).4);
+
+        Ok(IcpResult {
+            transform: best_transform,
+            residual: best_residual,
+            information: best_covariance.and_then(|covariance| covariance.try_inverse()),
+            correspondences,
+        })
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "serde"))]
 mod tests {
     use std::time::Instant;
 
+    use nalgebra::Vector3;
     use rstest::rstest;
 
     use super::ImageIcp;
     use crate::{
-        icp::icp_params::IcpParams,
+        error::A3dError,
+        icp::icp_params::{ColorInterpolation, IcpOptimizer, IcpParams},
         metrics::TransformMetrics,
         unit_test::{sample_range_img_ds2, TestRangeImageDataset},
     };
@@ -198,4 +370,225 @@ mod tests {
         println!("Result metric: {}", angle_diff);
         assert!(angle_diff < 0.01);
     }
+
+    #[rstest]
+    fn test_align_levenberg_marquardt(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let rimage1 = sample_range_img_ds2.get(1).unwrap();
+
+        let gt_transform = sample_range_img_ds2.get_ground_truth(1, 0);
+
+        let actual = ImageIcp::new(
+            IcpParams {
+                max_iterations: 10,
+                optimizer: IcpOptimizer::LevenbergMarquardt,
+                ..Default::default()
+            },
+            &rimage0,
+        )
+        .align(&rimage1);
+        let angle_diff = TransformMetrics::new(&actual, &gt_transform).angle.abs();
+        assert!(angle_diff < 0.01);
+    }
+
+    #[rstest]
+    fn test_align_bicubic(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let rimage1 = sample_range_img_ds2.get(1).unwrap();
+
+        let gt_transform = sample_range_img_ds2.get_ground_truth(1, 0);
+
+        let actual = ImageIcp::new(
+            IcpParams {
+                max_iterations: 5,
+                color_interpolation: ColorInterpolation::Bicubic,
+                ..Default::default()
+            },
+            &rimage0,
+        )
+        .align(&rimage1);
+        let angle_diff = TransformMetrics::new(&actual, &gt_transform).angle.abs();
+        assert!(angle_diff < 0.01);
+    }
+
+    #[rstest]
+    fn test_align_subpixel_points(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let rimage1 = sample_range_img_ds2.get(1).unwrap();
+
+        let gt_transform = sample_range_img_ds2.get_ground_truth(1, 0);
+
+        let actual = ImageIcp::new(
+            IcpParams {
+                max_iterations: 5,
+                subpixel_points: true,
+                ..Default::default()
+            },
+            &rimage0,
+        )
+        .align(&rimage1);
+        let angle_diff = TransformMetrics::new(&actual, &gt_transform).angle.abs();
+        assert!(angle_diff < 0.01);
+    }
+
+    #[rstest]
+    fn test_missing_attributes(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let mut rimage1 = sample_range_img_ds2.get(1).unwrap();
+        rimage1.intensities = None;
+
+        let result = ImageIcp::new(IcpParams::default(), &rimage0).try_align_with_result(&rimage1);
+        assert!(matches!(result, Err(A3dError::InvalidParameter(_))));
+    }
+
+    #[rstest]
+    fn test_on_iteration(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let rimage1 = sample_range_img_ds2.get(1).unwrap();
+
+        let mut infos = Vec::new();
+        let mut icp = ImageIcp::new(
+            IcpParams {
+                max_iterations: 5,
+                ..Default::default()
+            },
+            &rimage0,
+        );
+        *icp.on_iteration.get_mut() = Some(Box::new(|info| infos.push(info.clone())));
+        let result = icp.align_with_result(&rimage1);
+        drop(icp);
+
+        assert_eq!(infos.len(), 5);
+        for (i, info) in infos.iter().enumerate() {
+            assert_eq!(info.iteration, i);
+            assert!(info.num_correspondences > 0);
+            assert!(info.best_residual <= info.residual);
+        }
+        assert!(infos
+            .windows(2)
+            .all(|pair| pair[1].best_residual <= pair[0].best_residual));
+        assert_eq!(infos.last().unwrap().best_residual, result.residual);
+    }
+
+    #[rstest]
+    fn test_correspondences(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let rimage1 = sample_range_img_ds2.get(1).unwrap();
+
+        let mut infos = Vec::new();
+        let mut icp = ImageIcp::new(
+            IcpParams {
+                max_iterations: 5,
+                ..Default::default()
+            },
+            &rimage0,
+        );
+        assert!(icp.align_with_result(&rimage1).correspondences.is_none());
+
+        icp.collect_correspondences = true;
+        *icp.on_iteration.get_mut() = Some(Box::new(|info| infos.push(info.clone())));
+        let result = icp.align_with_result(&rimage1);
+        drop(icp);
+
+        let correspondences = result.correspondences.unwrap();
+        let width = rimage1.width();
+        assert!(correspondences.iter().all(|(source, target, _)| {
+            rimage1.mask[(source / width, source % width)] != 0
+                && *target < rimage0.width() * rimage0.height()
+        }));
+
+        // The same correspondences as the best iteration's.
+        let best_info = infos
+            .into_iter()
+            .find(|info| info.residual == result.residual)
+            .unwrap();
+        assert_eq!(correspondences.len(), best_info.num_correspondences);
+    }
+
+    #[rstest]
+    fn test_out_of_range_projections(sample_range_img_ds2: TestRangeImageDataset) {
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let mut rimage1 = sample_range_img_ds2.get(1).unwrap();
+
+        // Points projecting far outside the image, at the camera center, and behind it.
+        let width = rimage1.width();
+        for (i, point) in rimage1.points.iter_mut().enumerate() {
+            match i % 7 {
+                0 => *point = Vector3::new(1000.0, -1000.0, 1.0),
+                1 => *point = Vector3::new(0.0, 0.0, 0.0),
+                2 if i % width < width / 2 => *point = Vector3::new(0.5, 0.5, -1.0),
+                _ => (),
+            }
+        }
+
+        for subpixel_points in [false, true] {
+            let result = ImageIcp::new(
+                IcpParams {
+                    max_iterations: 3,
+                    subpixel_points,
+                    ..Default::default()
+                },
+                &rimage0,
+            )
+            .align_with_result(&rimage1);
+            assert!(result.residual.is_finite());
+        }
+    }
+
+    #[rstest]
+    fn test_color_huber_delta(sample_range_img_ds2: TestRangeImageDataset) {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        let rimage0 = sample_range_img_ds2.get(0).unwrap();
+        let mut rimage1 = sample_range_img_ds2.get(1).unwrap();
+        let gt_transform = sample_range_img_ds2.get_ground_truth(1, 0);
+
+        // Specular-like highlights.
+        let mut rng = SmallRng::seed_from_u64(11);
+        for intensity in rimage1.intensities.as_mut().unwrap().iter_mut() {
+            if rng.gen_range(0.0..1.0) < 0.3 {
+                *intensity = 255;
+            }
+        }
+
+        // Accepts all color residuals, so only the robust kernel can handle the outliers.
+        let params = IcpParams {
+            max_iterations: 10,
+            color_weight: 1.0,
+            max_color_distance: 1.0,
+            ..Default::default()
+        };
+        let plain = ImageIcp::new(params, &rimage0).align(&rimage1);
+        let robust = ImageIcp::new(
+            IcpParams {
+                color_huber_delta: Some(0.05),
+                ..params
+            },
+            &rimage0,
+        )
+        .align(&rimage1);
+
+        let plain_angle = TransformMetrics::new(&plain, &gt_transform).angle.abs();
+        let robust_angle = TransformMetrics::new(&robust, &gt_transform).angle.abs();
+        assert!(robust_angle < 0.01, "{robust_angle}");
+        assert!(
+            robust_angle * 2.0 < plain_angle,
+            "{robust_angle} vs {plain_angle}"
+        );
+
+        let mut num_color_rejected = 0;
+        let mut icp = ImageIcp::new(
+            IcpParams {
+                max_iterations: 1,
+                ..Default::default()
+            },
+            &rimage0,
+        );
+        *icp.on_iteration.get_mut() = Some(Box::new(|info| {
+            num_color_rejected = info.num_color_rejected
+        }));
+        icp.align(&rimage1);
+        drop(icp);
+        assert!(num_color_rejected > 0);
+    }
 }
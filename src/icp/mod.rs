@@ -1,6 +1,8 @@
 mod icp_params;
-pub use icp_params::{IcpParams, MsIcpParams};
+pub use icp_params::{ColorInterpolation, IcpCost, IcpOptimizer, IcpParams, MsIcpParams};
 mod cost_function;
+mod icp_result;
+pub use icp_result::{IcpIterationCallback, IcpIterationInfo, IcpResult};
 mod pcl_icp;
 pub use pcl_icp::Icp;
 mod image_icp;
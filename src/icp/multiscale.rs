@@ -22,16 +22,23 @@ impl<'pyramid_lt> MultiscaleAlign<'pyramid_lt> {
     ///
     /// * Ok(MultiscaleAlign)
     /// * Err(Error(InvalidParameter)) if the number of levels in the target pyramid and the number
-    ///   of ICP parameters are equal.
+    ///   of ICP parameters are not equal, or if they're empty.
     pub fn new(
         params: MsIcpParams,
         target_pyramid: &'pyramid_lt Vec<RangeImage>,
     ) -> Result<Self, A3dError> {
-        if params.len() != target_pyramid.len() {
+        if params.is_empty() {
             return Err(A3dError::invalid_parameter(
-                "The number of range images pyramid levels and ICP parameters must be equal.",
+                "The multiscale ICP parameters must have at least one level.",
             ));
         }
+        if params.len() != target_pyramid.len() {
+            return Err(A3dError::invalid_parameter(format!(
+                "The number of range images pyramid levels ({}) and ICP parameters ({}) must be equal.",
+                target_pyramid.len(),
+                params.len()
+            )));
+        }
 
         Ok(Self {
             target_pyramid,
@@ -49,7 +56,27 @@ impl<'pyramid_lt> MultiscaleAlign<'pyramid_lt> {
     ///
     /// * The optimized transform.
     pub fn align(&self, source_pyramid: &[RangeImage]) -> Transform {
-        let mut optim_transform = Transform::eye();
+        self.align_with_initial(source_pyramid, &Transform::eye())
+    }
+
+    /// Aligns the source point cloud to the target point cloud starting from
+    /// an initial guess, e.g., a constant velocity motion prior. The guess is used
+    /// in the coarsest level and refined in the finer ones.
+    ///
+    /// # Arguments
+    ///
+    /// * source_pyramid: The source point cloud pyramid.
+    /// * initial: Initial transform from the source to the target.
+    ///
+    /// # Returns
+    ///
+    /// * The optimized transform.
+    pub fn align_with_initial(
+        &self,
+        source_pyramid: &[RangeImage],
+        initial: &Transform,
+    ) -> Transform {
+        let mut optim_transform = initial.clone();
 
         for (params, target, source) in izip!(
             self.params.iter(),
@@ -69,15 +96,25 @@ impl<'pyramid_lt> MultiscaleAlign<'pyramid_lt> {
 
 #[cfg(test)]
 mod tests {
-    use rstest::rstest;
+    use crate::icp::{IcpParams, MsIcpParams};
 
-    use crate::{
-        bilateral::BilateralFilter,
-        icp::{IcpParams, MsIcpParams},
-        range_image::RangeImageBuilder,
-        unit_test::{sample_rgbd_frame_dataset1, TestRgbdFrameDataset},
+    // Imports of the tests on the sample dataset, which needs `SlamTbDataset`.
+    #[cfg(feature = "serde")]
+    use {
+        super::MultiscaleAlign,
+        crate::{
+            bilateral::BilateralFilter,
+            io::dataset::RgbdDataset,
+            metrics::TransformMetrics,
+            range_image::RangeImageBuilder,
+            transform::Transform,
+            unit_test::{sample_rgbd_dataset1, sample_rgbd_frame_dataset1, TestRgbdFrameDataset},
+        },
+        nalgebra::{UnitQuaternion, Vector3},
+        rstest::rstest,
     };
 
+    #[cfg(feature = "serde")]
     #[rstest]
     fn test_align(sample_rgbd_frame_dataset1: TestRgbdFrameDataset) {
         let ri_builder = RangeImageBuilder::default()
@@ -94,4 +131,142 @@ mod tests {
         // Just test that it doesn't crash. Use integration tests for more thorough testing.
         let _ = align.align(&source);
     }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_new_validates_levels(sample_rgbd_frame_dataset1: TestRgbdFrameDataset) {
+        let target = RangeImageBuilder::default()
+            .pyramid_levels(3)
+            .build(sample_rgbd_frame_dataset1.get_item(0).unwrap());
+
+        assert!(
+            MultiscaleAlign::new(MsIcpParams::repeat(3, &IcpParams::default()), &target).is_ok()
+        );
+        assert!(
+            MultiscaleAlign::new(MsIcpParams::repeat(2, &IcpParams::default()), &target).is_err()
+        );
+        assert!(MultiscaleAlign::new(MsIcpParams::new(Vec::new()), &target).is_err());
+    }
+
+    #[test]
+    fn test_from_coarse_to_fine() {
+        let params = MsIcpParams::from_coarse_to_fine(vec![
+            IcpParams {
+                max_iterations: 30,
+                ..Default::default()
+            },
+            IcpParams {
+                max_iterations: 10,
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(params[0].max_iterations, 10);
+        assert_eq!(params[1].max_iterations, 30);
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_per_level_params(sample_rgbd_dataset1: impl RgbdDataset) {
+        let ri_builder = RangeImageBuilder::default()
+            .with_bilateral_filter(Some(BilateralFilter::default()))
+            .with_intensity(true)
+            .with_normals(true)
+            .pyramid_levels(3);
+        let target = ri_builder.build(sample_rgbd_dataset1.get(0).unwrap());
+        let source = ri_builder.build(sample_rgbd_dataset1.get(4).unwrap());
+        let gt_transform = sample_rgbd_dataset1
+            .trajectory()
+            .unwrap()
+            .get_relative_transform(4, 0)
+            .unwrap();
+
+        let uniform = MultiscaleAlign::new(
+            MsIcpParams::repeat(
+                3,
+                &IcpParams {
+                    max_iterations: 10,
+                    ..Default::default()
+                },
+            ),
+            &target,
+        )
+        .unwrap()
+        .align(&source);
+
+        let per_level = MultiscaleAlign::new(
+            // More iterations and larger distance thresholds in the coarse levels.
+            MsIcpParams::from_coarse_to_fine(vec![
+                IcpParams {
+                    max_iterations: 20,
+                    max_distance: 0.5,
+                    ..Default::default()
+                },
+                IcpParams {
+                    max_iterations: 15,
+                    max_distance: 0.3,
+                    ..Default::default()
+                },
+                IcpParams {
+                    max_iterations: 10,
+                    max_distance: 0.1,
+                    ..Default::default()
+                },
+            ]),
+            &target,
+        )
+        .unwrap()
+        .align(&source);
+
+        let uniform_error = TransformMetrics::new(&gt_transform, &uniform).total();
+        let per_level_error = TransformMetrics::new(&gt_transform, &per_level).total();
+        assert!(
+            per_level_error <= uniform_error + 1e-3,
+            "{per_level_error} vs {uniform_error}"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_align_with_initial(sample_rgbd_dataset1: impl RgbdDataset) {
+        let ri_builder = RangeImageBuilder::default()
+            .with_bilateral_filter(Some(BilateralFilter::default()))
+            .with_intensity(true)
+            .with_normals(true)
+            .pyramid_levels(3);
+        let target = ri_builder.build(sample_rgbd_dataset1.get(0).unwrap());
+        let mut source = ri_builder.build(sample_rgbd_dataset1.get(4).unwrap());
+
+        // Simulates a large inter-frame motion by moving the source points.
+        let motion = Transform::new(
+            &Vector3::new(0.15, 0.0, 0.05),
+            &UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.3).into_inner(),
+        );
+        for level in source.iter_mut() {
+            level
+                .points
+                .mapv_inplace(|point| motion.transform_vector(&point));
+        }
+        let gt_transform = &sample_rgbd_dataset1
+            .trajectory()
+            .unwrap()
+            .get_relative_transform(4, 0)
+            .unwrap()
+            * &motion.inverse();
+
+        let align = MultiscaleAlign::new(MsIcpParams::default(), &target).unwrap();
+        let from_identity = align.align(&source);
+
+        // A noisy prior, like a constant velocity model would give.
+        let prior = &gt_transform
+            * &Transform::new(
+                &Vector3::new(0.01, -0.01, 0.02),
+                &UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.02).into_inner(),
+            );
+        let from_prior = align.align_with_initial(&source, &prior);
+
+        let identity_error = TransformMetrics::new(&gt_transform, &from_identity).total();
+        let prior_error = TransformMetrics::new(&gt_transform, &from_prior).total();
+        assert!(identity_error > 0.1, "{identity_error}");
+        assert!(prior_error < 0.02, "{prior_error}");
+    }
 }
@@ -1,24 +1,75 @@
-use super::cost_function::PointPlaneDistance;
-use super::icp_params::IcpParams;
+use super::cost_function::{GeneralizedDistance, PointPlaneDistance};
+use super::icp_params::{IcpCost, IcpParams};
+use super::{IcpIterationCallback, IcpIterationInfo, IcpResult};
 use crate::{
+    error::A3dError,
     extra_math,
     kdtree::R3dTree,
     optim::GaussNewton,
     pointcloud::PointCloud,
     transform::{LieGroup, Transform},
 };
-use itertools::izip;
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
+use ndarray::{Array1, ArrayView1};
 use num::Float;
+use std::{borrow::Cow, cell::RefCell};
+
+/// Number of neighbors used to estimate the point covariances of Generalized-ICP.
+const COVARIANCE_NEIGHBORS: usize = 20;
+
+/// Covariance along the normal of the plane-regularized covariances.
+const PLANE_EPSILON: f32 = 1e-3;
 
 /// Standard Iterative Closest Point (ICP) algorithm for aligning two point clouds.
-/// This implementation uses the point-to-plane distance.
+/// This implementation uses the point-to-plane distance or the Generalized-ICP
+/// distance, see [`IcpCost`].
 pub struct Icp<'target> {
     // Parameters of the ICP algorithm.
     pub params: IcpParams,
     // Initial transformation to start the algorithm. Default is the identity.
     pub initial_transform: Transform,
+    // Called after each iteration, e.g., for logging or visualizing the convergence.
+    // It's in a `RefCell` so aligning doesn't need a mutable `Icp`.
+    pub on_iteration: RefCell<Option<IcpIterationCallback<'target>>>,
+    // Whether to return the correspondences in `IcpResult::correspondences`.
+    pub collect_correspondences: bool,
     target: &'target PointCloud,
     kdtree: R3dTree,
+    target_covariances: Option<Array1<Matrix3<f32>>>,
+}
+
+/// Computes the plane-regularized covariance of each point from its neighbors,
+/// as in Segal et al., Generalized-ICP, RSS 2009. The covariance is
+/// $U diag(\epsilon, 1, 1) U^t$, where $U$ are the eigenvectors of the neighborhood
+/// covariance, sorted by increasing eigenvalues.
+///
+/// # Arguments
+///
+/// * points - The points.
+/// * kdtree - Kd-tree of the same points.
+fn plane_covariances(points: &ArrayView1<Vector3<f32>>, kdtree: &R3dTree) -> Array1<Matrix3<f32>> {
+    points.map(|point| {
+        let neighbors = kdtree.k_nearest(point, COVARIANCE_NEIGHBORS);
+        let mean = neighbors
+            .iter()
+            .map(|(index, _)| points[*index])
+            .sum::<Vector3<f32>>()
+            / neighbors.len() as f32;
+        let covariance = neighbors
+            .iter()
+            .map(|(index, _)| {
+                let centered = points[*index] - mean;
+                centered * centered.transpose()
+            })
+            .sum::<Matrix3<f32>>()
+            / neighbors.len() as f32;
+
+        let eigen = SymmetricEigen::new(covariance);
+        let normal_index = eigen.eigenvalues.imin();
+        let mut values = Vector3::repeat(1.0);
+        values[normal_index] = PLANE_EPSILON;
+        eigen.eigenvectors * Matrix3::from_diagonal(&values) * eigen.eigenvectors.transpose()
+    })
 }
 
 impl<'target> Icp<'target> {
@@ -29,11 +80,19 @@ impl<'target> Icp<'target> {
     /// * params - Parameters of the ICP algorithm.
     /// * target - Target point cloud.
     pub fn new(params: IcpParams, target: &'target PointCloud) -> Self {
+        let kdtree = R3dTree::new(&target.points.view());
+        let target_covariances = match params.cost {
+            IcpCost::Generalized => Some(plane_covariances(&target.points.view(), &kdtree)),
+            IcpCost::PointToPlane => None,
+        };
         Self {
             params,
             initial_transform: Transform::eye(),
+            on_iteration: RefCell::new(None),
+            collect_correspondences: false,
             target,
-            kdtree: R3dTree::new(&target.points.view()),
+            kdtree,
+            target_covariances,
         }
     }
 
@@ -47,77 +106,300 @@ impl<'target> Icp<'target> {
     ///
     /// The transformation that aligns the source point cloud to the target point cloud.
     pub fn align(&self, source: &PointCloud) -> Transform {
-        let target_normals = self
-            .target
-            .normals
-            .as_ref()
-            .expect("Please, the target point cloud should have normals.");
-        let source_normals = source
-            .normals
-            .as_ref()
-            .expect("Please, the source point cloud should have normals.");
+        self.align_with_result(source).transform
+    }
+
+    /// Aligns the source point cloud to the target point cloud.
+    ///
+    /// # Arguments
+    ///
+    /// * source - Source point cloud.
+    ///
+    /// # Returns
+    ///
+    /// The alignment result, with the transformation, the residual and the pose information matrix.
+    ///
+    /// # Panics
+    ///
+    /// If the point clouds lack attributes required by the cost, see [`Self::try_align_with_result`].
+    pub fn align_with_result(&self, source: &PointCloud) -> IcpResult {
+        self.try_align_with_result(source)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Aligns the source point cloud to the target point cloud.
+    ///
+    /// # Arguments
+    ///
+    /// * source - Source point cloud.
+    ///
+    /// # Returns
+    ///
+    /// The alignment result, or an `A3dError::InvalidParameter` if the point clouds lack
+    /// attributes required by the cost, i.e., the point-to-plane cost needs the normals
+    /// of both point clouds.
+    pub fn try_align_with_result(&self, source: &PointCloud) -> Result<IcpResult, A3dError> {
+        self.align_impl(source, None)
+    }
+
+    /// Aligns the source point cloud to the target point cloud, weighting the correspondences
+    /// of each source point, e.g., by sensor confidence or distance falloff.
+    ///
+    /// # Arguments
+    ///
+    /// * source - Source point cloud.
+    /// * weights - Weight of each source point. The residual and Jacobian of its
+    ///   correspondences are scaled by the weight, so zero-weighted points are ignored.
+    ///
+    /// # Returns
+    ///
+    /// The alignment result.
+    ///
+    /// # Panics
+    ///
+    /// If the weights or the point clouds are invalid, see [`Self::try_align_weighted`].
+    pub fn align_weighted(&self, source: &PointCloud, weights: &[f32]) -> IcpResult {
+        self.try_align_weighted(source, weights)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Aligns the source point cloud to the target point cloud, weighting the correspondences
+    /// of each source point. See [`Self::align_weighted`].
+    ///
+    /// # Arguments
+    ///
+    /// * source - Source point cloud.
+    /// * weights - Weight of each source point.
+    ///
+    /// # Returns
+    ///
+    /// The alignment result, or an `A3dError::InvalidParameter` if there isn't one finite and
+    /// non-negative weight per source point, or the point clouds lack attributes required by
+    /// the cost, see [`Self::try_align_with_result`].
+    pub fn try_align_weighted(
+        &self,
+        source: &PointCloud,
+        weights: &[f32],
+    ) -> Result<IcpResult, A3dError> {
+        if weights.len() != source.len() {
+            return Err(A3dError::invalid_parameter(format!(
+                "Expected a weight for each of the {} source points. Got {} weights",
+                source.len(),
+                weights.len()
+            )));
+        }
+        if weights
+            .iter()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(A3dError::invalid_parameter(
+                "The weights must be finite and non-negative",
+            ));
+        }
+        self.align_impl(source, Some(weights))
+    }
+
+    fn align_impl(
+        &self,
+        source: &PointCloud,
+        weights: Option<&[f32]>,
+    ) -> Result<IcpResult, A3dError> {
+        let (target_normals, source_normals) = match self.params.cost {
+            IcpCost::PointToPlane => (
+                Some(self.target.normals.as_ref().ok_or_else(|| {
+                    A3dError::invalid_parameter(
+                        "The point-to-plane ICP needs the target point cloud's normals. \
+                         Compute them or use the Generalized-ICP cost.",
+                    )
+                })?),
+                Some(source.normals.as_ref().ok_or_else(|| {
+                    A3dError::invalid_parameter(
+                        "The point-to-plane ICP needs the source point cloud's normals. \
+                         Compute them or use the Generalized-ICP cost.",
+                    )
+                })?),
+            ),
+            // Normals are optional, but used for rejecting correspondences if available.
+            IcpCost::Generalized => (self.target.normals.as_ref(), source.normals.as_ref()),
+        };
+        let covariances = match self.params.cost {
+            IcpCost::Generalized => {
+                // Only recomputed if the cost was changed after construction.
+                let target_covariances = match self.target_covariances.as_ref() {
+                    Some(target_covariances) => Cow::Borrowed(target_covariances),
+                    None => Cow::Owned(plane_covariances(&self.target.points.view(), &self.kdtree)),
+                };
+                let source_covariances =
+                    plane_covariances(&source.points.view(), &R3dTree::new(&source.points.view()));
+                Some((target_covariances, source_covariances))
+            }
+            IcpCost::PointToPlane => None,
+        };
+
         let mut optim_transform = Transform::eye();
         let mut optimizer = GaussNewton::<6>::new();
         let geom_cost = PointPlaneDistance {};
+        let generalized_cost = GeneralizedDistance {};
 
         let max_distance_sqr = self.params.max_distance * self.params.max_distance;
 
-        let mut best_residual = Float::infinity();
-        let mut best_transform = optim_transform.clone();
-        for _ in 0..self.params.max_iterations {
-            for (source_point, source_normal) in izip!(source.points.iter(), source_normals.iter())
-            {
-                let source_point = optim_transform.transform_vector(source_point);
-                let source_normal = optim_transform.transform_normal(source_normal);
-
-                let (found_index, found_sqr_distance) = self.kdtree.nearest(&source_point);
-                if found_sqr_distance > max_distance_sqr {
-                    continue;
-                }
+        // Adds the residuals of the correspondences under `transform` into `optimizer`,
+        // returning their number. Pushes them into `correspondences` if given.
+        let add_correspondences =
+            |transform: &Transform,
+             optimizer: &mut GaussNewton<6>,
+             mut correspondences: Option<&mut Vec<(usize, usize, f32)>>| {
+                let mut num_correspondences = 0;
+                let rotation = transform.0.rotation.to_rotation_matrix().into_inner();
+                for (index, source_point) in source.points.iter().enumerate() {
+                    let weight = weights.map_or(1.0, |weights| weights[index]);
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    let source_point = transform.transform_vector(source_point);
 
-                let target_normal = target_normals[found_index];
+                    let (found_index, found_sqr_distance) = self.kdtree.nearest(&source_point);
+                    if found_sqr_distance > max_distance_sqr {
+                        continue;
+                    }
 
-                if extra_math::angle_between_normals(&source_normal, &target_normal)
-                    > self.params.max_normal_angle
-                {
-                    continue;
-                }
+                    if let (Some(source_normals), Some(target_normals)) =
+                        (source_normals, target_normals)
+                    {
+                        let source_normal = transform.transform_normal(&source_normals[index]);
+                        if extra_math::angle_between_normals(
+                            &source_normal,
+                            &target_normals[found_index],
+                        ) > self.params.max_normal_angle
+                        {
+                            continue;
+                        }
+                    }
 
-                let target_point = self.target.points[found_index];
+                    let target_point = self.target.points[found_index];
 
-                let (residual, jacobian) =
-                    geom_cost.jacobian(&source_point, &target_point, &target_normal);
+                    let residual = match (&covariances, target_normals) {
+                        (Some((target_covariances, source_covariances)), _) => {
+                            let combined_covariance = target_covariances[found_index]
+                                + rotation * source_covariances[index] * rotation.transpose();
+                            let weight_factor = combined_covariance
+                                .try_inverse()
+                                .and_then(|weight| weight.cholesky());
+                            let Some(weight_factor) = weight_factor else {
+                                continue;
+                            };
+                            let mut squared_residual = 0.0;
+                            for (residual, jacobian) in generalized_cost.jacobian(
+                                &source_point,
+                                &target_point,
+                                &weight_factor.l(),
+                            ) {
+                                let residual = residual * weight;
+                                optimizer.step(residual, &jacobian.map(|value| value * weight));
+                                squared_residual += residual * residual;
+                            }
+                            squared_residual.sqrt()
+                        }
+                        (None, Some(target_normals)) => {
+                            let (residual, jacobian) = geom_cost.jacobian(
+                                &source_point,
+                                &target_point,
+                                &target_normals[found_index],
+                            );
+                            let residual = residual * weight;
+                            optimizer.step(residual, &jacobian.map(|value| value * weight));
+                            residual
+                        }
+                        (None, None) => unreachable!(),
+                    };
+                    num_correspondences += 1;
+                    if let Some(correspondences) = correspondences.as_mut() {
+                        correspondences.push((index, found_index, residual));
+                    }
+                }
+                num_correspondences
+            };
 
-                optimizer.step(residual, &jacobian);
-            }
+        let mut best_residual = Float::infinity();
+        let mut best_transform = optim_transform.clone();
+        // Transform of the best iteration's correspondences, i.e., before its update.
+        let mut best_source_transform = optim_transform.clone();
+        let mut best_covariance = None;
+        for iteration in 0..self.params.max_iterations {
+            let source_transform = optim_transform.clone();
+            let num_correspondences = add_correspondences(&source_transform, &mut optimizer, None);
 
             let residual = optimizer.mean_squared_residual();
+            let covariance = optimizer.covariance();
             optimizer.weight(self.params.weight);
-            let update = optimizer.solve().unwrap();
-            optim_transform = &Transform::exp(&LieGroup::Se3(update)) * &optim_transform;
+            let update = optimizer.solve();
+            if let Some(update) = update {
+                optim_transform = &Transform::exp(&LieGroup::Se3(update)) * &optim_transform;
+            }
             optimizer.reset();
 
             if residual < best_residual {
                 best_residual = residual;
                 best_transform = optim_transform.clone();
+                best_source_transform = source_transform;
+                best_covariance = covariance;
+            }
+
+            if let Some(on_iteration) = self.on_iteration.borrow_mut().as_mut() {
+                on_iteration(&IcpIterationInfo {
+                    iteration,
+                    transform: optim_transform.clone(),
+                    residual,
+                    best_residual,
+                    num_correspondences,
+                    num_color_rejected: 0,
+                });
+            }
+
+            if update.is_none() {
+                // Degenerate system, e.g., all correspondences lie on a single plane.
+                break;
             }
         }
 
-        best_transform
+        // Found once, after converging, to not slow down the iterations.
+        let correspondences = self.collect_correspondences.then(|| {
+            let mut correspondences = Vec::new();
+            add_correspondences(
+                &best_source_transform,
+                &mut GaussNewton::new(),
+                Some(&mut correspondences),
+            );
+            correspondences
+        });
+
+        Ok(IcpResult {
+            transform: best_transform,
+            residual: best_residual,
+            information: best_covariance.and_then(|covariance| covariance.try_inverse()),
+            correspondences,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rstest::*;
+    use nalgebra::Vector3;
+    use ndarray::Array1;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
 
-    use crate::{
-        metrics::TransformMetrics,
-        unit_test::{sample_pcl_ds1, TestPclDataset},
+    use crate::metrics::TransformMetrics;
+
+    // Imports of the tests on the sample dataset, which needs `SlamTbDataset`.
+    #[cfg(feature = "serde")]
+    use {
+        crate::unit_test::{sample_pcl_ds1, TestPclDataset},
+        rstest::*,
     };
 
     /// Test the ICP algorithm.
+    #[cfg(feature = "serde")]
     #[rstest]
     fn test_icp(sample_pcl_ds1: TestPclDataset) {
         let target_pcl = sample_pcl_ds1.get(0);
@@ -134,4 +416,252 @@ mod tests {
         let gt_transform = sample_pcl_ds1.get_ground_truth(1, 0);
         assert!(TransformMetrics::new(&actual, &gt_transform).angle.abs() < 0.1);
     }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_missing_normals(sample_pcl_ds1: TestPclDataset) {
+        let target_pcl = sample_pcl_ds1.get(0);
+        let mut source_pcl = sample_pcl_ds1.get(1);
+        source_pcl.normals = None;
+
+        let result = Icp::new(IcpParams::default(), &target_pcl).try_align_with_result(&source_pcl);
+        match result {
+            Err(A3dError::InvalidParameter(message)) => {
+                assert!(
+                    message.contains("source point cloud's normals"),
+                    "{message}"
+                )
+            }
+            _ => panic!("Expected an error about the missing normals."),
+        }
+
+        let mut target_pcl = target_pcl;
+        target_pcl.normals = None;
+        let result = Icp::new(IcpParams::default(), &target_pcl).try_align_with_result(&source_pcl);
+        assert!(matches!(result, Err(A3dError::InvalidParameter(_))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_correspondences(sample_pcl_ds1: TestPclDataset) {
+        let target_pcl = sample_pcl_ds1.get(0);
+        let source_pcl = sample_pcl_ds1.get(1);
+
+        let mut icp = Icp::new(
+            IcpParams {
+                max_iterations: 5,
+                ..Default::default()
+            },
+            &target_pcl,
+        );
+        assert!(icp.align_with_result(&source_pcl).correspondences.is_none());
+
+        icp.collect_correspondences = true;
+        let result = icp.align_with_result(&source_pcl);
+        let correspondences = result.correspondences.unwrap();
+        assert!(!correspondences.is_empty());
+        assert!(correspondences
+            .iter()
+            .all(|(source, target, _)| *source < source_pcl.len() && *target < target_pcl.len()));
+
+        let mean_squared_residual = correspondences
+            .iter()
+            .map(|(_, _, residual)| residual * residual)
+            .sum::<f32>()
+            / correspondences.len() as f32;
+        assert!(
+            (mean_squared_residual - result.residual).abs() <= result.residual * 1e-3,
+            "{mean_squared_residual} != {}",
+            result.residual
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn test_on_iteration(sample_pcl_ds1: TestPclDataset) {
+        let target_pcl = sample_pcl_ds1.get(0);
+        let source_pcl = sample_pcl_ds1.get(1);
+
+        let mut infos = Vec::new();
+        let mut icp = Icp::new(
+            IcpParams {
+                max_iterations: 5,
+                ..Default::default()
+            },
+            &target_pcl,
+        );
+        *icp.on_iteration.get_mut() = Some(Box::new(|info| infos.push(info.clone())));
+        let result = icp.align_with_result(&source_pcl);
+        drop(icp);
+
+        assert_eq!(infos.len(), 5);
+        for (i, info) in infos.iter().enumerate() {
+            assert_eq!(info.iteration, i);
+            assert!(info.num_correspondences > 0);
+            assert!(info.best_residual <= info.residual);
+        }
+        assert!(infos
+            .windows(2)
+            .all(|pair| pair[1].best_residual <= pair[0].best_residual));
+        assert_eq!(infos.last().unwrap().best_residual, result.residual);
+    }
+
+    /// Creates a grid of points on the planes orthogonal to the given normals,
+    /// with a noise along the normal.
+    fn planes_point_cloud(normals: &[Vector3<f32>], noise: f32, seed: u64) -> PointCloud {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut points = Vec::new();
+        let mut point_normals = Vec::new();
+        for normal in normals {
+            let u_axis = if normal[0].abs() < 0.5 {
+                Vector3::x()
+            } else {
+                Vector3::y()
+            };
+            let v_axis = normal.cross(&u_axis);
+            for i in 0..20 {
+                for j in 0..20 {
+                    let noise = rng.gen_range(-noise..noise);
+                    points.push(
+                        u_axis * (0.05 * i as f32) + v_axis * (0.05 * j as f32) + normal * noise,
+                    );
+                    point_normals.push(*normal);
+                }
+            }
+        }
+
+        PointCloud {
+            points: Array1::from_vec(points),
+            normals: Some(Array1::from_vec(point_normals)),
+            colors: None,
+            confidences: None,
+        }
+    }
+
+    #[test]
+    fn test_information() {
+        let params = IcpParams {
+            max_iterations: 3,
+            ..Default::default()
+        };
+
+        let axes = [Vector3::x(), Vector3::y(), Vector3::z()];
+        let result = Icp::new(params, &planes_point_cloud(&axes, 0.001, 5))
+            .align_with_result(&planes_point_cloud(&axes, 0.001, 6));
+        let covariance = result.information.unwrap().try_inverse().unwrap();
+        assert!(covariance.trace() < 1e-4);
+
+        let result = Icp::new(params, &planes_point_cloud(&[Vector3::z()], 0.001, 5))
+            .align_with_result(&planes_point_cloud(&[Vector3::z()], 0.001, 6));
+        assert!(result.information.is_none());
+    }
+
+    #[test]
+    fn test_generalized_icp() {
+        let axes = [Vector3::x(), Vector3::y(), Vector3::z()];
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut noisy_normals = |mut pcl: PointCloud| {
+            // Emulates estimated normals.
+            pcl.normals.as_mut().unwrap().mapv_inplace(|normal| {
+                (normal
+                    + Vector3::new(
+                        rng.gen_range(-0.15..0.15),
+                        rng.gen_range(-0.15..0.15),
+                        rng.gen_range(-0.15..0.15),
+                    ))
+                .normalize()
+            });
+            pcl
+        };
+
+        let gt_transform = Transform::new(
+            &Vector3::new(0.02, -0.01, 0.03),
+            &nalgebra::UnitQuaternion::from_euler_angles(0.02, -0.03, 0.01).into_inner(),
+        );
+        let target = noisy_normals(planes_point_cloud(&axes, 0.01, 5));
+        let source = noisy_normals(&gt_transform.inverse() * &planes_point_cloud(&axes, 0.01, 6));
+
+        let params = IcpParams {
+            max_iterations: 15,
+            max_normal_angle: std::f32::consts::PI,
+            ..Default::default()
+        };
+        let point_to_plane = Icp::new(params, &target).align(&source);
+        let generalized = Icp::new(
+            IcpParams {
+                cost: IcpCost::Generalized,
+                ..params
+            },
+            &target,
+        )
+        .align(&source);
+
+        let point_to_plane_error = TransformMetrics::new(&point_to_plane, &gt_transform).total();
+        let generalized_error = TransformMetrics::new(&generalized, &gt_transform).total();
+        assert!(
+            generalized_error < point_to_plane_error,
+            "{generalized_error} vs {point_to_plane_error}"
+        );
+    }
+
+    #[test]
+    fn test_align_weighted() {
+        let axes = [Vector3::x(), Vector3::y(), Vector3::z()];
+        let gt_transform = Transform::new(
+            &Vector3::new(0.02, -0.01, 0.03),
+            &nalgebra::UnitQuaternion::from_euler_angles(0.02, -0.03, 0.01).into_inner(),
+        );
+        let target = planes_point_cloud(&axes, 0.001, 5);
+        let mut source = &gt_transform.inverse() * &planes_point_cloud(&axes, 0.001, 6);
+
+        // Every third point is displaced, biasing the alignment.
+        let is_outlier = |index: usize| index.is_multiple_of(3);
+        for (index, point) in source.points.iter_mut().enumerate() {
+            if is_outlier(index) {
+                *point += Vector3::new(0.04, 0.04, 0.04);
+            }
+        }
+        let weights = (0..source.len())
+            .map(|index| if is_outlier(index) { 0.0 } else { 1.0 })
+            .collect::<Vec<_>>();
+        let inliers = (0..source.len()).filter(|index| !is_outlier(*index));
+        let inlier_source = PointCloud {
+            points: inliers.clone().map(|index| source.points[index]).collect(),
+            normals: Some(
+                inliers
+                    .map(|index| source.normals.as_ref().unwrap()[index])
+                    .collect(),
+            ),
+            colors: None,
+            confidences: None,
+        };
+
+        let params = IcpParams {
+            max_iterations: 10,
+            ..Default::default()
+        };
+        let icp = Icp::new(params, &target);
+        let weighted = icp.align_weighted(&source, &weights).transform;
+        let inliers_only = icp.align(&inlier_source);
+        let unweighted = icp.align(&source);
+
+        assert!(TransformMetrics::new(&weighted, &inliers_only).total() < 1e-4);
+        let weighted_error = TransformMetrics::new(&weighted, &gt_transform).total();
+        let unweighted_error = TransformMetrics::new(&unweighted, &gt_transform).total();
+        assert!(
+            weighted_error < unweighted_error,
+            "{weighted_error} vs {unweighted_error}"
+        );
+
+        assert!(matches!(
+            icp.try_align_weighted(&source, &weights[1..]),
+            Err(A3dError::InvalidParameter(_))
+        ));
+        let mut negative_weights = weights.clone();
+        negative_weights[1] = -1.0;
+        assert!(matches!(
+            icp.try_align_weighted(&source, &negative_weights),
+            Err(A3dError::InvalidParameter(_))
+        ));
+    }
 }
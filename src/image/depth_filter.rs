@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use ndarray::{Array2, Zip};
+
+/// Temporal median filter for depth images. It keeps the latest frames and outputs
+/// the per-pixel median of their valid (non-zero) depths, reducing the flickering noise
+/// of structured-light sensors.
+///
+/// Frames are not registered, so it assumes small camera motion within the window.
+pub struct TemporalDepthFilter {
+    window_size: usize,
+    frames: VecDeque<Array2<u16>>,
+}
+
+impl TemporalDepthFilter {
+    /// Creates a new filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_size` - Number of frames used in the median, including the current one.
+    ///   Must be greater than zero.
+    pub fn new(window_size: usize) -> Self {
+        assert!(window_size > 0, "Window size must be greater than zero");
+        Self {
+            window_size,
+            frames: VecDeque::with_capacity(window_size),
+        }
+    }
+
+    /// Number of frames used in the median.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Discards the buffered frames, e.g., when the camera moves abruptly.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Adds a depth frame and filters it. If the frame size differs from the buffered
+    /// ones, the buffer is reset first.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - Depth image, zero values are treated as missing depth.
+    ///
+    /// # Returns
+    ///
+    /// * The per-pixel median of the valid depths in the latest frames. Zero where no
+    ///   frame has a valid depth. With an even number of valid depths, the lower one is used.
+    pub fn push(&mut self, depth: &Array2<u16>) -> Array2<u16> {
+        if self
+            .frames
+            .front()
+            .is_some_and(|frame| frame.dim() != depth.dim())
+        {
+            self.reset();
+        }
+        if self.frames.len() == self.window_size {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(depth.clone());
+
+        Zip::indexed(depth).par_map_collect(|index, _| {
+            let mut values = Vec::with_capacity(self.frames.len());
+            values.extend(
+                self.frames
+                    .iter()
+                    .map(|frame| frame[index])
+                    .filter(|value| *value > 0),
+            );
+            if values.is_empty() {
+                return 0;
+            }
+            let middle = (values.len() - 1) / 2;
+            *values.select_nth_unstable(middle).1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn count_errors(depth: &Array2<u16>, expected: &Array2<u16>) -> usize {
+        depth
+            .iter()
+            .zip(expected.iter())
+            .filter(|(value, expected)| value != expected)
+            .count()
+    }
+
+    #[test]
+    fn should_remove_salt_and_pepper_noise() {
+        let clean = Array2::from_shape_fn((48, 64), |(row, col)| 1000 + (row + col) as u16);
+        let mut rng = SmallRng::seed_from_u64(5);
+
+        let mut filter = TemporalDepthFilter::new(5);
+        let mut noisy_errors = 0;
+        let mut filtered = Array2::zeros((0, 0));
+        for _ in 0..5 {
+            let noisy = clean.map(|value| match rng.gen_range(0.0..1.0) {
+                x if x < 0.05 => 0,
+                x if x < 0.1 => u16::MAX,
+                _ => *value,
+            });
+            noisy_errors = count_errors(&noisy, &clean);
+            filtered = filter.push(&noisy);
+        }
+
+        let filtered_errors = count_errors(&filtered, &clean);
+        assert!(noisy_errors > 200, "{noisy_errors}");
+        assert!(
+            filtered_errors * 20 < noisy_errors,
+            "{filtered_errors} vs {noisy_errors}"
+        );
+    }
+
+    #[test]
+    fn should_reset_on_size_change() {
+        let mut filter = TemporalDepthFilter::new(3);
+        filter.push(&Array2::from_elem((4, 4), 10));
+        filter.push(&Array2::from_elem((4, 4), 20));
+        assert_eq!(filter.push(&Array2::from_elem((4, 4), 30))[(0, 0)], 20);
+
+        let filtered = filter.push(&Array2::from_elem((2, 2), 40));
+        assert_eq!(filtered, Array2::from_elem((2, 2), 40));
+    }
+}
@@ -71,12 +71,6 @@ impl IntoLumaArray<u8> for ArrayView3<'_, u8> {
     }
 }
 
-/// Converts a RGB value into luma/grayscale.
-pub fn rgb_to_luma(r: u8, g: u8, b: u8) -> f32 {
-    const DIV: f32 = 1.0 / 255.0;
-    (r as f32 * 0.3 + g as f32 * 0.59 + b as f32 * 0.11) * DIV
-}
-
 /// Converts a RGB value into luma/grayscale, `u8` version
 pub fn rgb_to_luma_u8(r: u8, g: u8, b: u8) -> u8 {
     (r as f32 * 0.3 + g as f32 * 0.59 + b as f32 * 0.11) as u8
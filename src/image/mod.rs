@@ -2,7 +2,10 @@ mod rgb;
 pub use rgb::{py_scale_down, IntoArray3, IntoImageRgb8, ToImageRgb8};
 
 mod luma;
-pub use luma::{rgb_to_luma, rgb_to_luma_u8, IntoLumaArray, IntoLumaImage};
+pub use luma::{rgb_to_luma_u8, IntoLumaArray, IntoLumaImage};
 
 mod rgbd_image;
 pub use rgbd_image::{RgbdFrame, RgbdImage};
+
+mod depth_filter;
+pub use depth_filter::TemporalDepthFilter;
@@ -3,6 +3,7 @@ use ndarray::{Array2, Array3};
 use crate::{
     bilateral::BilateralFilter,
     camera::{CameraIntrinsics, PinholeCamera},
+    error::A3dError,
     sampling::Downsample,
     transform::Transform,
 };
@@ -10,10 +11,15 @@ use crate::{
 use super::{py_scale_down, IntoImageRgb8};
 
 /// A convinence struct that holds a color image, a depth image and its depth scale.
+#[derive(Clone)]
 pub struct RgbdImage {
     pub color: Array3<u8>,
     pub depth: Array2<u16>,
     pub depth_scale: Option<f64>,
+    /// Offset added to the scaled depth, i.e., depth is `depth * depth_scale + depth_bias`.
+    pub depth_bias: f64,
+    /// Maximum valid scaled depth, farther readings are discarded. None for no limit.
+    pub depth_max: Option<f64>,
 }
 
 impl RgbdImage {
@@ -22,6 +28,8 @@ impl RgbdImage {
             color,
             depth,
             depth_scale: None,
+            depth_bias: 0.0,
+            depth_max: None,
         }
     }
 
@@ -30,9 +38,65 @@ impl RgbdImage {
             color,
             depth,
             depth_scale: Some(depth_scale),
+            depth_bias: 0.0,
+            depth_max: None,
         }
     }
 
+    /// Sets the offset added to the scaled depth.
+    pub fn with_depth_bias(mut self, depth_bias: f64) -> Self {
+        self.depth_bias = depth_bias;
+        self
+    }
+
+    /// Sets the maximum valid scaled depth.
+    pub fn with_depth_max(mut self, depth_max: Option<f64>) -> Self {
+        self.depth_max = depth_max;
+        self
+    }
+
+    /// Creates an image from raw buffers, like the ones from live camera drivers.
+    ///
+    /// # Arguments
+    ///
+    /// * `rgb` - Interleaved RGB buffer, row-major, with `width * height * 3` bytes.
+    /// * `depth` - Depth buffer, row-major, with `width * height` values.
+    /// * `width` - Image width.
+    /// * `height` - Image height.
+    /// * `depth_scale` - Scale to convert depth values to meters.
+    ///
+    /// # Returns
+    ///
+    /// The image, or an `A3dError::InvalidParameter` if the buffer sizes don't match the dimensions.
+    pub fn from_raw(
+        rgb: &[u8],
+        depth: &[u16],
+        width: usize,
+        height: usize,
+        depth_scale: f64,
+    ) -> Result<Self, A3dError> {
+        if rgb.len() != width * height * 3 {
+            return Err(A3dError::invalid_parameter(format!(
+                "RGB buffer has {} bytes, expected {} for a {width}x{height} image.",
+                rgb.len(),
+                width * height * 3
+            )));
+        }
+        if depth.len() != width * height {
+            return Err(A3dError::invalid_parameter(format!(
+                "Depth buffer has {} values, expected {} for a {width}x{height} image.",
+                depth.len(),
+                width * height
+            )));
+        }
+
+        Ok(Self::with_depth_scale(
+            Array3::from_shape_vec((height, width, 3), rgb.to_vec()).unwrap(),
+            Array2::from_shape_vec((height, width), depth.to_vec()).unwrap(),
+            depth_scale,
+        ))
+    }
+
     pub fn width(&self) -> usize {
         self.color.shape()[1]
     }
@@ -54,11 +118,14 @@ impl Downsample for RgbdImage {
             color: resized_color,
             depth: resized_depth,
             depth_scale: self.depth_scale,
+            depth_bias: self.depth_bias,
+            depth_max: self.depth_max,
         }
     }
 }
 
 /// A struct that holds a camera intrinsics, a camera pose and an RGB-D image. Used by RGBD dataset readers.
+#[derive(Clone)]
 pub struct RgbdFrame {
     /// The camera intrinsics.
     pub camera: CameraIntrinsics,
@@ -106,13 +173,50 @@ impl Downsample for RgbdFrame {
 
 #[cfg(test)]
 mod tests {
-    use rstest::rstest;
-
-    use crate::{
-        image::IntoImageRgb8, io::dataset::RgbdDataset, sampling::Downsample,
-        unit_test::sample_rgbd_dataset1,
+    use super::RgbdImage;
+    use crate::error::A3dError;
+
+    // Imports of the tests on the sample dataset, which needs `SlamTbDataset`.
+    #[cfg(feature = "serde")]
+    use {
+        crate::{
+            image::IntoImageRgb8, io::dataset::RgbdDataset, sampling::Downsample,
+            unit_test::sample_rgbd_dataset1,
+        },
+        rstest::rstest,
     };
 
+    #[test]
+    fn test_from_raw() {
+        let (width, height) = (4, 3);
+        let rgb = (0..width * height * 3).map(|i| i as u8).collect::<Vec<_>>();
+        let depth = (0..width * height)
+            .map(|i| i as u16 * 100)
+            .collect::<Vec<_>>();
+
+        let image = RgbdImage::from_raw(&rgb, &depth, width, height, 0.001).unwrap();
+        assert_eq!(image.width(), 4);
+        assert_eq!(image.height(), 3);
+        // Pixel at row 2, column 1.
+        let offset = 2 * width + 1;
+        assert_eq!(image.depth[(2, 1)], depth[offset]);
+        assert_eq!(
+            image.color.slice(ndarray::s![2, 1, ..]).to_vec(),
+            rgb[offset * 3..offset * 3 + 3]
+        );
+        assert_eq!(image.depth_scale, Some(0.001));
+
+        assert!(matches!(
+            RgbdImage::from_raw(&rgb[1..], &depth, width, height, 0.001),
+            Err(A3dError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            RgbdImage::from_raw(&rgb, &depth, width, height + 1, 0.001),
+            Err(A3dError::InvalidParameter(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
     #[rstest]
     fn test_downsample(sample_rgbd_dataset1: impl RgbdDataset) {
         let image = sample_rgbd_dataset1.get(0).unwrap().image;
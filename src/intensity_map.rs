@@ -1,5 +1,8 @@
-use super::image::rgb_to_luma;
-use ndarray::{s, Array2, ArrayView2, ArrayView3};
+use crate::{
+    error::A3dError,
+    image::{rgb_to_luma_u8, RgbdImage},
+};
+use ndarray::{s, Array2, ArrayView2, ArrayView3, Zip};
 use nshare::ToNdarray2;
 
 /// Stores a grayscale image with float and interpolation operations.
@@ -9,9 +12,6 @@ pub struct IntensityMap {
     shape: (usize, usize),
 }
 
-// The H is gradient divisor constant.
-const H: f32 = 0.005;
-const H_INV: f32 = 1.0 / H;
 const BORDER_SIZE: usize = 2;
 
 impl IntensityMap {
@@ -35,46 +35,41 @@ impl IntensityMap {
     /// * image: The image data to be converted in a intensity map.
     ///   Its values are divided by 255.0.
     pub fn fill(&mut self, image: &ArrayView2<u8>) {
-        let (in_height, in_width) = {
-            let dim = image.shape();
-            (dim[0], dim[1])
-        };
-
+        let (in_height, in_width) = image.dim();
         let (map_grid_height, map_grid_width) = self.map.dim();
 
-        if in_height >= map_grid_height && in_width >= map_grid_width {
+        if in_height + BORDER_SIZE > map_grid_height || in_width + BORDER_SIZE > map_grid_width {
             self.map = Array2::zeros((in_height + BORDER_SIZE, in_width + BORDER_SIZE));
         }
 
         self.shape = (in_height, in_width);
+        if in_height == 0 || in_width == 0 {
+            return;
+        }
 
         // Fills the image.
-        self.map
-            .slice_mut(s!(..in_height, ..in_width))
-            .iter_mut()
-            .zip(image.iter())
-            .for_each(|(dst, src)| {
+        Zip::from(self.map.slice_mut(s![..in_height, ..in_width]))
+            .and(image)
+            .par_for_each(|dst, src| {
                 *dst = *src as f32 / 255.0;
             });
 
-        // Fills the border X:
-        for col in 0..in_width - 1 {
-            let border = self.map[(in_height - 1, col)];
-            for k in 0..2 {
-                self.map[(in_height + k, col)] = border;
-            }
-        }
-
-        for row in 0..in_height - 1 {
-            let border = self.map[(row, in_width - 1)];
-            for k in 0..2 {
-                self.map[(row, in_width + k)] = border;
-            }
+        // Replicates the last column, then the last row including the corner.
+        let last_col = self.map.slice(s![..in_height, in_width - 1]).to_owned();
+        for k in 0..BORDER_SIZE {
+            self.map
+                .slice_mut(s![..in_height, in_width + k])
+                .assign(&last_col);
         }
 
-        let last_elem = image[(in_height - 1, in_width - 1)] as f32 / 255.0;
-        for k in 0..2 {
-            self.map[(in_height + k, in_width + k)] = last_elem;
+        let last_row = self
+            .map
+            .slice(s![in_height - 1, ..in_width + BORDER_SIZE])
+            .to_owned();
+        for k in 0..BORDER_SIZE {
+            self.map
+                .slice_mut(s![in_height + k, ..in_width + BORDER_SIZE])
+                .assign(&last_row);
         }
     }
 
@@ -91,49 +86,39 @@ impl IntensityMap {
         map
     }
 
-    /// Constructor to create a map filled with a RGBimage.
+    /// Constructor to create a map filled with the luma of a RGB image.
     /// See `fill`.
-    pub fn from_rgb_image(image: &ArrayView3<u8>) -> Self {
-        // TODO: remove unnecessary copies.
-
-        let luma = match image.dim() {
-            (width, height, 3) => {
-                let mut luma = Array2::<u8>::zeros((width, height));
-                for y in 0..height {
-                    for x in 0..width {
-                        let r = image[[x, y, 0]];
-                        let g = image[[x, y, 1]];
-                        let b = image[[x, y, 2]];
-                        luma[[x, y]] = (rgb_to_luma(r, g, b) * 255.0) as u8;
-                    }
-                }
-                luma
-
-                //let shape = image.shape();
-                //let color = image.view().into_shape((shape[1] * shape[2], 3)).unwrap();
-                //color
-                //    .axis_iter(Axis(0))
-                //    .map(|rgb| (rgb_to_luma(rgb[0], rgb[1], rgb[2]) * 255.0) as u8)
-                //    .collect::<Array1<u8>>()
-                //    .into_shape((shape[1], shape[2]))
-                //    .unwrap()
-            }
-            (3, width, height) => {
-                let mut luma = Array2::<u8>::zeros((width, height));
-                for y in 0..height {
-                    for x in 0..width {
-                        let r = image[[0, x, y]];
-                        let g = image[[1, x, y]];
-                        let b = image[[2, x, y]];
-                        luma[[x, y]] = (rgb_to_luma(r, g, b) * 255.0) as u8;
-                    }
-                }
-                luma
-            }
-            _ => panic!("Invalid image shape"),
-        };
+    ///
+    /// # Arguments
+    ///
+    /// * image: RGB image with shape (height, width, 3), like [`RgbdImage::color`].
+    ///
+    /// # Returns
+    ///
+    /// * The intensity map, or `A3dError::InvalidParameter` if the image doesn't have 3 channels.
+    pub fn from_rgb_image(image: &ArrayView3<u8>) -> Result<Self, A3dError> {
+        let (height, width, channels) = image.dim();
+        if channels != 3 {
+            return Err(A3dError::invalid_parameter(format!(
+                "Expected a RGB image with shape (height, width, 3). Got {:?}",
+                image.dim()
+            )));
+        }
 
-        Self::from_luma_image(&luma.view())
+        let luma = Array2::from_shape_fn((height, width), |(row, col)| {
+            rgb_to_luma_u8(
+                image[(row, col, 0)],
+                image[(row, col, 1)],
+                image[(row, col, 2)],
+            )
+        });
+        Ok(Self::from_luma_image(&luma.view()))
+    }
+
+    /// Constructor to create a map filled with the luma of a RGB-D image's color.
+    /// See [`Self::from_rgb_image`].
+    pub fn from_rgbd_image(image: &RgbdImage) -> Result<Self, A3dError> {
+        Self::from_rgb_image(&image.color.view())
     }
 
     /// Returns the intensity value with bilinear interpolation if
@@ -169,12 +154,17 @@ impl IntensityMap {
     }
 
     /// Returns the intensity value with bilinear interpolation if
-    /// u or v are not round numbers, and `u` and `v` numerical gradients.
+    /// u or v are not round numbers, and its gradient with respect to `u` and `v`.
+    ///
+    /// The gradient is the analytic derivative of the bilinear interpolation inside the
+    /// pixel cell containing `(u, v)`, i.e., the intensity change per pixel. It's positive
+    /// when the intensity increases towards the right (`u`) or the bottom (`v`). At integer
+    /// coordinates, it's the derivative of the cell at the right/bottom.
     ///
     /// # Arguments:
     ///
-    /// * `u`: The "x" coordinate. Range is [0..1].
-    /// * `v`: The "y" coordinate. Range is [0..1].
+    /// * `u`: The "x" coordinate in pixels. Range is [0..width].
+    /// * `v`: The "y" coordinate in pixels. Range is [0..height].
     ///
     /// # Returns:
     ///
@@ -200,14 +190,106 @@ impl IntensityMap {
 
         let value = u0_interp * (1.0 - v_frac) + u1_interp * v_frac;
 
-        let uh = self.bilinear(u + H, v);
-        let vh = self.bilinear(u, v + H);
+        let grad_u = (val10 - val00) * (1.0 - v_frac) + (val11 - val01) * v_frac;
+        let grad_v = u1_interp - u0_interp;
+
+        (value, grad_u, grad_v)
+    }
+
+    /// Returns the intensity value with bicubic interpolation using the Catmull-Rom
+    /// kernel over the 4x4 neighborhood of `(u, v)`. It's smoother than [`Self::bilinear`]
+    /// and still matches the image at integer coordinates. Pixels outside the image
+    /// repeat the border ones.
+    ///
+    /// # Arguments:
+    ///
+    /// * `u`: The "x" coordinate in pixels. Range is [0..width].
+    /// * `v`: The "y" coordinate in pixels. Range is [0..height].
+    ///
+    /// # Returns:
+    ///
+    /// Bicubic interpolated value.
+    pub fn bicubic(&self, u: f32, v: f32) -> f32 {
+        let (ui, u_frac) = (u as usize, u.fract());
+        let (vi, v_frac) = (v as usize, v.fract());
+        let u_weights = catmull_rom_weights(u_frac);
+        let v_weights = catmull_rom_weights(v_frac);
+
+        (0..4)
+            .map(|j| v_weights[j] * self.row_dot(vi + j, ui, &u_weights))
+            .sum()
+    }
 
-        let grad_u = (uh - value) * H_INV;
-        let grad_v = (vh - value) * H_INV;
+    /// Returns the intensity value with bicubic interpolation and its analytic gradient
+    /// with respect to `u` and `v`. See [`Self::bicubic`] and [`Self::bilinear_grad`]
+    /// for the conventions.
+    ///
+    /// # Arguments:
+    ///
+    /// * `u`: The "x" coordinate in pixels. Range is [0..width].
+    /// * `v`: The "y" coordinate in pixels. Range is [0..height].
+    ///
+    /// # Returns:
+    ///
+    /// * Bicubic interpolated value.
+    /// * `u`'s gradient.
+    /// * `v`'s gradient.
+    pub fn bicubic_grad(&self, u: f32, v: f32) -> (f32, f32, f32) {
+        let (ui, u_frac) = (u as usize, u.fract());
+        let (vi, v_frac) = (v as usize, v.fract());
+        let u_weights = catmull_rom_weights(u_frac);
+        let u_derivatives = catmull_rom_derivatives(u_frac);
+        let v_weights = catmull_rom_weights(v_frac);
+        let v_derivatives = catmull_rom_derivatives(v_frac);
+
+        let (mut value, mut grad_u, mut grad_v) = (0.0, 0.0, 0.0);
+        for j in 0..4 {
+            let row_value = self.row_dot(vi + j, ui, &u_weights);
+            value += v_weights[j] * row_value;
+            grad_v += v_derivatives[j] * row_value;
+            grad_u += v_weights[j] * self.row_dot(vi + j, ui, &u_derivatives);
+        }
 
         (value, grad_u, grad_v)
     }
+
+    /// Dot product between the 4 pixels around `ui` of a row and the given weights.
+    /// `row` and the columns are shifted by one, so `row = 0` and `ui = 0`
+    /// start at the pixel before the image. Out of image pixels repeat the border.
+    fn row_dot(&self, row: usize, ui: usize, weights: &[f32; 4]) -> f32 {
+        let (height, width) = self.shape;
+        let row = row.saturating_sub(1).min(height - 1);
+        (0..4)
+            .map(|i| weights[i] * self.map[(row, (ui + i).saturating_sub(1).min(width - 1))])
+            .sum()
+    }
+}
+
+/// Catmull-Rom interpolation weights of the pixels at offsets -1, 0, 1 and 2.
+///
+/// # Arguments
+///
+/// * `t`: Fractional position between the pixels at offsets 0 and 1.
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        -0.5 * t3 + t2 - 0.5 * t,
+        1.5 * t3 - 2.5 * t2 + 1.0,
+        -1.5 * t3 + 2.0 * t2 + 0.5 * t,
+        0.5 * t3 - 0.5 * t2,
+    ]
+}
+
+/// Derivatives of [`catmull_rom_weights`] with respect to `t`.
+fn catmull_rom_derivatives(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    [
+        -1.5 * t2 + 2.0 * t - 0.5,
+        4.5 * t2 - 5.0 * t,
+        -4.5 * t2 + 4.0 * t + 0.5,
+        1.5 * t2 - t,
+    ]
 }
 
 impl ToNdarray2 for IntensityMap {
@@ -219,11 +301,16 @@ impl ToNdarray2 for IntensityMap {
 
 #[cfg(test)]
 mod tests {
-    use ndarray::Array2;
+    use ndarray::{s, Array2, Array3};
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
     use rstest::rstest;
 
-    use super::IntensityMap;
-    use crate::unit_test::bloei_luma8;
+    use super::{IntensityMap, BORDER_SIZE};
+    use crate::{
+        error::A3dError,
+        image::{rgb_to_luma_u8, RgbdImage},
+        unit_test::{bloei_luma8, bloei_rgb},
+    };
 
     #[rstest]
     fn border_should_repeat(bloei_luma8: Array2<u8>) {
@@ -260,4 +347,154 @@ mod tests {
             assert_eq!(*img_value as f32 / 255.0, value);
         }
     }
+
+    #[rstest]
+    fn gradient_should_match_finite_differences(bloei_luma8: Array2<u8>) {
+        let map = IntensityMap::from_luma_image(&bloei_luma8.view());
+        let (height, width) = map.shape();
+        let mut rng = SmallRng::seed_from_u64(8);
+        const STEP: f32 = 0.01;
+
+        for _ in 0..1000 {
+            // Keeps the finite differences inside the same pixel cell, where the
+            // interpolation is differentiable.
+            let u = rng.gen_range(1..width - 2) as f32 + rng.gen_range(0.05..0.95);
+            let v = rng.gen_range(1..height - 2) as f32 + rng.gen_range(0.05..0.95);
+
+            let (value, du, dv) = map.bilinear_grad(u, v);
+            assert!((value - map.bilinear(u, v)).abs() < 1e-6);
+
+            let expected_du =
+                (map.bilinear(u + STEP, v) - map.bilinear(u - STEP, v)) / (2.0 * STEP);
+            let expected_dv =
+                (map.bilinear(u, v + STEP) - map.bilinear(u, v - STEP)) / (2.0 * STEP);
+            assert!(
+                (du - expected_du).abs() < 1e-3,
+                "{du} != {expected_du} at ({u}, {v})"
+            );
+            assert!(
+                (dv - expected_dv).abs() < 1e-3,
+                "{dv} != {expected_dv} at ({u}, {v})"
+            );
+        }
+    }
+
+    #[rstest]
+    fn bicubic_round_uv_should_match_image(bloei_luma8: Array2<u8>) {
+        let map = IntensityMap::from_luma_image(&bloei_luma8.view());
+        for ((y, x), img_value) in bloei_luma8.indexed_iter() {
+            let expected = *img_value as f32 / 255.0;
+            assert_eq!(map.bicubic(x as f32, y as f32), expected);
+            assert_eq!(map.bicubic_grad(x as f32, y as f32).0, expected);
+        }
+    }
+
+    #[test]
+    fn bicubic_should_be_smoother_than_bilinear() {
+        // Quadratic ramp along x, bilinear interpolation has kinks at every pixel.
+        let ramp = Array2::from_shape_fn((8, 16), |(_, x)| (x * x) as u8);
+        let map = IntensityMap::from_luma_image(&ramp.view());
+        const STEP: f32 = 0.1;
+
+        let max_second_difference = |interp: &dyn Fn(f32) -> f32| {
+            (20..130)
+                .map(|i| {
+                    let u = i as f32 * STEP;
+                    (interp(u + STEP) - 2.0 * interp(u) + interp(u - STEP)).abs()
+                })
+                .fold(0.0, f32::max)
+        };
+
+        let bilinear = max_second_difference(&|u| map.bilinear(u, 4.0));
+        let bicubic = max_second_difference(&|u| map.bicubic(u, 4.0));
+        assert!(bicubic * 5.0 < bilinear, "{bicubic} vs {bilinear}");
+    }
+
+    #[rstest]
+    fn bicubic_gradient_should_match_finite_differences(bloei_luma8: Array2<u8>) {
+        let map = IntensityMap::from_luma_image(&bloei_luma8.view());
+        let (height, width) = map.shape();
+        let mut rng = SmallRng::seed_from_u64(8);
+        const STEP: f32 = 0.01;
+
+        for _ in 0..1000 {
+            let u = rng.gen_range(0.05..(width - 1) as f32);
+            let v = rng.gen_range(0.05..(height - 1) as f32);
+
+            let (value, du, dv) = map.bicubic_grad(u, v);
+            assert!((value - map.bicubic(u, v)).abs() < 1e-5);
+
+            let expected_du = (map.bicubic(u + STEP, v) - map.bicubic(u - STEP, v)) / (2.0 * STEP);
+            let expected_dv = (map.bicubic(u, v + STEP) - map.bicubic(u, v - STEP)) / (2.0 * STEP);
+            assert!(
+                (du - expected_du).abs() < 5e-3,
+                "{du} != {expected_du} at ({u}, {v})"
+            );
+            assert!(
+                (dv - expected_dv).abs() < 5e-3,
+                "{dv} != {expected_dv} at ({u}, {v})"
+            );
+        }
+    }
+
+    #[rstest]
+    fn should_create_from_rgb_image(bloei_rgb: Array3<u8>) {
+        let map = IntensityMap::from_rgb_image(&bloei_rgb.view()).unwrap();
+        let (height, width, _) = bloei_rgb.dim();
+        assert_eq!(map.shape(), (height, width));
+
+        for ((row, col), luma) in map.map.slice(s![..height, ..width]).indexed_iter() {
+            let expected = rgb_to_luma_u8(
+                bloei_rgb[(row, col, 0)],
+                bloei_rgb[(row, col, 1)],
+                bloei_rgb[(row, col, 2)],
+            );
+            assert_eq!(*luma, expected as f32 / 255.0);
+        }
+
+        let rgbd_image = RgbdImage::new(bloei_rgb, Array2::zeros((height, width)));
+        assert_eq!(
+            IntensityMap::from_rgbd_image(&rgbd_image).unwrap().map,
+            map.map
+        );
+
+        assert!(matches!(
+            IntensityMap::from_rgb_image(&Array3::zeros((3, height, width)).view()),
+            Err(A3dError::InvalidParameter(_))
+        ));
+    }
+
+    #[rstest]
+    fn fill_should_copy_image_and_replicate_borders(bloei_luma8: Array2<u8>) {
+        let mut map = IntensityMap::zeros((10, 10));
+        map.fill(&bloei_luma8.view());
+        let (height, width) = bloei_luma8.dim();
+        assert_eq!(map.shape(), (height, width));
+
+        for ((row, col), value) in map.map.slice(s![..height, ..width]).indexed_iter() {
+            assert_eq!(*value, bloei_luma8[(row, col)] as f32 / 255.0);
+        }
+
+        for k in 0..BORDER_SIZE {
+            for row in 0..height {
+                assert_eq!(map.map[(row, width + k)], map.map[(row, width - 1)]);
+            }
+            for col in 0..width + BORDER_SIZE {
+                assert_eq!(
+                    map.map[(height + k, col)],
+                    map.map[(height - 1, col.min(width - 1))]
+                );
+            }
+        }
+
+        // Refills with a taller image, but narrower.
+        let image = bloei_luma8.slice(s![.., ..width / 2]).to_owned();
+        let image = ndarray::concatenate![ndarray::Axis(0), image, image];
+        map.fill(&image.view());
+        assert_eq!(map.shape(), (height * 2, width / 2));
+        assert_eq!(
+            map.bilinear((width / 2 - 1) as f32 + 0.5, (height * 2 - 1) as f32 + 0.5),
+            image[(height * 2 - 1, width / 2 - 1)] as f32 / 255.0
+        );
+    }
 }
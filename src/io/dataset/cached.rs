@@ -0,0 +1,199 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use crate::{
+    camera::CameraIntrinsics, image::RgbdFrame, trajectory::Trajectory, transform::Transform,
+};
+
+use super::{DatasetError, RgbdDataset};
+
+/// Least recently used frames, with their indices.
+struct FrameCache {
+    frames: HashMap<usize, RgbdFrame>,
+    /// Indices from the least to the most recently used.
+    usage: VecDeque<usize>,
+}
+
+impl FrameCache {
+    /// Marks the frame as the most recently used.
+    fn touch(&mut self, index: usize) {
+        self.usage.retain(|used| *used != index);
+        self.usage.push_back(index);
+    }
+}
+
+/// Dataset decorator that keeps the most recently read frames in memory, so
+/// reading them again doesn't need to decode their images.
+///
+/// It's `Send` and `Sync`, so it can be shared between threads or read ahead by a
+/// [`super::DatasetPrefetcher`].
+pub struct CachedDataset {
+    dataset: Box<dyn RgbdDataset + Send + Sync>,
+    capacity: usize,
+    cache: Mutex<FrameCache>,
+}
+
+impl CachedDataset {
+    /// Creates a new cached dataset.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - The base dataset.
+    /// * `capacity` - Maximum number of frames in memory. When full, the least recently used frame is evicted.
+    pub fn new(dataset: Box<dyn RgbdDataset + Send + Sync>, capacity: usize) -> Self {
+        Self {
+            dataset,
+            capacity,
+            cache: Mutex::new(FrameCache {
+                frames: HashMap::new(),
+                usage: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Number of frames in memory.
+    pub fn cached_len(&self) -> usize {
+        self.cache.lock().unwrap().frames.len()
+    }
+}
+
+impl RgbdDataset for CachedDataset {
+    fn len(&self) -> usize {
+        self.dataset.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dataset.is_empty()
+    }
+
+    fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(frame) = cache.frames.get(&index).cloned() {
+                cache.touch(index);
+                return Ok(frame);
+            }
+        }
+
+        // Decodes without holding the lock, so other threads can read cached frames.
+        let frame = self.dataset.get(index)?;
+        if self.capacity > 0 {
+            let mut cache = self.cache.lock().unwrap();
+            if !cache.frames.contains_key(&index) {
+                if cache.frames.len() == self.capacity {
+                    let evicted = cache.usage.pop_front().unwrap();
+                    cache.frames.remove(&evicted);
+                }
+                cache.frames.insert(index, frame.clone());
+            }
+            cache.touch(index);
+        }
+        Ok(frame)
+    }
+
+    fn trajectory(&self) -> Option<Trajectory> {
+        self.dataset.trajectory()
+    }
+
+    fn camera(&self, index: usize) -> (CameraIntrinsics, Option<Transform>) {
+        self.dataset.camera(index)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::CachedDataset;
+    use crate::{
+        camera::CameraIntrinsics,
+        image::RgbdFrame,
+        io::dataset::{DatasetError, DatasetPrefetcher, RgbdDataset, SlamTbDataset},
+        trajectory::Trajectory,
+        transform::Transform,
+    };
+
+    /// Counts the frames read from the base dataset.
+    struct CountingDataset {
+        dataset: SlamTbDataset,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl RgbdDataset for CountingDataset {
+        fn len(&self) -> usize {
+            self.dataset.len()
+        }
+
+        fn is_empty(&self) -> bool {
+            self.dataset.is_empty()
+        }
+
+        fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.dataset.get(index)
+        }
+
+        fn trajectory(&self) -> Option<Trajectory> {
+            self.dataset.trajectory()
+        }
+
+        fn camera(&self, index: usize) -> (CameraIntrinsics, Option<Transform>) {
+            self.dataset.camera(index)
+        }
+    }
+
+    #[test]
+    fn test_cache() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let dataset = CachedDataset::new(
+            Box::new(CountingDataset {
+                dataset: SlamTbDataset::load("tests/data/rgbd/sample1").unwrap(),
+                reads: reads.clone(),
+            }),
+            2,
+        );
+
+        let first = dataset.get(0).unwrap();
+        let second = dataset.get(0).unwrap();
+        assert_eq!(reads.load(Ordering::Relaxed), 1);
+        assert_eq!(first.image.color, second.image.color);
+        assert_eq!(first.image.depth, second.image.depth);
+
+        dataset.get(1).unwrap();
+        // Makes 1 the least recently used.
+        dataset.get(0).unwrap();
+        dataset.get(2).unwrap();
+        assert_eq!(reads.load(Ordering::Relaxed), 3);
+        assert_eq!(dataset.cached_len(), 2);
+
+        dataset.get(0).unwrap();
+        assert_eq!(reads.load(Ordering::Relaxed), 3);
+        dataset.get(1).unwrap();
+        assert_eq!(reads.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let dataset = CachedDataset::new(
+            Box::new(CountingDataset {
+                dataset: SlamTbDataset::load("tests/data/rgbd/sample1").unwrap(),
+                reads: reads.clone(),
+            }),
+            2,
+        );
+        let len = dataset.len();
+
+        let frames = DatasetPrefetcher::new(dataset, 2)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), len);
+        assert_eq!(reads.load(Ordering::Relaxed), len);
+    }
+}
@@ -1,7 +1,8 @@
 use image::ImageError;
 
 use crate::{
-    camera::CameraIntrinsics, image::RgbdFrame, trajectory::Trajectory, transform::Transform,
+    camera::CameraIntrinsics, error::A3dError, image::RgbdFrame, trajectory::Trajectory,
+    transform::Transform,
 };
 use std::io::Error;
 
@@ -10,6 +11,22 @@ pub enum DatasetError {
     Io(Error),
     Parser(String),
     Image(ImageError),
+    /// Frame index out of the dataset's range.
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+}
+
+impl DatasetError {
+    /// Checks whether `index` is a valid frame index for a dataset with `len` frames.
+    pub(super) fn check_index(index: usize, len: usize) -> Result<(), DatasetError> {
+        if index < len {
+            Ok(())
+        } else {
+            Err(DatasetError::IndexOutOfRange { index, len })
+        }
+    }
 }
 
 impl From<Error> for DatasetError {
@@ -24,6 +41,7 @@ impl std::error::Error for DatasetError {
             DatasetError::Io(err) => Some(err),
             DatasetError::Parser(_) => None,
             DatasetError::Image(err) => Some(err),
+            DatasetError::IndexOutOfRange { .. } => None,
         }
     }
 }
@@ -34,6 +52,23 @@ impl std::fmt::Display for DatasetError {
             DatasetError::Io(err) => write!(f, "IO error: {err}"),
             DatasetError::Parser(err) => write!(f, "Parser error: {err}"),
             DatasetError::Image(err) => write!(f, "Image error: {err}"),
+            DatasetError::IndexOutOfRange { index, len } => {
+                write!(
+                    f,
+                    "Index {index} out of range for a dataset of {len} frames"
+                )
+            }
+        }
+    }
+}
+
+impl From<DatasetError> for A3dError {
+    fn from(err: DatasetError) -> Self {
+        match err {
+            DatasetError::Io(err) => A3dError::Io(err),
+            DatasetError::Parser(err) => A3dError::Parser(err),
+            DatasetError::Image(err) => A3dError::Parser(err.to_string()),
+            DatasetError::IndexOutOfRange { .. } => A3dError::invalid_parameter(err),
         }
     }
 }
@@ -61,6 +96,47 @@ impl SubsetDataset {
     pub fn new(dataset: Box<dyn RgbdDataset>, indices: Vec<usize>) -> Self {
         Self { dataset, indices }
     }
+
+    /// Creates a subset with the frames in the range `start..end` of the dataset.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - The base dataset.
+    /// * `start` - The first frame index.
+    /// * `end` - The frame index after the last one.
+    pub fn range(dataset: Box<dyn RgbdDataset>, start: usize, end: usize) -> Self {
+        Self {
+            dataset,
+            indices: (start..end).collect(),
+        }
+    }
+
+    /// Creates a subset with every `step`-th frame in the range `start..end` of the dataset.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - The base dataset.
+    /// * `start` - The first frame index.
+    /// * `end` - The frame index after the last one.
+    /// * `step` - The step between the frames. Must be greater than zero.
+    ///
+    /// # Returns
+    ///
+    /// The subset, or an invalid parameter error if `step` is zero.
+    pub fn strided(
+        dataset: Box<dyn RgbdDataset>,
+        start: usize,
+        end: usize,
+        step: usize,
+    ) -> Result<Self, A3dError> {
+        if step == 0 {
+            return Err(A3dError::invalid_parameter(
+                "The subset step must be greater than zero.",
+            ));
+        }
+        let indices = (start..end).step_by(step).collect();
+        Ok(Self { dataset, indices })
+    }
 }
 
 unsafe impl Send for SubsetDataset {}
@@ -75,6 +151,7 @@ impl RgbdDataset for SubsetDataset {
     }
 
     fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+        DatasetError::check_index(index, self.len())?;
         self.dataset.get(self.indices[index])
     }
 
@@ -91,3 +168,46 @@ impl RgbdDataset for SubsetDataset {
         self.dataset.camera(self.indices[index])
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use rstest::*;
+
+    use super::{RgbdDataset, SubsetDataset};
+    use crate::{error::A3dError, io::dataset::SlamTbDataset};
+
+    #[fixture]
+    fn sample1() -> Box<dyn RgbdDataset> {
+        Box::new(SlamTbDataset::load("tests/data/rgbd/sample1").unwrap())
+    }
+
+    #[rstest]
+    fn test_strided(sample1: Box<dyn RgbdDataset>) {
+        let subset = SubsetDataset::strided(sample1, 0, 10, 2).unwrap();
+        assert_eq!(subset.indices, vec![0, 2, 4, 6, 8]);
+        assert_eq!(subset.len(), 5);
+
+        let base = SlamTbDataset::load("tests/data/rgbd/sample1").unwrap();
+        assert_eq!(
+            subset.get(3).unwrap().image.depth,
+            base.get(6).unwrap().image.depth
+        );
+        assert_eq!(subset.camera(4).1.unwrap().0, base.camera(8).1.unwrap().0);
+    }
+
+    #[rstest]
+    fn test_strided_zero_step(sample1: Box<dyn RgbdDataset>) {
+        assert!(matches!(
+            SubsetDataset::strided(sample1, 0, 10, 0),
+            Err(A3dError::InvalidParameter(_))
+        ));
+    }
+
+    #[rstest]
+    fn test_range(sample1: Box<dyn RgbdDataset>) {
+        let subset = SubsetDataset::range(sample1, 3, 7);
+        assert_eq!(subset.indices, vec![3, 4, 5, 6]);
+        assert_eq!(subset.len(), 4);
+        assert_eq!(subset.trajectory().unwrap().len(), 4);
+    }
+}
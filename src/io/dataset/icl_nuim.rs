@@ -0,0 +1,155 @@
+use std::{io::BufRead, path::PathBuf};
+
+use nshare::ToNdarray2;
+
+use crate::{
+    camera::CameraIntrinsics,
+    image::{IntoArray3, RgbdFrame, RgbdImage},
+    trajectory::Trajectory,
+    transform::Transform,
+};
+
+use super::{
+    tum::{associate, load_trajectory},
+    DatasetError, RgbdDataset,
+};
+
+/// Parser for the ICL-NUIM dataset, in its TUM RGB-D compatible format. Available at:
+/// https://www.doc.ic.ac.uk/~ahanda/VaFRIC/iclnuim.html.
+/// A. Handa, T. Whelan, J.B. McDonald and A.J. Davison,
+/// A Benchmark for RGB-D Visual Odometry, 3D Reconstruction and SLAM. ICRA, 2014.
+///
+/// The sequence folder must contain the `associations.txt` file, the `rgb` and `depth`
+/// folders, and the ground truth trajectory in a `*.freiburg` file.
+pub struct IclNuimDataset {
+    base_dir: PathBuf,
+    rgb_images: Vec<String>,
+    depth_images: Vec<String>,
+    trajectory: Trajectory,
+}
+
+/// Reads the `associations.txt` file, its lines are: `depth_timestamp depth_file rgb_timestamp rgb_file`.
+fn read_associations(filepath: &PathBuf) -> Result<Vec<(f64, String, String)>, DatasetError> {
+    let file = std::fs::File::open(filepath)?;
+    let reader = std::io::BufReader::new(file);
+    reader
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+        .map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens[..] {
+                [timestamp, depth_file, _, rgb_file] => Ok((
+                    timestamp.parse::<f64>().map_err(|_| {
+                        DatasetError::Parser(format!("Invalid timestamp. Got `{line}`"))
+                    })?,
+                    depth_file.to_string(),
+                    rgb_file.to_string(),
+                )),
+                _ => Err(DatasetError::Parser(format!(
+                    "Invalid association. Got `{line}`"
+                ))),
+            }
+        })
+        .collect()
+}
+
+impl IclNuimDataset {
+    pub fn load(base_dirpath: &str) -> Result<Self, DatasetError> {
+        let base_dir = PathBuf::from(base_dirpath);
+        let associations = read_associations(&base_dir.join("associations.txt"))?;
+
+        let trajectory_file = glob::glob(&format!("{base_dirpath}/*.freiburg"))?
+            .next()
+            .and_then(Result::ok)
+            .ok_or_else(|| {
+                DatasetError::Parser(format!("No *.freiburg trajectory file in {base_dirpath}"))
+            })?;
+        let trajectory = load_trajectory(trajectory_file.to_str().unwrap())?;
+
+        let depth_files = associations
+            .iter()
+            .map(|(timestamp, depth_file, rgb_file)| {
+                (*timestamp, (depth_file.clone(), rgb_file.clone()))
+            })
+            .collect::<Vec<_>>();
+        let depth_traj_assoc = associate(&depth_files, &trajectory, 0.02);
+
+        let rgb_images = depth_traj_assoc
+            .iter()
+            .map(|entry| entry.1 .1.clone())
+            .collect::<Vec<String>>();
+        let depth_images = depth_traj_assoc
+            .iter()
+            .map(|entry| entry.1 .0.clone())
+            .collect::<Vec<String>>();
+        let trajectory = depth_traj_assoc
+            .iter()
+            .map(|entry| (entry.3.clone(), entry.2 as f32))
+            .collect::<Trajectory>();
+
+        Ok(IclNuimDataset {
+            base_dir,
+            rgb_images,
+            depth_images,
+            trajectory,
+        })
+    }
+}
+
+impl RgbdDataset for IclNuimDataset {
+    fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+        let rgb_image = image::open(self.base_dir.join(&self.rgb_images[index]))?
+            .into_rgb8()
+            .into_array3();
+
+        let depth_image = image::open(self.base_dir.join(&self.depth_images[index]))?
+            .into_luma16()
+            .into_ndarray2();
+        let mut rgbd_image = RgbdImage::new(rgb_image, depth_image);
+        rgbd_image.depth_scale = Some(1.0 / 5000.0);
+
+        let (camera, transform) = self.camera(index);
+        Ok(RgbdFrame::new(camera, rgbd_image, transform))
+    }
+
+    fn len(&self) -> usize {
+        self.rgb_images.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn trajectory(&self) -> Option<Trajectory> {
+        Some(self.trajectory.clone())
+    }
+
+    fn camera(&self, index: usize) -> (CameraIntrinsics, Option<Transform>) {
+        let camera = CameraIntrinsics {
+            fx: 481.20,
+            fy: 480.0,
+            cx: 319.5,
+            cy: 239.5,
+            width: 640,
+            height: 480,
+            distortion: None,
+        };
+        (camera, Some(self.trajectory[index].clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[ignore]
+    #[test]
+    fn test_load() {
+        // Ignored: requires the ICL-NUIM dataset to be downloaded.
+        let dataset = IclNuimDataset::load("tests/data/living_room_traj0_frei_png").expect("
+        Please, link the folder data/living_room_traj0_frei_png to the corresponding in the ICL-NUIM dataset folder");
+        assert_eq!(dataset.len(), 1510);
+        let _item = dataset.get(0).unwrap();
+    }
+}
@@ -18,6 +18,10 @@ use super::core::{DatasetError, RgbdDataset};
 /// http://redwood-data.org/indoor_lidar_rgbd/index.html.
 /// Jaesik Park and Qian-Yi Zhou and Vladlen Koltun,
 /// Colored Point Cloud Registration Revisited. ICCV, 2017.
+///
+/// The ground truth poses of the `<sequence>.log` file are camera-to-world transforms, i.e.,
+/// they map points from the camera frame into the world frame. Their timestamps are the
+/// frame indices.
 pub struct IndoorLidarDataset {
     rgb_images: Vec<String>,
     depth_images: Vec<String>,
@@ -31,13 +35,20 @@ impl From<PatternError> for DatasetError {
 }
 
 impl IndoorLidarDataset {
+    /// Loads a sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dir` - The sequence directory, e.g., `bedroom`. It must contain the `image`
+    ///   and `depth` directories, and the `bedroom.log` ground truth trajectory.
+    ///
+    /// # Returns
+    ///
+    /// The dataset, or an error if the files are missing, the log is malformed, or it has
+    /// fewer poses than frames.
     pub fn load(base_dir: &str) -> Result<Self, DatasetError> {
-        let rgb_images: Vec<String> = glob::glob(&format!("{base_dir}/image/*.jpg"))?
-            .map(|x| x.unwrap().to_str().unwrap().to_string())
-            .collect();
-        let depth_images: Vec<String> = glob::glob(&format!("{base_dir}/depth/*.png"))?
-            .map(|x| x.unwrap().to_str().unwrap().to_string())
-            .collect();
+        let rgb_images = list_files(&format!("{base_dir}/image/*.jpg"))?;
+        let depth_images = list_files(&format!("{base_dir}/depth/*.png"))?;
 
         if rgb_images.len() != depth_images.len() {
             return Err(DatasetError::Parser(
@@ -45,36 +56,95 @@ impl IndoorLidarDataset {
             ));
         }
 
-        let log_filename = Path::new(base_dir).file_stem().unwrap().to_str().unwrap();
+        let log_filename = Path::new(base_dir)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| DatasetError::Parser(format!("Invalid dataset path `{base_dir}`")))?;
         let file = std::fs::File::open(format!("{base_dir}/{log_filename}.log"))?;
         let reader = std::io::BufReader::new(file);
-        let trajectory = reader
+        let lines = reader
             .lines()
             .map_ok(|line| line.trim().to_string())
-            .filter(|line| !line.as_ref().unwrap().is_empty())
-            .map(|line| line.unwrap())
+            .filter_ok(|line| !line.is_empty())
+            .collect::<Result<Vec<String>, _>>()?;
+        if lines.len() % 5 != 0 {
+            return Err(DatasetError::Parser(format!(
+                "Expected 5 lines per pose in the log file. Got {} lines",
+                lines.len()
+            )));
+        }
+
+        let trajectory = lines
             .chunks(5)
-            .into_iter()
             .enumerate()
             .map(|(n, lines)| {
                 let mut matrix = Matrix4::zeros();
-                let lines = lines.skip(1);
-                for (i, line) in lines.enumerate() {
-                    let iter = line.split_whitespace();
-                    for (j, token) in iter.enumerate() {
-                        matrix[(i, j)] = token.parse::<f32>().unwrap();
+                for (i, line) in lines[1..].iter().enumerate() {
+                    let tokens = line
+                        .split_whitespace()
+                        .map(|token| token.parse::<f32>())
+                        .collect::<Result<Vec<f32>, _>>()
+                        .map_err(|_| {
+                            DatasetError::Parser(format!("Invalid number. Got `{line}`"))
+                        })?;
+                    if tokens.len() != 4 {
+                        return Err(DatasetError::Parser(format!(
+                            "Expected 4 values per matrix row. Got `{line}`"
+                        )));
+                    }
+                    for (j, value) in tokens.into_iter().enumerate() {
+                        matrix[(i, j)] = value;
                     }
                 }
 
-                (Transform::from_matrix4(&matrix), n as f32)
+                Ok((Transform::from_matrix4(&matrix), n as f32))
             })
-            .collect::<Trajectory>();
+            .collect::<Result<Trajectory, DatasetError>>()?;
+        if trajectory.len() < rgb_images.len() {
+            return Err(DatasetError::Parser(format!(
+                "Expected a pose for each of the {} frames. Got {} poses",
+                rgb_images.len(),
+                trajectory.len()
+            )));
+        }
+
         Ok(IndoorLidarDataset {
             rgb_images,
             depth_images,
             trajectory,
         })
     }
+
+    /// Returns the ground truth relative transform between two frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_index` - Index of the source frame.
+    /// * `target_index` - Index of the target frame.
+    ///
+    /// # Returns
+    ///
+    /// The transform that maps points from the source camera frame into the target camera
+    /// frame, or `None` if an index is out of range.
+    pub fn get_ground_truth(&self, source_index: usize, target_index: usize) -> Option<Transform> {
+        if source_index.max(target_index) >= self.trajectory.len() {
+            return None;
+        }
+        self.trajectory
+            .get_relative_transform(source_index, target_index)
+    }
+}
+
+/// Lists the files matching a glob pattern.
+fn list_files(pattern: &str) -> Result<Vec<String>, DatasetError> {
+    glob::glob(pattern)?
+        .map(|entry| {
+            let path = entry.map_err(|err| DatasetError::Io(err.into_error()))?;
+            path.to_str()
+                .map(|path| path.to_string())
+                .ok_or_else(|| DatasetError::Parser(format!("Invalid file name {path:?}")))
+        })
+        .collect()
 }
 
 impl RgbdDataset for IndoorLidarDataset {
@@ -87,6 +157,7 @@ impl RgbdDataset for IndoorLidarDataset {
     }
 
     fn get(&self, idx: usize) -> Result<RgbdFrame, DatasetError> {
+        DatasetError::check_index(idx, self.len())?;
         let rgb_image = image::open(&self.rgb_images[idx])?
             .into_rgb8()
             .into_array3();
@@ -113,7 +184,111 @@ impl RgbdDataset for IndoorLidarDataset {
             cy: 239.5,
             width: 640,
             height: 480,
+            distortion: None,
         };
-        (camera, Some(self.trajectory[index].clone()))
+        (camera, self.trajectory.camera_to_world.get(index).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    fn write_sequence(base_dir: &str, log: &str) {
+        std::fs::create_dir_all(format!("{base_dir}/image")).unwrap();
+        std::fs::create_dir_all(format!("{base_dir}/depth")).unwrap();
+        let log_filename = Path::new(base_dir).file_stem().unwrap().to_str().unwrap();
+        std::fs::write(format!("{base_dir}/{log_filename}.log"), log).unwrap();
+    }
+
+    #[test]
+    fn test_load_malformed_log() {
+        let base_dir = "tests/outputs/indoor-lidar-malformed";
+        write_sequence(base_dir, "0 0 1\n1 0 0 0\n0 1 0 0\n0 0 1 0\n0 0 0 1\n");
+        let dataset = IndoorLidarDataset::load(base_dir).unwrap();
+        assert_eq!(dataset.trajectory.len(), 1);
+        assert!(matches!(
+            dataset.get(0),
+            Err(DatasetError::IndexOutOfRange { index: 0, len: 0 })
+        ));
+
+        write_sequence(base_dir, "0 0 1\n1 0 0 0\n0 1 0 0\n");
+        assert!(matches!(
+            IndoorLidarDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+
+        write_sequence(base_dir, "0 0 1\n1 0 0 0\n0 1 x 0\n0 0 1 0\n0 0 0 1\n");
+        assert!(matches!(
+            IndoorLidarDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+    }
+
+    #[test]
+    fn test_ground_truth() {
+        let base_dir = "tests/outputs/indoor-lidar-ground-truth";
+        write_sequence(
+            base_dir,
+            "0 0 1\n1 0 0 0\n0 1 0 0\n0 0 1 0\n0 0 0 1\n\
+             1 1 2\n0 -1 0 0.5\n1 0 0 0\n0 0 1 0.2\n0 0 0 1\n",
+        );
+        for i in 0..2 {
+            image::RgbImage::new(4, 3)
+                .save(format!("{base_dir}/image/{i:06}.jpg"))
+                .unwrap();
+            image::ImageBuffer::<image::Luma<u16>, _>::new(4, 3)
+                .save(format!("{base_dir}/depth/{i:06}.png"))
+                .unwrap();
+        }
+
+        let dataset = IndoorLidarDataset::load(base_dir).unwrap();
+        assert_eq!(dataset.len(), 2);
+        let trajectory = dataset.trajectory().unwrap();
+        assert_eq!(trajectory.len(), 2);
+
+        // The log poses are camera-to-world.
+        let (camera, camera_to_world) = dataset.camera(1);
+        assert_eq!((camera.width, camera.height), (640, 480));
+        assert_eq!(camera.fx, 525.0);
+        let camera_to_world = camera_to_world.unwrap();
+        let world_point = camera_to_world.transform_vector(&Vector3::new(1.0, 0.0, 0.0));
+        assert!((world_point - Vector3::new(0.5, 1.0, 0.2)).norm() < 1e-6);
+
+        let ground_truth = dataset.get_ground_truth(1, 0).unwrap();
+        let point = Vector3::new(0.1, 0.2, 0.3);
+        assert!(
+            (ground_truth.transform_vector(&point) - camera_to_world.transform_vector(&point))
+                .norm()
+                < 1e-6
+        );
+        assert!(dataset.get_ground_truth(0, 2).is_none());
+
+        // Fewer poses than frames.
+        write_sequence(base_dir, "0 0 1\n1 0 0 0\n0 1 0 0\n0 0 1 0\n0 0 0 1\n");
+        assert!(matches!(
+            IndoorLidarDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+    }
+
+    #[ignore]
+    #[test]
+    fn test_load_bedroom() {
+        let dataset = IndoorLidarDataset::load("tests/data/indoor_lidar/bedroom").unwrap();
+        assert!(!dataset.is_empty());
+        assert_eq!(dataset.trajectory().unwrap().len(), dataset.len());
+
+        let frame = dataset.get(0).unwrap();
+        assert_eq!(frame.image.depth.dim(), (480, 640));
+        assert!(frame.camera_to_world.is_some());
+
+        for i in 1..dataset.len().min(50) {
+            let relative = dataset.get_ground_truth(i, i - 1).unwrap();
+            assert!(relative.translation().norm() < 0.1, "frame {i}");
+            assert!(relative.angle() < 0.1, "frame {i}");
+        }
     }
 }
@@ -1,10 +1,21 @@
 mod core;
 pub use self::core::{DatasetError, RgbdDataset, SubsetDataset};
 
+mod cached;
+pub use cached::CachedDataset;
+
+mod prefetch;
+pub use prefetch::DatasetPrefetcher;
+
+mod icl_nuim;
+pub use icl_nuim::IclNuimDataset;
+
 mod indoor_lidar;
 pub use indoor_lidar::IndoorLidarDataset;
 
+#[cfg(feature = "serde")]
 mod slamtb;
+#[cfg(feature = "serde")]
 #[doc(hidden)]
 pub use slamtb::SlamTbDataset;
 
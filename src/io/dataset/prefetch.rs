@@ -0,0 +1,190 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::mpsc::{sync_channel, Receiver},
+    thread::{Builder, Result as ThreadResult},
+};
+
+use crate::image::RgbdFrame;
+
+use super::{DatasetError, RgbdDataset};
+
+/// Reads the frames of a dataset in a background thread, so disk IO and image
+/// decoding overlap with the processing of the previous frames.
+///
+/// It iterates over all frames in order. Up to `capacity` frames are read ahead of the
+/// consumer, and the thread waits until the consumer takes one before reading more, so
+/// memory use stays bounded.
+///
+/// # Example
+///
+/// ```no_run
+/// use align3d::io::dataset::{DatasetPrefetcher, IndoorLidarDataset};
+///
+/// let dataset = IndoorLidarDataset::load("tests/data/indoor_lidar/bedroom").unwrap();
+/// for frame in DatasetPrefetcher::new(dataset, 4).unwrap() {
+///     let frame = frame.unwrap();
+///     // Process the frame.
+/// }
+/// ```
+pub struct DatasetPrefetcher {
+    /// Frames read by the background thread, in order.
+    receiver: Receiver<ThreadResult<Result<RgbdFrame, DatasetError>>>,
+    remaining: usize,
+}
+
+impl DatasetPrefetcher {
+    /// Starts reading the frames of the dataset.
+    ///
+    /// # Arguments
+    ///
+    /// * `dataset` - The dataset, it's moved into the background thread.
+    /// * `capacity` - Maximum number of frames read ahead of the consumer. At least one
+    ///   frame is read ahead.
+    ///
+    /// # Returns
+    ///
+    /// The prefetcher, or an IO error if the background thread couldn't be spawned.
+    pub fn new<D>(dataset: D, capacity: usize) -> Result<Self, DatasetError>
+    where
+        D: RgbdDataset + Send + 'static,
+    {
+        let remaining = dataset.len();
+        let (sender, receiver) = sync_channel(capacity.max(1));
+        Builder::new()
+            .name("dataset-prefetch".to_string())
+            .spawn(move || {
+                for index in 0..dataset.len() {
+                    // Panics are sent to the consumer, which resumes them.
+                    let frame = catch_unwind(AssertUnwindSafe(|| dataset.get(index)));
+                    let panicked = frame.is_err();
+                    // Fails if the prefetcher was dropped.
+                    if sender.send(frame).is_err() || panicked {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            receiver,
+            remaining,
+        })
+    }
+}
+
+impl Iterator for DatasetPrefetcher {
+    type Item = Result<RgbdFrame, DatasetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Disconnected once the thread has sent all frames.
+        match self.receiver.recv().ok()? {
+            Ok(frame) => {
+                self.remaining -= 1;
+                Some(frame)
+            }
+            Err(panic) => {
+                self.remaining = 0;
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for DatasetPrefetcher {}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::{cell::Cell, thread::sleep, time::Duration};
+
+    use super::DatasetPrefetcher;
+    use crate::{
+        camera::CameraIntrinsics,
+        image::RgbdFrame,
+        io::dataset::{DatasetError, RgbdDataset, SlamTbDataset},
+        trajectory::Trajectory,
+        transform::Transform,
+    };
+
+    #[test]
+    fn test_prefetch() {
+        let dataset = SlamTbDataset::load("tests/data/rgbd/sample1").unwrap();
+        let prefetcher =
+            DatasetPrefetcher::new(SlamTbDataset::load("tests/data/rgbd/sample1").unwrap(), 4)
+                .unwrap();
+        assert_eq!(prefetcher.len(), dataset.len());
+
+        let mut count = 0;
+        for (index, frame) in prefetcher.enumerate() {
+            let frame = frame.unwrap();
+            let expected = dataset.get(index).unwrap();
+            assert_eq!(frame.image.color, expected.image.color);
+            assert_eq!(frame.image.depth, expected.image.depth);
+            assert_eq!(
+                frame.camera_to_world.unwrap().0,
+                expected.camera_to_world.unwrap().0
+            );
+            count += 1;
+        }
+        assert_eq!(count, dataset.len());
+    }
+
+    /// Reads the frames of `sample1`, taking longer for the first ones. It checks that
+    /// the frames are read in order through a `Cell`, so it isn't `Sync`.
+    struct SlowDataset(SlamTbDataset, Cell<usize>);
+
+    impl RgbdDataset for SlowDataset {
+        fn len(&self) -> usize {
+            8
+        }
+
+        fn is_empty(&self) -> bool {
+            false
+        }
+
+        fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+            assert_eq!(self.1.replace(index + 1), index);
+            sleep(Duration::from_millis(10 * (8 - index as u64)));
+            self.0.get(index)
+        }
+
+        fn trajectory(&self) -> Option<Trajectory> {
+            None
+        }
+
+        fn camera(&self, index: usize) -> (CameraIntrinsics, Option<Transform>) {
+            self.0.camera(index)
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let dataset = SlamTbDataset::load("tests/data/rgbd/sample1").unwrap();
+        let prefetcher = DatasetPrefetcher::new(
+            SlowDataset(
+                SlamTbDataset::load("tests/data/rgbd/sample1").unwrap(),
+                Cell::new(0),
+            ),
+            4,
+        )
+        .unwrap();
+        for (index, frame) in prefetcher.enumerate() {
+            assert_eq!(
+                frame.unwrap().image.depth,
+                dataset.get(index).unwrap().image.depth
+            );
+        }
+    }
+
+    #[test]
+    fn test_early_drop() {
+        let mut prefetcher =
+            DatasetPrefetcher::new(SlamTbDataset::load("tests/data/rgbd/sample1").unwrap(), 1)
+                .unwrap();
+        assert!(prefetcher.next().unwrap().is_ok());
+        // The thread must stop without blocking.
+        drop(prefetcher);
+    }
+}
@@ -16,6 +16,8 @@ pub struct SlamTbDataset {
     rgb_images: Vec<String>,
     depth_images: Vec<String>,
     depth_scales: Vec<f64>,
+    depth_biases: Vec<f64>,
+    depth_maxes: Vec<f64>,
     base_dir: PathBuf,
 }
 
@@ -24,8 +26,7 @@ mod json {
     #[derive(Deserialize, Debug)]
     pub struct KCam {
         pub matrix: Vec<Vec<f64>>,
-        #[serde(rename = "undist_coeff")]
-        pub _undist_coeff: Vec<f32>,
+        pub undist_coeff: Vec<f32>,
         pub image_size: (i32, i32),
     }
 
@@ -39,9 +40,9 @@ mod json {
         pub kcam: KCam,
         pub depth_scale: f64,
         pub depth_bias: f64,
+        /// Maximum depth, in raw depth units.
         pub depth_max: f64,
         pub rt_cam: RTCam,
-        pub timestamp: f64,
     }
 
     #[derive(Deserialize, Debug)]
@@ -62,53 +63,69 @@ impl SlamTbDataset {
         let buffer = std::io::BufReader::new(std::fs::File::open(
             Path::new(base_dir).join("frames.json"),
         )?);
-        serde_json::from_reader(buffer)
-            .map(|doc: json::Document| {
-                let mut cameras = Vec::new();
-                let mut extrinsic_cameras = Vec::new();
-                let mut rgb_images = Vec::new();
-                let mut depth_images = Vec::new();
-                let mut depth_scales = Vec::new();
-
-                for frame in doc.root.iter() {
-                    let info = &frame.info;
-
-                    let fx = info.kcam.matrix[0][0];
-                    let fy = info.kcam.matrix[1][1];
-                    let cx = info.kcam.matrix[0][2];
-                    let cy = info.kcam.matrix[1][2];
-
-                    let extrinsics = if info.rt_cam.matrix.len() == 4 {
-                        Transform::from_matrix4(&nalgebra::Matrix4::<f32>::from_fn(|r, c| {
-                            info.rt_cam.matrix[r][c]
-                        }))
-                    } else {
-                        Transform::eye()
-                    };
-
-                    cameras.push(CameraIntrinsics::from_simple_intrinsic(
-                        fx,
-                        fy,
-                        cx,
-                        cy,
-                        info.kcam.image_size.0 as usize,
-                        info.kcam.image_size.1 as usize,
-                    ));
-                    extrinsic_cameras.push(extrinsics);
-                    rgb_images.push(frame.rgb_image.clone());
-                    depth_images.push(frame.depth_image.clone());
-                    depth_scales.push(info.depth_scale);
-                }
-                Self {
-                    cameras,
-                    extrinsic_cameras,
-                    rgb_images,
-                    depth_images,
-                    depth_scales,
-                    base_dir: PathBuf::from(base_dir),
-                }
-            })
-            .map_err(|err| DatasetError::Parser(err.to_string()))
+        let doc: json::Document =
+            serde_json::from_reader(buffer).map_err(|err| DatasetError::Parser(err.to_string()))?;
+        let mut cameras = Vec::new();
+        let mut extrinsic_cameras = Vec::new();
+        let mut rgb_images = Vec::new();
+        let mut depth_images = Vec::new();
+        let mut depth_scales = Vec::new();
+        let mut depth_biases = Vec::new();
+        let mut depth_maxes = Vec::new();
+
+        for frame in doc.root.iter() {
+            let info = &frame.info;
+
+            if info.kcam.matrix.len() != 3 || info.kcam.matrix.iter().any(|row| row.len() != 3) {
+                return Err(DatasetError::Parser(format!(
+                    "Intrinsic matrix of {} must be 3x3",
+                    frame.depth_image
+                )));
+            }
+            let fx = info.kcam.matrix[0][0];
+            let fy = info.kcam.matrix[1][1];
+            let cx = info.kcam.matrix[0][2];
+            let cy = info.kcam.matrix[1][2];
+
+            let extrinsics = if info.rt_cam.matrix.len() == 4
+                && info.rt_cam.matrix.iter().all(|row| row.len() == 4)
+            {
+                Transform::from_matrix4(&nalgebra::Matrix4::<f32>::from_fn(|r, c| {
+                    info.rt_cam.matrix[r][c]
+                }))
+            } else {
+                Transform::eye()
+            };
+
+            let mut camera = CameraIntrinsics::from_simple_intrinsic(
+                fx,
+                fy,
+                cx,
+                cy,
+                info.kcam.image_size.0 as usize,
+                info.kcam.image_size.1 as usize,
+            );
+            if let Ok(distortion) = <[f32; 5]>::try_from(info.kcam.undist_coeff.as_slice()) {
+                camera = camera.with_distortion(distortion);
+            }
+            cameras.push(camera);
+            extrinsic_cameras.push(extrinsics);
+            rgb_images.push(frame.rgb_image.clone());
+            depth_images.push(frame.depth_image.clone());
+            depth_scales.push(info.depth_scale);
+            depth_biases.push(info.depth_bias);
+            depth_maxes.push(info.depth_max * info.depth_scale);
+        }
+        Ok(Self {
+            cameras,
+            extrinsic_cameras,
+            rgb_images,
+            depth_images,
+            depth_scales,
+            depth_biases,
+            depth_maxes,
+            base_dir: PathBuf::from(base_dir),
+        })
     }
 }
 
@@ -122,6 +139,7 @@ impl RgbdDataset for SlamTbDataset {
     }
 
     fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+        DatasetError::check_index(index, self.len())?;
         let rgb_image = image::open(self.base_dir.join(&self.rgb_images[index]))?
             .into_rgb8()
             .into_array3();
@@ -132,7 +150,9 @@ impl RgbdDataset for SlamTbDataset {
             .into_ndarray2();
         Ok(RgbdFrame::new(
             self.cameras[index].clone(),
-            RgbdImage::with_depth_scale(rgb_image, depth_image, self.depth_scales[index]),
+            RgbdImage::with_depth_scale(rgb_image, depth_image, self.depth_scales[index])
+                .with_depth_bias(self.depth_biases[index])
+                .with_depth_max(Some(self.depth_maxes[index])),
             Some(self.extrinsic_cameras[index].clone()),
         ))
     }
@@ -171,4 +191,31 @@ mod tests {
         assert_eq!(image.height(), 480);
         assert_eq!(image.width(), 640);
     }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(matches!(
+            SlamTbDataset::load("tests/outputs/slamtb-missing"),
+            Err(DatasetError::Io(_))
+        ));
+
+        let base_dir = "tests/outputs/slamtb-truncated";
+        std::fs::create_dir_all(base_dir).unwrap();
+        let frames = std::fs::read_to_string("tests/data/rgbd/sample1/frames.json").unwrap();
+        std::fs::write(
+            format!("{base_dir}/frames.json"),
+            &frames[..frames.len() / 2],
+        )
+        .unwrap();
+        assert!(matches!(
+            SlamTbDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+
+        let dataset = SlamTbDataset::load("tests/data/rgbd/sample1").unwrap();
+        assert!(matches!(
+            dataset.get(dataset.len()),
+            Err(DatasetError::IndexOutOfRange { .. })
+        ));
+    }
 }
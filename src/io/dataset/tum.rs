@@ -22,25 +22,45 @@ pub struct TumRgbdDataset {
 fn read_file_list(filepath: &PathBuf) -> Result<Vec<(f64, String)>, DatasetError> {
     let file = std::fs::File::open(filepath)?;
     let reader = std::io::BufReader::new(file);
-    let file_list: Vec<(f64, String)> = reader
+    reader
         .lines()
         .map_while(Result::ok)
-        .filter(|line| !line.trim().starts_with('#'))
+        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
         .map(|line| {
-            let tokens: Vec<&str> = line.split(&[',', '\t', ' ']).collect();
-            (
-                tokens[0].trim().parse::<f64>().unwrap(),
-                tokens[1].trim().to_string(),
-            )
+            let tokens: Vec<&str> = line
+                .split(&[',', '\t', ' '])
+                .filter(|token| !token.is_empty())
+                .collect();
+            match tokens[..] {
+                [timestamp, filename, ..] => Ok((
+                    timestamp.trim().parse::<f64>().map_err(|_| {
+                        DatasetError::Parser(format!("Invalid timestamp. Got `{line}`"))
+                    })?,
+                    filename.trim().to_string(),
+                )),
+                _ => Err(DatasetError::Parser(format!(
+                    "Invalid file list entry. Got `{line}`"
+                ))),
+            }
         })
-        .collect();
-
-    Ok(file_list)
+        .collect()
 }
 
-fn associate<T1: Clone, T2: Clone>(
+/// Associates the entries of two lists sorted by timestamp.
+///
+/// # Arguments
+///
+/// * `first_list` - List of (timestamp, value).
+/// * `second_list` - List of (timestamp, value).
+/// * `max_diff` - Maximum difference in seconds between the timestamps of associated entries.
+///
+/// # Returns
+///
+/// A list of (first timestamp, first value, second timestamp, second value).
+pub(super) fn associate<T1: Clone, T2: Clone>(
     first_list: &[(f64, T1)],
     second_list: &[(f64, T2)],
+    max_diff: f64,
 ) -> Vec<(f64, T1, f64, T2)> {
     let mut first_list = first_list.iter().peekable();
     let mut second_list = second_list.iter().peekable();
@@ -48,7 +68,7 @@ fn associate<T1: Clone, T2: Clone>(
     while let (Some((first_time, first_value)), Some((second_time, second_value))) =
         (first_list.peek(), second_list.peek())
     {
-        if (first_time - second_time).abs() < 0.02 {
+        if (first_time - second_time).abs() < max_diff {
             result.push((
                 *first_time,
                 first_value.clone(),
@@ -67,19 +87,25 @@ fn associate<T1: Clone, T2: Clone>(
     result
 }
 
-fn load_trajectory(filepath: &str) -> Result<Vec<(f64, Transform)>, DatasetError> {
+pub(super) fn load_trajectory(filepath: &str) -> Result<Vec<(f64, Transform)>, DatasetError> {
     let file = std::fs::File::open(filepath)?;
     let reader = std::io::BufReader::new(file);
-    let trajectory = reader
+    reader
         .lines()
         .map_while(Result::ok)
-        .filter(|line| !line.trim().starts_with('#'))
+        .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
         .map(|line| {
-            let tokens: Vec<f64> = line
+            let tokens = line
                 .split_whitespace()
-                .map(|token| token.trim().parse::<f64>().unwrap())
-                .collect();
-            (
+                .map(|token| token.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| DatasetError::Parser(format!("Invalid number. Got `{line}`")))?;
+            if tokens.len() != 8 {
+                return Err(DatasetError::Parser(format!(
+                    "Expected `timestamp tx ty tz qx qy qz qw`. Got `{line}`"
+                )));
+            }
+            Ok((
                 tokens[0],
                 Transform::new(
                     &Vector3::new(tokens[1] as f32, tokens[2] as f32, tokens[3] as f32),
@@ -90,18 +116,33 @@ fn load_trajectory(filepath: &str) -> Result<Vec<(f64, Transform)>, DatasetError
                         tokens[6] as f32,
                     ),
                 ),
-            )
+            ))
         })
-        .collect::<Vec<(f64, Transform)>>();
-
-    Ok(trajectory)
+        .collect()
 }
 
 impl TumRgbdDataset {
+    /// Loads a TUM RGB-D sequence, associating frames and poses whose timestamps differ
+    /// less than 0.02 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dirpath` - The sequence folder.
     pub fn load(base_dirpath: &str) -> Result<Self, DatasetError> {
+        Self::load_with_tolerance(base_dirpath, 0.02)
+    }
+
+    /// Loads a TUM RGB-D sequence with a custom timestamp association tolerance.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_dirpath` - The sequence folder.
+    /// * `max_diff` - Maximum difference in seconds between the timestamps of
+    ///   associated depth, RGB and pose entries.
+    pub fn load_with_tolerance(base_dirpath: &str, max_diff: f64) -> Result<Self, DatasetError> {
         let rgb_files = read_file_list(&PathBuf::from(base_dirpath).join("rgb.txt"))?;
         let depth_files = read_file_list(&PathBuf::from(base_dirpath).join("depth.txt"))?;
-        let depth_rgb_assoc = associate(&depth_files, &rgb_files);
+        let depth_rgb_assoc = associate(&depth_files, &rgb_files, max_diff);
         let rgb_images = depth_rgb_assoc
             .iter()
             .map(|entry| entry.3.clone())
@@ -111,14 +152,9 @@ impl TumRgbdDataset {
             .map(|entry| entry.1.clone())
             .collect::<Vec<String>>();
 
-        let trajectory = load_trajectory(
-            PathBuf::from(base_dirpath)
-                .join("groundtruth.txt")
-                .to_str()
-                .unwrap(),
-        )?;
+        let trajectory = load_trajectory(&format!("{base_dirpath}/groundtruth.txt"))?;
 
-        let depth_traj_assoc = associate(&depth_files, &trajectory);
+        let depth_traj_assoc = associate(&depth_files, &trajectory, max_diff);
 
         let trajectory = depth_traj_assoc
             .iter()
@@ -136,6 +172,7 @@ impl TumRgbdDataset {
 
 impl RgbdDataset for TumRgbdDataset {
     fn get(&self, index: usize) -> Result<RgbdFrame, DatasetError> {
+        DatasetError::check_index(index, self.len())?;
         let rgb_image = image::open(self.base_dir.join(&self.rgb_images[index]))?
             .into_rgb8()
             .into_array3();
@@ -170,6 +207,7 @@ impl RgbdDataset for TumRgbdDataset {
             cy: 239.5,
             width: 640,
             height: 480,
+            distortion: None,
         };
         (camera, Some(self.trajectory[index].clone()))
     }
@@ -188,4 +226,88 @@ mod test {
         assert_eq!(dataset.len(), 797);
         let _item = dataset.get(0).unwrap();
     }
+
+    #[test]
+    fn test_load_with_tolerance() {
+        let base_dir = "tests/outputs/tum-tolerance";
+        std::fs::create_dir_all(base_dir).unwrap();
+        std::fs::write(
+            format!("{base_dir}/depth.txt"),
+            "# depth maps\n1.00 depth/1.png\n2.00 depth/2.png\n3.00 depth/3.png\n",
+        )
+        .unwrap();
+        std::fs::write(
+            format!("{base_dir}/rgb.txt"),
+            "# color images\n1.01 rgb/1.png\n2.03 rgb/2.png\n3.04 rgb/3.png\n",
+        )
+        .unwrap();
+        std::fs::write(
+            format!("{base_dir}/groundtruth.txt"),
+            "# timestamp tx ty tz qx qy qz qw\n\
+             1.00 0 0 0 0 0 0 1\n\
+             2.00 1 0 0 0 0 0 1\n\
+             3.00 2 0 0 0 0 0 1\n",
+        )
+        .unwrap();
+
+        let dataset = TumRgbdDataset::load(base_dir).unwrap();
+        assert_eq!(dataset.len(), 1);
+
+        let dataset = TumRgbdDataset::load_with_tolerance(base_dir, 0.05).unwrap();
+        assert_eq!(dataset.len(), 3);
+        assert_eq!(
+            dataset.rgb_images,
+            vec!["rgb/1.png", "rgb/2.png", "rgb/3.png"]
+        );
+    }
+
+    fn write_sequence(base_dir: &str, rgb_list: &str) {
+        std::fs::create_dir_all(base_dir).unwrap();
+        std::fs::write(format!("{base_dir}/depth.txt"), "1.00 depth/1.png\n").unwrap();
+        std::fs::write(format!("{base_dir}/rgb.txt"), rgb_list).unwrap();
+        std::fs::write(
+            format!("{base_dir}/groundtruth.txt"),
+            "1.00 0 0 0 0 0 0 1\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_load_truncated_file_list() {
+        let base_dir = "tests/outputs/tum-truncated";
+        write_sequence(base_dir, "1.00 rgb/1.png\n2.00");
+        assert!(matches!(
+            TumRgbdDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+
+        write_sequence(base_dir, "1.00 rgb/1.png\n2.0x rgb/2.png\n");
+        assert!(matches!(
+            TumRgbdDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let base_dir = "tests/outputs/tum-errors";
+        write_sequence(base_dir, "1.00 rgb/1.png\n");
+        std::fs::write(format!("{base_dir}/groundtruth.txt"), "1.00 0 0 0 0 0\n").unwrap();
+        assert!(matches!(
+            TumRgbdDataset::load(base_dir),
+            Err(DatasetError::Parser(_))
+        ));
+
+        assert!(matches!(
+            TumRgbdDataset::load("tests/outputs/tum-missing"),
+            Err(DatasetError::Io(_))
+        ));
+
+        write_sequence(base_dir, "1.00 rgb/1.png\n");
+        let dataset = TumRgbdDataset::load(base_dir).unwrap();
+        assert!(matches!(
+            dataset.get(1),
+            Err(DatasetError::IndexOutOfRange { index: 1, len: 1 })
+        ));
+    }
 }
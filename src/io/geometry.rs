@@ -1,7 +1,10 @@
 use nalgebra::{Vector2, Vector3};
 use ndarray::prelude::*;
 
+use crate::extra_math;
+
 /// Generic representation of attributes found in 3D model/object/geometry files.
+#[derive(Clone)]
 pub struct Geometry {
     /// The 3D points. Shape is (Nx3).
     pub points: Array1<Vector3<f32>>,
@@ -9,6 +12,8 @@ pub struct Geometry {
     pub colors: Option<Array1<Vector3<u8>>>,
     /// Per vertices normals. Shape is (Nx3)
     pub normals: Option<Array1<Vector3<f32>>>,
+    /// Per vertices confidence (or quality) scalars.
+    pub confidences: Option<Array1<f32>>,
     /// The indices to connect vertices that make faces in the geometry.
     /// Shape is (Nx3), we always convert to triangles.
     pub faces: Option<Array2<usize>>,
@@ -26,6 +31,44 @@ impl Geometry {
     pub fn len_faces(&self) -> usize {
         self.faces.as_ref().map_or(0, |faces| faces.nrows())
     }
+
+    /// Returns the axis-aligned bounding box as the (min, max) corners.
+    /// None if the geometry has no vertices.
+    pub fn bounding_box(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        extra_math::bounding_box(self.points.iter())
+    }
+
+    /// Computes the vertex normals from the faces. Each vertex normal is the sum
+    /// of the normals of its adjacent faces weighted by their areas, renormalized to unit length.
+    /// Vertices without faces get zero normals. Does nothing if the geometry has no faces.
+    pub fn compute_vertex_normals(&mut self) {
+        let faces = match self.faces.as_ref() {
+            Some(faces) => faces,
+            None => return,
+        };
+
+        let mut normals = Array1::<Vector3<f32>>::zeros(self.points.len());
+        for face in faces.axis_iter(Axis(0)) {
+            let (p0, p1, p2) = (
+                self.points[face[0]],
+                self.points[face[1]],
+                self.points[face[2]],
+            );
+            // The cross product's norm is twice the triangle's area.
+            let face_normal = (p1 - p0).cross(&(p2 - p0));
+            for index in [face[0], face[1], face[2]] {
+                normals[index] += face_normal;
+            }
+        }
+
+        normals.iter_mut().for_each(|normal| {
+            let norm = normal.norm();
+            if norm > 0.0 {
+                *normal /= norm;
+            }
+        });
+        self.normals = Some(normals);
+    }
 }
 
 pub struct GeometryBuilder {
@@ -39,6 +82,7 @@ impl GeometryBuilder {
                 points,
                 colors: None,
                 normals: None,
+                confidences: None,
                 faces: None,
                 texcoords: None,
             },
@@ -60,6 +104,11 @@ impl GeometryBuilder {
         self
     }
 
+    pub fn with_confidences(mut self, confidences: Array1<f32>) -> Self {
+        self.geometry.confidences = Some(confidences);
+        self
+    }
+
     pub fn with_faces(mut self, faces: Array2<usize>) -> Self {
         self.geometry.faces = Some(faces);
         self
@@ -74,3 +123,46 @@ impl GeometryBuilder {
         self.geometry
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use rstest::rstest;
+
+    use crate::{
+        io::{read_off, Geometry},
+        unit_test::sample_cube_geometry,
+    };
+
+    #[test]
+    fn test_bounding_box() {
+        let geometry = read_off("tests/data/teapot.off").unwrap();
+        let (min, max) = geometry.bounding_box().unwrap();
+        for point in geometry.points.iter() {
+            assert!(point >= &min && point <= &max);
+        }
+        assert!(geometry.points.iter().any(|p| p[0] == min[0]));
+        assert!(geometry.points.iter().any(|p| p[2] == max[2]));
+        assert_eq!(
+            super::GeometryBuilder::new(ndarray::Array1::<Vector3<f32>>::zeros(0))
+                .build()
+                .bounding_box(),
+            None
+        );
+    }
+
+    #[rstest]
+    fn test_compute_vertex_normals(mut sample_cube_geometry: Geometry) {
+        sample_cube_geometry.compute_vertex_normals();
+
+        let normals = sample_cube_geometry.normals.as_ref().unwrap();
+        assert_eq!(normals.len(), sample_cube_geometry.len_vertices());
+        for (index, normal) in normals.iter().enumerate() {
+            // The cube's vertices are not shared between the sides, every 4 vertices
+            // form a side, ordered as +X, -X, +Y, -Y, +Z, -Z.
+            let side = index / 4;
+            let expected = Vector3::ith(side / 2, if side % 2 == 0 { 1.0 } else { -1.0 });
+            assert!((normal - expected).norm() < 1e-6, "{normal} != {expected}");
+        }
+    }
+}
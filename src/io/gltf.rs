@@ -0,0 +1,309 @@
+use nalgebra::Vector3;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::Geometry;
+
+/// GLB header magic, `glTF` in ASCII.
+const GLB_MAGIC: u32 = 0x4654_6C67;
+/// GLB chunk type of the JSON document, `JSON` in ASCII.
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+/// GLB chunk type of the binary buffer, `BIN\0` in ASCII.
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+/// glTF component types.
+const FLOAT: u32 = 5126;
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_INT: u32 = 5125;
+
+/// glTF buffer view targets.
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// glTF primitive modes.
+const MODE_POINTS: u32 = 0;
+const MODE_TRIANGLES: u32 = 4;
+
+/// Accumulates the binary buffer and its buffer views and accessors.
+struct GltfBuffer {
+    data: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GltfBuffer {
+    fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            buffer_views: Vec::new(),
+            accessors: Vec::new(),
+        }
+    }
+
+    /// Appends an attribute or index array.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Little endian data of the array.
+    /// * `target` - Buffer view's target.
+    /// * `accessor` - Accessor's description, without the buffer view.
+    ///
+    /// # Returns
+    ///
+    /// The index of the accessor.
+    fn push(&mut self, bytes: Vec<u8>, target: u32, mut accessor: Value) -> usize {
+        // Accessors' offsets must be aligned to their component size.
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.data.extend(bytes);
+
+        accessor["bufferView"] = json!(self.buffer_views.len() - 1);
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+/// Writes a GLB chunk, padding it to 4 bytes.
+fn write_chunk(
+    buf: &mut impl Write,
+    chunk_type: u32,
+    data: &[u8],
+    padding: u8,
+) -> Result<(), std::io::Error> {
+    let padding_len = data.len().next_multiple_of(4) - data.len();
+    buf.write_all(&((data.len() + padding_len) as u32).to_le_bytes())?;
+    buf.write_all(&chunk_type.to_le_bytes())?;
+    buf.write_all(data)?;
+    buf.write_all(&vec![padding; padding_len])
+}
+
+/// Writes a geometry into a binary glTF (`.glb`) file, as a single mesh with one primitive.
+/// Writes the points, normals, colors and faces. Geometries without faces are written
+/// as point primitives, so point clouds can be exported too. Zero normals, e.g., of
+/// vertices without faces, are written as +Z, since glTF requires unit normals.
+///
+/// # Arguments
+///
+/// * `filepath` - Path to the output file.
+/// * `geom` - The geometry to write.
+pub fn write_gltf<P>(filepath: P, geom: &Geometry) -> Result<(), std::io::Error>
+where
+    P: AsRef<Path>,
+{
+    let mut buffer = GltfBuffer::new();
+    let mut attributes = json!({});
+
+    let (min, max) = geom
+        .bounding_box()
+        .unwrap_or_else(|| (Vector3::zeros(), Vector3::zeros()));
+    attributes["POSITION"] = json!(buffer.push(
+        geom.points
+            .iter()
+            .flat_map(|p| p.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>())
+            .collect(),
+        ARRAY_BUFFER,
+        json!({
+            "componentType": FLOAT,
+            "count": geom.len_vertices(),
+            "type": "VEC3",
+            "min": [min[0], min[1], min[2]],
+            "max": [max[0], max[1], max[2]],
+        }),
+    ));
+
+    if let Some(normals) = &geom.normals {
+        // glTF requires unit normals, but vertices without faces have zero normals,
+        // see `Geometry::compute_vertex_normals`.
+        attributes["NORMAL"] = json!(buffer.push(
+            normals
+                .iter()
+                .map(|n| n.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::z))
+                .flat_map(|n| n.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>())
+                .collect(),
+            ARRAY_BUFFER,
+            json!({
+                "componentType": FLOAT,
+                "count": normals.len(),
+                "type": "VEC3",
+            }),
+        ));
+    }
+
+    if let Some(colors) = &geom.colors {
+        // RGBA, since vertex attributes must be aligned to 4 bytes.
+        attributes["COLOR_0"] = json!(buffer.push(
+            colors
+                .iter()
+                .flat_map(|c| [c[0], c[1], c[2], 255])
+                .collect(),
+            ARRAY_BUFFER,
+            json!({
+                "componentType": UNSIGNED_BYTE,
+                "normalized": true,
+                "count": colors.len(),
+                "type": "VEC4",
+            }),
+        ));
+    }
+
+    let mut primitive = json!({
+        "attributes": attributes,
+        "mode": MODE_POINTS,
+    });
+    if let Some(faces) = &geom.faces {
+        primitive["indices"] = json!(buffer.push(
+            faces
+                .iter()
+                .flat_map(|index| (*index as u32).to_le_bytes())
+                .collect(),
+            ELEMENT_ARRAY_BUFFER,
+            json!({
+                "componentType": UNSIGNED_INT,
+                "count": faces.len(),
+                "type": "SCALAR",
+            }),
+        ));
+        primitive["mode"] = json!(MODE_TRIANGLES);
+    }
+
+    let document = json!({
+        "asset": {"version": "2.0", "generator": "align3d"},
+        "scene": 0,
+        "scenes": [{"nodes": [0]}],
+        "nodes": [{"mesh": 0}],
+        "meshes": [{"primitives": [primitive]}],
+        "buffers": [{"byteLength": buffer.data.len()}],
+        "bufferViews": buffer.buffer_views,
+        "accessors": buffer.accessors,
+    });
+    let document = serde_json::to_vec(&document)?;
+
+    let total_len =
+        12 + 8 + document.len().next_multiple_of(4) + 8 + buffer.data.len().next_multiple_of(4);
+
+    let mut buf = BufWriter::new(File::create(filepath)?);
+    buf.write_all(&GLB_MAGIC.to_le_bytes())?;
+    buf.write_all(&2u32.to_le_bytes())?;
+    buf.write_all(&(total_len as u32).to_le_bytes())?;
+    write_chunk(&mut buf, CHUNK_JSON, &document, b' ')?;
+    write_chunk(&mut buf, CHUNK_BIN, &buffer.data, 0)?;
+    buf.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use ndarray::Array1;
+    use rstest::rstest;
+    use serde_json::Value;
+
+    use super::{write_gltf, CHUNK_BIN, CHUNK_JSON, GLB_MAGIC};
+    use crate::{io::Geometry, unit_test::sample_cube_geometry};
+
+    /// Reads the JSON document and the binary buffer of a GLB file, checking the binary chunk's size.
+    fn read_glb(filepath: &str) -> (Value, Vec<u8>) {
+        let bytes = std::fs::read(filepath).unwrap();
+        let read_u32 =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(read_u32(0), GLB_MAGIC);
+        assert_eq!(read_u32(4), 2);
+        assert_eq!(read_u32(8) as usize, bytes.len());
+
+        let json_len = read_u32(12) as usize;
+        assert_eq!(read_u32(16), CHUNK_JSON);
+        let document: Value = serde_json::from_slice(&bytes[20..20 + json_len]).unwrap();
+
+        let bin_offset = 20 + json_len;
+        assert_eq!(read_u32(bin_offset + 4), CHUNK_BIN);
+        let bin_len = read_u32(bin_offset) as usize;
+        assert!(bin_len as u64 >= document["buffers"][0]["byteLength"].as_u64().unwrap());
+        let bin = bytes[bin_offset + 8..bin_offset + 8 + bin_len].to_vec();
+        (document, bin)
+    }
+
+    #[rstest]
+    fn should_write_mesh(mut sample_cube_geometry: Geometry) {
+        sample_cube_geometry.compute_vertex_normals();
+        sample_cube_geometry.colors = Some(Array1::from_elem(
+            sample_cube_geometry.len_vertices(),
+            Vector3::new(255, 128, 0),
+        ));
+        write_gltf("tests/outputs/out-cube.glb", &sample_cube_geometry).unwrap();
+
+        let (document, _) = read_glb("tests/outputs/out-cube.glb");
+        let primitive = &document["meshes"][0]["primitives"][0];
+        let accessor_count = |index: &Value| {
+            document["accessors"][index.as_u64().unwrap() as usize]["count"]
+                .as_u64()
+                .unwrap() as usize
+        };
+
+        assert_eq!(primitive["mode"], 4);
+        for attribute in ["POSITION", "NORMAL", "COLOR_0"] {
+            assert_eq!(
+                accessor_count(&primitive["attributes"][attribute]),
+                sample_cube_geometry.len_vertices()
+            );
+        }
+        assert_eq!(
+            accessor_count(&primitive["indices"]),
+            sample_cube_geometry.len_faces() * 3
+        );
+    }
+
+    #[rstest]
+    fn should_write_point_cloud(mut sample_cube_geometry: Geometry) {
+        sample_cube_geometry.faces = None;
+        write_gltf("tests/outputs/out-cube-points.glb", &sample_cube_geometry).unwrap();
+
+        let (document, _) = read_glb("tests/outputs/out-cube-points.glb");
+        let primitive = &document["meshes"][0]["primitives"][0];
+        assert_eq!(primitive["mode"], 0);
+        assert!(primitive.get("indices").is_none());
+        assert!(primitive["attributes"].get("NORMAL").is_none());
+        assert_eq!(document["accessors"].as_array().unwrap().len(), 1);
+    }
+
+    #[rstest]
+    fn should_write_unit_normals(mut sample_cube_geometry: Geometry) {
+        // A vertex without faces.
+        let mut points = sample_cube_geometry.points.to_vec();
+        points.push(Vector3::new(2.0, 2.0, 2.0));
+        sample_cube_geometry.points = Array1::from_vec(points);
+        sample_cube_geometry.compute_vertex_normals();
+        write_gltf(
+            "tests/outputs/out-cube-loose-vertex.glb",
+            &sample_cube_geometry,
+        )
+        .unwrap();
+
+        let (document, bin) = read_glb("tests/outputs/out-cube-loose-vertex.glb");
+        let primitive = &document["meshes"][0]["primitives"][0];
+        let accessor =
+            &document["accessors"][primitive["attributes"]["NORMAL"].as_u64().unwrap() as usize];
+        let view = &document["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap() as usize;
+        let normals = bin[offset..offset + view["byteLength"].as_u64().unwrap() as usize]
+            .chunks_exact(12)
+            .map(|bytes| {
+                let component =
+                    |i: usize| f32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+                Vector3::new(component(0), component(1), component(2))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(normals.len(), sample_cube_geometry.len_vertices());
+        assert!(normals
+            .iter()
+            .all(|normal| (normal.norm() - 1.0).abs() < 1e-5));
+        assert_eq!(normals.last(), Some(&Vector3::z()));
+    }
+}
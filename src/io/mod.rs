@@ -1,10 +1,18 @@
 pub mod dataset;
 mod off;
 pub use off::read_off;
+mod obj;
+pub use obj::{read_obj, write_obj};
 mod geometry;
 
 pub use geometry::{Geometry, GeometryBuilder};
 mod error;
 pub use error::LoadError;
 mod ply;
-pub use ply::{read_ply, write_ply};
+pub use ply::{read_ply, write_ply, write_ply_with_encoding, PlyEncoding};
+mod stl;
+pub use stl::write_stl;
+#[cfg(feature = "gltf")]
+mod gltf;
+#[cfg(feature = "gltf")]
+pub use gltf::write_gltf;
@@ -0,0 +1,284 @@
+use nalgebra::{Vector2, Vector3};
+use ndarray::prelude::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Geometry, LoadError};
+
+/// Parses the `count` first numbers of an OBJ line's arguments.
+fn parse_values<T: FromStr>(args: &[&str], count: usize) -> Option<Vec<T>> {
+    if args.len() < count {
+        return None;
+    }
+    args[..count].iter().map(|x| x.parse::<T>().ok()).collect()
+}
+
+/// Converts a 1-based (or negative, relative to the end) OBJ index into a 0-based one.
+fn resolve_index(index: &str, len: usize) -> Option<usize> {
+    match index.parse::<i64>().ok()? {
+        0 => None,
+        i if i > 0 && (i as usize) <= len => Some(i as usize - 1),
+        i if i < 0 && (-i as usize) <= len => Some(len - (-i as usize)),
+        _ => None,
+    }
+}
+
+/// Reads a Wavefront OBJ file. Supports `v`, `vn`, `vt` and `f` lines, with the
+/// `v`, `v/vt`, `v//vn` and `v/vt/vn` face syntaxes. Polygons with more than three vertices
+/// are triangulated as fans. Other statements (e.g., groups and materials) are ignored.
+///
+/// Normals and texture coordinates are indexed per vertex in `Geometry`, so they're assigned
+/// to the position index they're referenced with in the faces.
+///
+/// # Arguments
+///
+/// * `filepath` - Path to the OBJ file.
+///
+/// # Returns
+///
+/// The geometry or a `LoadError` if the file is malformed.
+pub fn read_obj<P>(filepath: P) -> Result<Geometry, LoadError>
+where
+    P: AsRef<Path>,
+{
+    let filepath = filepath.as_ref();
+    let buf_reader = BufReader::new(File::open(filepath)?);
+    let gen_error = |line_count: usize, message: String| {
+        LoadError::ParseError(format!(
+            "{}:{}: {}",
+            filepath.display(),
+            line_count,
+            message
+        ))
+    };
+
+    let mut points = Vec::new();
+    let mut obj_normals = Vec::new();
+    let mut obj_texcoords = Vec::new();
+    // (position, texcoord, normal) indices.
+    let mut corners = Vec::<(usize, Option<usize>, Option<usize>)>::new();
+
+    for (line_count, line) in buf_reader.lines().enumerate() {
+        let line = line?;
+        let line_count = line_count + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(statement) = tokens.next() else {
+            continue;
+        };
+        let args = tokens.collect::<Vec<_>>();
+
+        match statement {
+            "v" => {
+                let v = parse_values::<f32>(&args, 3).ok_or_else(|| {
+                    gen_error(line_count, format!("Invalid vertex. Got `{line}`"))
+                })?;
+                points.push(Vector3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let n = parse_values::<f32>(&args, 3).ok_or_else(|| {
+                    gen_error(line_count, format!("Invalid normal. Got `{line}`"))
+                })?;
+                obj_normals.push(Vector3::new(n[0], n[1], n[2]));
+            }
+            "vt" => {
+                let t = parse_values::<f32>(&args, 2).ok_or_else(|| {
+                    gen_error(
+                        line_count,
+                        format!("Invalid texture coordinate. Got `{line}`"),
+                    )
+                })?;
+                obj_texcoords.push(Vector2::new(t[0], t[1]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(gen_error(line_count, format!("Invalid face. Got `{line}`")));
+                }
+
+                let face = args
+                    .iter()
+                    .map(|corner| {
+                        let mut parts = corner.split('/');
+                        let v = resolve_index(parts.next()?, points.len())?;
+                        let vt = match parts.next() {
+                            None | Some("") => None,
+                            Some(vt) => Some(resolve_index(vt, obj_texcoords.len())?),
+                        };
+                        let vn = match parts.next() {
+                            None | Some("") => None,
+                            Some(vn) => Some(resolve_index(vn, obj_normals.len())?),
+                        };
+                        Some((v, vt, vn))
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| gen_error(line_count, format!("Invalid face. Got `{line}`")))?;
+
+                for i in 1..face.len() - 1 {
+                    corners.push(face[0]);
+                    corners.push(face[i]);
+                    corners.push(face[i + 1]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut normals = None;
+    let mut texcoords = None;
+    if !obj_normals.is_empty() {
+        let normals = normals.insert(Array1::<Vector3<f32>>::zeros(points.len()));
+        for (v, _, vn) in corners.iter() {
+            if let Some(vn) = vn {
+                normals[*v] = obj_normals[*vn];
+            }
+        }
+    }
+    if !obj_texcoords.is_empty() {
+        let texcoords = texcoords.insert(Array1::<Vector2<f32>>::zeros(points.len()));
+        for (v, vt, _) in corners.iter() {
+            if let Some(vt) = vt {
+                texcoords[*v] = obj_texcoords[*vt];
+            }
+        }
+    }
+
+    let faces = if corners.is_empty() {
+        None
+    } else {
+        Some(
+            Array2::from_shape_vec(
+                (corners.len() / 3, 3),
+                corners.iter().map(|(v, _, _)| *v).collect(),
+            )
+            .unwrap(),
+        )
+    };
+
+    Ok(Geometry {
+        points: Array1::from_vec(points),
+        colors: None,
+        normals,
+        confidences: None,
+        faces,
+        texcoords,
+    })
+}
+
+/// Writes a geometry into a Wavefront OBJ file. Writes the points, normals, texture
+/// coordinates and faces. Colors and confidences are not supported by the format.
+///
+/// # Arguments
+///
+/// * `filepath` - Path to the output file.
+/// * `geom` - The geometry to write.
+pub fn write_obj<P>(filepath: P, geom: &Geometry) -> Result<(), std::io::Error>
+where
+    P: AsRef<Path>,
+{
+    let mut buf = BufWriter::new(File::create(filepath)?);
+
+    for point in geom.points.iter() {
+        writeln!(buf, "v {} {} {}", point[0], point[1], point[2])?;
+    }
+
+    if let Some(texcoords) = &geom.texcoords {
+        for texcoord in texcoords.iter() {
+            writeln!(buf, "vt {} {}", texcoord[0], texcoord[1])?;
+        }
+    }
+
+    if let Some(normals) = &geom.normals {
+        for normal in normals.iter() {
+            writeln!(buf, "vn {} {} {}", normal[0], normal[1], normal[2])?;
+        }
+    }
+
+    if let Some(faces) = &geom.faces {
+        let has_texcoords = geom.texcoords.is_some();
+        let has_normals = geom.normals.is_some();
+        for face in faces.axis_iter(Axis(0)) {
+            write!(buf, "f")?;
+            for index in face.iter() {
+                let index = index + 1;
+                match (has_texcoords, has_normals) {
+                    (true, true) => write!(buf, " {index}/{index}/{index}")?,
+                    (true, false) => write!(buf, " {index}/{index}")?,
+                    (false, true) => write!(buf, " {index}//{index}")?,
+                    (false, false) => write!(buf, " {index}")?,
+                }
+            }
+            writeln!(buf)?;
+        }
+    }
+
+    buf.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector2, Vector3};
+    use ndarray::{array, Array1};
+
+    use super::{read_obj, write_obj};
+    use crate::io::{GeometryBuilder, LoadError};
+
+    #[test]
+    fn should_write_the_same_as_read() {
+        let points =
+            Array1::from_iter((0..8).map(|i| {
+                Vector3::new((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32)
+            }));
+        let normals = points.map(|p| (p - Vector3::new(0.5, 0.5, 0.5)).normalize());
+        let texcoords = points.map(|p| Vector2::new(p[0], p[1]));
+        let faces = array![
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5]
+        ];
+        let cube = GeometryBuilder::new(points)
+            .with_normals(normals)
+            .with_texcoords(texcoords)
+            .with_faces(faces)
+            .build();
+
+        write_obj("tests/outputs/out-cube.obj", &cube).unwrap();
+        let geom = read_obj("tests/outputs/out-cube.obj").unwrap();
+
+        assert_eq!(geom.len_vertices(), 8);
+        assert_eq!(geom.len_faces(), 12);
+        assert_eq!(geom.points, cube.points);
+        assert_eq!(geom.faces, cube.faces);
+        assert_eq!(geom.texcoords, cube.texcoords);
+        for (n1, n2) in geom
+            .normals
+            .unwrap()
+            .iter()
+            .zip(cube.normals.unwrap().iter())
+        {
+            assert!((n1 - n2).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn should_fail_on_invalid_index() {
+        std::fs::write(
+            "tests/outputs/out-invalid.obj",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            read_obj("tests/outputs/out-invalid.obj"),
+            Err(LoadError::ParseError(_))
+        ));
+    }
+}
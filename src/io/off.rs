@@ -20,7 +20,7 @@ impl TextParserContext {
         let mut line = String::new();
         self.buf_reader.read_line(&mut line)?;
         self.line_count += 1;
-        return Ok(line.trim().to_string());
+        Ok(line.trim().to_string())
     }
 
     /// Formats an error message by putting the file name, the current line and the supplied message.
@@ -128,6 +128,7 @@ pub fn read_off(filepath: &str) -> Result<Geometry, LoadError> {
         points: vertices,
         colors: None,
         normals: None,
+        confidences: None,
         faces: Some(faces),
         texcoords: None,
     })
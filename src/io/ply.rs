@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
 use super::{Geometry, LoadError};
@@ -19,6 +19,7 @@ struct Vertex {
     point: [f32; 3],
     normal: [f32; 3],
     color: [u8; 3],
+    confidence: f32,
 }
 
 #[derive(Debug)]
@@ -35,6 +36,7 @@ impl ply::PropertyAccess for Vertex {
             point: [0f32; 3],
             normal: [0f32; 3],
             color: [0u8; 3],
+            confidence: 0.0,
         }
     }
     fn set_property(&mut self, key: String, property: ply::Property) {
@@ -48,6 +50,7 @@ impl ply::PropertyAccess for Vertex {
             ("red", ply::Property::UChar(v)) => self.color[0] = v,
             ("green", ply::Property::UChar(v)) => self.color[1] = v,
             ("blue", ply::Property::UChar(v)) => self.color[2] = v,
+            ("confidence" | "quality", ply::Property::Float(v)) => self.confidence = v,
             (_, _) => (), // TODO: Add log
         }
     }
@@ -61,15 +64,25 @@ impl ply::PropertyAccess for Face {
         }
     }
     fn set_property(&mut self, key: String, property: ply::Property) {
-        match (key.as_ref(), property) {
-            ("vertex_index" | "vertex_indices", ply::Property::ListInt(vec)) => {
-                self.vertex_index = vec
-            }
-            (k, _) => panic!("Face: Unexpected key/value combination: key: {k}"),
+        // Other face properties are ignored.
+        if let ("vertex_index" | "vertex_indices", ply::Property::ListInt(vec)) =
+            (key.as_ref(), property)
+        {
+            self.vertex_index = vec
         }
     }
 }
 
+/// Reads a PLY file. Only the `vertex` (position, normal, color and confidence) and `face` elements
+/// are loaded, other elements are skipped.
+///
+/// # Arguments
+///
+/// * `filepath` - Path to the PLY file.
+///
+/// # Returns
+///
+/// The geometry or a `LoadError` if the file is malformed or has non-triangular faces.
 pub fn read_ply<P>(filepath: P) -> Result<Geometry, LoadError>
 where
     P: AsRef<Path>,
@@ -78,20 +91,19 @@ where
     let mut f = std::io::BufReader::new(fptr);
 
     let vertex_parser = parser::Parser::<Vertex>::new();
-    let header = vertex_parser.read_header(&mut f).unwrap();
+    let header = vertex_parser.read_header(&mut f)?;
 
     // Depending on the header, read the data into our structs..
     let mut point_array = None;
     let mut normal_array = None;
     let mut color_array = None;
+    let mut confidence_array = None;
     let mut face_array = None;
     for (_ignore_key, element) in &header.elements {
         match element.name.as_ref() {
             "vertex" => {
-                let vertex_parser = parser::Parser::<Vertex>::new();
-                let vertex_vec = vertex_parser
-                    .read_payload_for_element(&mut f, element, &header)
-                    .unwrap();
+                let vertex_vec =
+                    vertex_parser.read_payload_for_element(&mut f, element, &header)?;
 
                 point_array = Some(Array1::<Vector3<f32>>::from_shape_fn(
                     vertex_vec.len(),
@@ -117,31 +129,96 @@ where
                         |i| Vector3::from_row_slice(&vertex_vec[i].color),
                     ));
                 }
+
+                if ["confidence", "quality"]
+                    .iter()
+                    .any(|k| element.properties.contains_key(*k))
+                {
+                    confidence_array = Some(Array1::<f32>::from_shape_fn(vertex_vec.len(), |i| {
+                        vertex_vec[i].confidence
+                    }));
+                }
             }
             "face" => {
                 let face_parser = parser::Parser::<Face>::new();
-                let face_vec = face_parser
-                    .read_payload_for_element(&mut f, element, &header)
-                    .unwrap();
+                let face_vec = face_parser.read_payload_for_element(&mut f, element, &header)?;
+                if let Some((i, face)) = face_vec
+                    .iter()
+                    .enumerate()
+                    .find(|(_, face)| face.vertex_index.len() != 3)
+                {
+                    return Err(LoadError::ParseError(format!(
+                        "Face {i} has {} vertices, only triangles are supported",
+                        face.vertex_index.len()
+                    )));
+                }
                 face_array = Some(Array2::<usize>::from_shape_fn(
                     (face_vec.len(), 3),
                     |(i, c)| face_vec[i].vertex_index[c] as usize,
                 ));
             }
-            _ => panic!("Unexpected element"), // _ => return Err(LoadError::ParseError((format!("Unexpected element {}", _))));
+            _ => {
+                // Consumes the payload of unknown elements.
+                parser::Parser::<DefaultElement>::new()
+                    .read_payload_for_element(&mut f, element, &header)?;
+            }
         }
     }
 
     Ok(Geometry {
-        points: point_array.unwrap(),
+        points: point_array
+            .ok_or_else(|| LoadError::ParseError("Missing vertex element".to_string()))?,
         colors: color_array,
         normals: normal_array,
+        confidences: confidence_array,
         faces: face_array,
         texcoords: None,
     })
 }
 
+/// Encoding of the PLY files' payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyEncoding {
+    /// Human-readable text.
+    Ascii,
+    /// Binary, little endian.
+    BinaryLittleEndian,
+    /// Binary, big endian.
+    BinaryBigEndian,
+}
+
+impl From<PlyEncoding> for Encoding {
+    fn from(encoding: PlyEncoding) -> Self {
+        match encoding {
+            PlyEncoding::Ascii => Encoding::Ascii,
+            PlyEncoding::BinaryLittleEndian => Encoding::BinaryLittleEndian,
+            PlyEncoding::BinaryBigEndian => Encoding::BinaryBigEndian,
+        }
+    }
+}
+
+/// Writes a geometry into a PLY file using ASCII encoding.
+/// See [`write_ply_with_encoding`] for binary files.
 pub fn write_ply<P>(filepath: P, geom: &Geometry) -> Result<(), std::io::Error>
+where
+    P: AsRef<Path>,
+{
+    write_ply_with_encoding(filepath, geom, PlyEncoding::Ascii)
+}
+
+/// Writes a geometry into a PLY file.
+///
+/// # Arguments
+///
+/// * `filepath` - Path to the output file.
+/// * `geom` - The geometry to write.
+/// * `encoding` - Encoding of the payload. Binary encodings produce much smaller
+///   and faster files for large point clouds.
+pub fn write_ply_with_encoding<P>(
+    filepath: P,
+    geom: &Geometry,
+    encoding: PlyEncoding,
+) -> Result<(), std::io::Error>
 where
     P: AsRef<Path>,
 {
@@ -197,6 +274,17 @@ where
             });
         }
 
+        if let Some(confidences) = &geom.confidences {
+            vertex_element.properties.add(PropertyDef::new(
+                "confidence".to_string(),
+                PropertyType::Scalar(ScalarType::Float),
+            ));
+
+            confidences.iter().enumerate().for_each(|(i, confidence)| {
+                vertex_array[i].insert("confidence".to_string(), Property::Float(*confidence));
+            });
+        }
+
         ply.header.elements.add(vertex_element);
         ply.payload.insert("vertex".to_string(), vertex_array);
 
@@ -227,21 +315,95 @@ where
         ply
     };
 
-    ply.header.encoding = Encoding::Ascii;
+    ply.header.encoding = encoding.into();
 
     let mut buf = BufWriter::new(File::create(filepath)?);
-    Writer::new().write_ply(&mut buf, &mut ply)?;
+    let writer = Writer::new();
+    if encoding == PlyEncoding::Ascii {
+        writer.write_ply(&mut buf, &mut ply)?;
+        return Ok(());
+    }
+
+    // ply-rs writes the element count instead of the list length on binary list
+    // properties, so the faces' payload is written here.
+    writer.write_header(&mut buf, &ply.header)?;
+    writer.write_payload_of_element(
+        &mut buf,
+        &ply.payload["vertex"],
+        &ply.header.elements["vertex"],
+        &ply.header,
+    )?;
+    if let Some(faces) = &geom.faces {
+        for face in faces.axis_iter(Axis(0)) {
+            buf.write_all(&[face.len() as u8])?;
+            for index in face.iter() {
+                let index = *index as i32;
+                buf.write_all(&match encoding {
+                    PlyEncoding::BinaryBigEndian => index.to_be_bytes(),
+                    _ => index.to_le_bytes(),
+                })?;
+            }
+        }
+    }
 
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use super::{read_ply, write_ply};
+    use super::{read_ply, write_ply, write_ply_with_encoding, PlyEncoding};
+    use crate::io::LoadError;
 
     #[test]
     fn should_write_the_same_as_read() {
         let geom = read_ply("tests/data/teapot.ply").unwrap();
         write_ply("tests/data/out-teapot.ply", &geom).unwrap();
     }
+
+    #[test]
+    fn should_write_binary() {
+        let geom = read_ply("tests/data/teapot.ply").unwrap();
+        write_ply_with_encoding(
+            "tests/outputs/out-teapot-binary.ply",
+            &geom,
+            PlyEncoding::BinaryLittleEndian,
+        )
+        .unwrap();
+
+        let binary_geom = read_ply("tests/outputs/out-teapot-binary.ply").unwrap();
+        assert_eq!(binary_geom.len_vertices(), geom.len_vertices());
+        assert_eq!(binary_geom.len_faces(), geom.len_faces());
+        assert_eq!(binary_geom.points, geom.points);
+        assert_eq!(binary_geom.faces, geom.faces);
+    }
+
+    #[test]
+    fn should_skip_unknown_elements() {
+        let geom = read_ply("tests/data/ply/extra_element.ply").unwrap();
+        assert_eq!(geom.len_vertices(), 4);
+        assert_eq!(geom.len_faces(), 2);
+        assert_eq!(geom.faces.unwrap()[(1, 2)], 3);
+    }
+
+    #[test]
+    fn should_fail_on_quad_faces() {
+        assert!(matches!(
+            read_ply("tests/data/ply/quad_face.ply"),
+            Err(LoadError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn should_read_and_write_confidence() {
+        let geom = read_ply("tests/data/ply/confidence.ply").unwrap();
+        let confidences = geom.confidences.as_ref().unwrap();
+        assert_eq!(confidences.to_vec(), vec![0.25, 0.5, 0.75, 1.0]);
+
+        write_ply("tests/outputs/out-confidence.ply", &geom).unwrap();
+        let geom = read_ply("tests/outputs/out-confidence.ply").unwrap();
+        assert_eq!(
+            geom.confidences.unwrap().to_vec(),
+            vec![0.25, 0.5, 0.75, 1.0]
+        );
+    }
 }
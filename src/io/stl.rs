@@ -0,0 +1,126 @@
+use nalgebra::Vector3;
+use ndarray::Axis;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::Geometry;
+
+/// Name written in the header of the STL files.
+const SOLID_NAME: &str = "align3d";
+
+/// Writes the triangles of a geometry into a STL file. The face normals are computed
+/// from the triangles' vertices, following their counter-clockwise order.
+/// Only the points and faces are written, the format doesn't support other attributes.
+///
+/// # Arguments
+///
+/// * `filepath` - Path to the output file.
+/// * `geom` - The geometry to write. A geometry without faces produces an empty solid.
+/// * `binary` - Whether to write the binary or the ASCII variant of the format.
+pub fn write_stl<P>(filepath: P, geom: &Geometry, binary: bool) -> Result<(), std::io::Error>
+where
+    P: AsRef<Path>,
+{
+    let mut buf = BufWriter::new(File::create(filepath)?);
+
+    let triangles = geom
+        .faces
+        .iter()
+        .flat_map(|faces| faces.axis_iter(Axis(0)))
+        .map(|face| {
+            let (p0, p1, p2) = (
+                geom.points[face[0]],
+                geom.points[face[1]],
+                geom.points[face[2]],
+            );
+            let normal = (p1 - p0).cross(&(p2 - p0));
+            let normal = normal.try_normalize(0.0).unwrap_or_else(Vector3::zeros);
+            (normal, [p0, p1, p2])
+        });
+
+    if binary {
+        let mut header = [0u8; 80];
+        header[..SOLID_NAME.len()].copy_from_slice(SOLID_NAME.as_bytes());
+        buf.write_all(&header)?;
+        buf.write_all(&(geom.len_faces() as u32).to_le_bytes())?;
+        for (normal, vertices) in triangles {
+            for vector in std::iter::once(normal).chain(vertices) {
+                for value in vector.iter() {
+                    buf.write_all(&value.to_le_bytes())?;
+                }
+            }
+            // Attribute byte count, unused.
+            buf.write_all(&0u16.to_le_bytes())?;
+        }
+    } else {
+        writeln!(buf, "solid {SOLID_NAME}")?;
+        for (normal, vertices) in triangles {
+            writeln!(
+                buf,
+                "  facet normal {} {} {}",
+                normal[0], normal[1], normal[2]
+            )?;
+            writeln!(buf, "    outer loop")?;
+            for vertex in vertices {
+                writeln!(
+                    buf,
+                    "      vertex {} {} {}",
+                    vertex[0], vertex[1], vertex[2]
+                )?;
+            }
+            writeln!(buf, "    endloop")?;
+            writeln!(buf, "  endfacet")?;
+        }
+        writeln!(buf, "endsolid {SOLID_NAME}")?;
+    }
+
+    buf.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::write_stl;
+    use crate::{io::Geometry, unit_test::sample_cube_geometry};
+
+    #[rstest]
+    fn should_write_binary(sample_cube_geometry: Geometry) {
+        write_stl("tests/outputs/out-cube.stl", &sample_cube_geometry, true).unwrap();
+
+        let bytes = std::fs::read("tests/outputs/out-cube.stl").unwrap();
+        let num_triangles = u32::from_le_bytes(bytes[80..84].try_into().unwrap());
+        assert_eq!(num_triangles as usize, sample_cube_geometry.len_faces());
+        assert_eq!(bytes.len(), 84 + 50 * sample_cube_geometry.len_faces());
+
+        // First triangle is on the +X side.
+        let normal = bytes[84..96]
+            .chunks(4)
+            .map(|value| f32::from_le_bytes(value.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(normal, vec![1.0, 0.0, 0.0]);
+    }
+
+    #[rstest]
+    fn should_write_ascii(sample_cube_geometry: Geometry) {
+        write_stl(
+            "tests/outputs/out-cube-ascii.stl",
+            &sample_cube_geometry,
+            false,
+        )
+        .unwrap();
+
+        let text = std::fs::read_to_string("tests/outputs/out-cube-ascii.stl").unwrap();
+        assert!(text.starts_with("solid "));
+        assert!(text.trim_end().ends_with("endsolid align3d"));
+        assert_eq!(
+            text.matches("facet normal").count(),
+            sample_cube_geometry.len_faces()
+        );
+        assert_eq!(
+            text.matches("vertex").count(),
+            3 * sample_cube_geometry.len_faces()
+        );
+    }
+}
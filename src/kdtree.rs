@@ -103,6 +103,178 @@ impl R3dTree {
             }
         }
     }
+
+    /// Find the exact nearest neighbor to a query point. Unlike [`Self::nearest`], which
+    /// only searches the leaf containing the query, it backtracks into the other branches
+    /// that may have closer points. It is slower, but required for accurate measurements.
+    ///
+    /// # Arguments
+    ///
+    /// * point - The query point.
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the index of the nearest neighbor and the squared distance to it.
+    pub fn nearest_exact(&self, point: &Vector3<f32>) -> (usize, f32) {
+        let (index, distance, _) = self.nearest_approx(point, usize::MAX);
+        (index, distance)
+    }
+
+    /// Find an approximate nearest neighbor to a query point. It searches like
+    /// [`Self::nearest_exact`], but stops backtracking after visiting `max_leaf_checks`
+    /// leaves, trading accuracy for speed on large point clouds.
+    ///
+    /// # Arguments
+    ///
+    /// * point - The query point.
+    /// * max_leaf_checks - Maximum number of leaves to visit. The leaf containing the query
+    ///   is always visited.
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the index of the best neighbor found, the squared distance to it,
+    /// and whether the search was exhaustive, i.e., the neighbor is the exact nearest one.
+    pub fn nearest_approx(
+        &self,
+        point: &Vector3<f32>,
+        max_leaf_checks: usize,
+    ) -> (usize, f32, bool) {
+        let mut search = NearestSearch {
+            best: (0, f32::MAX),
+            leaf_checks: 0,
+            max_leaf_checks: max_leaf_checks.max(1),
+            exhaustive: true,
+        };
+        search_node(&self.root, point, 0, &mut search);
+        (search.best.0, search.best.1, search.exhaustive)
+    }
+
+    /// Find the `k` exact nearest neighbors to a query point.
+    ///
+    /// # Arguments
+    ///
+    /// * point - The query point.
+    /// * k - The number of neighbors.
+    ///
+    /// # Returns
+    ///
+    /// Up to `k` tuples with the index of the neighbor and the squared distance to it,
+    /// sorted by distance.
+    pub fn k_nearest(&self, point: &Vector3<f32>, k: usize) -> Vec<(usize, f32)> {
+        let mut search = KNearestSearch {
+            k,
+            best: Vec::with_capacity(k + 1),
+        };
+        if k > 0 {
+            search_node(&self.root, point, 0, &mut search);
+        }
+        search.best
+    }
+}
+
+/// State of a backtracking search, see [`search_node`].
+trait NeighborSearch {
+    /// Squared distance that a point must be under to be a candidate.
+    fn worst_distance(&self) -> f32;
+
+    /// Adds a candidate point closer than [`Self::worst_distance`].
+    fn insert(&mut self, index: usize, distance: f32);
+
+    /// Called when a leaf is visited.
+    fn visit_leaf(&mut self) {}
+
+    /// Whether a branch that may have candidates can be visited.
+    fn can_backtrack(&mut self) -> bool {
+        true
+    }
+}
+
+/// Searches the leaf containing the query first, then backtracks into the branches whose
+/// split plane is closer than the worst candidate.
+fn search_node(node: &Node, point: &Vector3<f32>, dim: usize, search: &mut impl NeighborSearch) {
+    match node {
+        Node::NonLeaf {
+            middle_value: mid,
+            left,
+            right,
+        } => {
+            let diff = point[dim] - *mid;
+            let (near, far) = if diff < 0.0 {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            search_node(near, point, (dim + 1) % 3, search);
+            if diff * diff <= search.worst_distance() && search.can_backtrack() {
+                search_node(far, point, (dim + 1) % 3, search);
+            }
+        }
+        Node::Leaf {
+            points: leaf_points,
+            indices,
+        } => {
+            search.visit_leaf();
+            for (leaf_point, index) in leaf_points.iter().zip(indices.iter()) {
+                let distance = (point - leaf_point).norm_squared();
+                if distance < search.worst_distance() {
+                    search.insert(*index, distance);
+                }
+            }
+        }
+    }
+}
+
+/// Nearest neighbor search that visits at most `max_leaf_checks` leaves.
+struct NearestSearch {
+    best: (usize, f32),
+    leaf_checks: usize,
+    max_leaf_checks: usize,
+    exhaustive: bool,
+}
+
+impl NeighborSearch for NearestSearch {
+    fn worst_distance(&self) -> f32 {
+        self.best.1
+    }
+
+    fn insert(&mut self, index: usize, distance: f32) {
+        self.best = (index, distance);
+    }
+
+    fn visit_leaf(&mut self) {
+        self.leaf_checks += 1;
+    }
+
+    fn can_backtrack(&mut self) -> bool {
+        if self.leaf_checks < self.max_leaf_checks {
+            true
+        } else {
+            self.exhaustive = false;
+            false
+        }
+    }
+}
+
+/// Search of the `k` nearest neighbors, kept sorted by distance.
+struct KNearestSearch {
+    k: usize,
+    best: Vec<(usize, f32)>,
+}
+
+impl NeighborSearch for KNearestSearch {
+    fn worst_distance(&self) -> f32 {
+        if self.best.len() < self.k {
+            f32::MAX
+        } else {
+            self.best[self.k - 1].1
+        }
+    }
+
+    fn insert(&mut self, index: usize, distance: f32) {
+        let position = self.best.partition_point(|(_, d)| *d <= distance);
+        self.best.insert(position, (index, distance));
+        self.best.truncate(self.k);
+    }
 }
 
 #[cfg(test)]
@@ -115,7 +287,7 @@ mod tests {
     use ndarray::prelude::*;
     use rand::rngs::SmallRng;
     use rand::seq::SliceRandom;
-    use rand::SeedableRng;
+    use rand::{Rng, SeedableRng};
 
     #[test]
     fn should_find_nearest_points() {
@@ -169,6 +341,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_find_exact_nearest_points() {
+        let mut rng = SmallRng::seed_from_u64(3);
+        let points = Array1::from_shape_fn(1000, |_| {
+            Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+        });
+        let tree = R3dTree::new(&points.view());
+
+        for _ in 0..200 {
+            let query = Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            let (expected_idx, expected_dist) = points
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (i, (p - query).norm_squared()))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .unwrap();
+            assert_eq!(tree.nearest_exact(&query), (expected_idx, expected_dist));
+        }
+    }
+
+    #[test]
+    fn should_find_k_nearest_points() {
+        let mut rng = SmallRng::seed_from_u64(4);
+        let points = Array1::from_shape_fn(1000, |_| {
+            Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+        });
+        let tree = R3dTree::new(&points.view());
+
+        for _ in 0..50 {
+            let query = Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            let mut expected = points
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (i, (p - query).norm_squared()))
+                .collect::<Vec<_>>();
+            expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            expected.truncate(10);
+            assert_eq!(tree.k_nearest(&query, 10), expected);
+        }
+
+        assert_eq!(tree.k_nearest(&Vector3::zeros(), 2000).len(), 1000);
+        assert!(tree.k_nearest(&Vector3::zeros(), 0).is_empty());
+    }
+
     #[test]
     fn bench_nearest() {
         const N: usize = 500_000;
@@ -207,4 +439,46 @@ mod tests {
 
         println!("Mean time: {}", sum_millis as f64 / M as f64);
     }
+
+    #[test]
+    fn should_find_approximate_nearest_points() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut random_point = || {
+            Vector3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+        };
+        let points = Array1::from_shape_fn(5000, |_| random_point());
+        let queries = (0..500).map(|_| random_point()).collect::<Vec<_>>();
+        let tree = R3dTree::new(&points.view());
+
+        let recalls = [1, 2, 4, 8, 1000]
+            .iter()
+            .map(|max_leaf_checks| {
+                let mut hits = 0;
+                for query in queries.iter() {
+                    let (index, dist, exhaustive) = tree.nearest_approx(query, *max_leaf_checks);
+                    let (exact_index, exact_dist) = tree.nearest_exact(query);
+                    assert!(dist >= exact_dist);
+                    if exhaustive {
+                        assert_eq!((index, dist), (exact_index, exact_dist));
+                    }
+                    if index == exact_index {
+                        hits += 1;
+                    }
+                }
+                hits as f32 / queries.len() as f32
+            })
+            .collect::<Vec<_>>();
+
+        assert!(
+            recalls.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{recalls:?}"
+        );
+        assert!(recalls[0] < 1.0, "{recalls:?}");
+        assert!(recalls[3] > 0.9, "{recalls:?}");
+        assert_eq!(recalls[4], 1.0);
+    }
 }
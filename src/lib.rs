@@ -1,5 +1,6 @@
 pub mod bilateral;
 pub mod camera;
+pub mod fusion;
 
 pub mod icp;
 mod intensity_map;
@@ -10,6 +11,7 @@ pub mod mesh;
 pub mod pointcloud;
 pub mod range_image;
 mod sampling;
+pub use crate::sampling::Downsample;
 pub mod transform;
 
 pub mod error;
@@ -24,6 +26,7 @@ pub mod viz;
 mod extra_math;
 pub mod metrics;
 mod optim;
+pub use crate::optim::{GaussNewton, HuberEstimator, LevenbergMarquardt, RobustEstimator};
 
 mod image;
-pub use crate::image::{RgbdFrame, RgbdImage};
+pub use crate::image::{IntoLumaArray, IntoLumaImage, RgbdFrame, RgbdImage, TemporalDepthFilter};
@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+use ndarray::{Array1, Array2};
+
+use crate::{
+    error::A3dError,
+    io::{Geometry, GeometryBuilder},
+    pointcloud::PointCloud,
+};
+
+/// Parameters of [`PointCloud::reconstruct_mesh`].
+#[derive(Debug, Clone)]
+pub struct ReconParams {
+    /// Radii of the pivoting balls, in increasing order. Each radius pivots on the boundary
+    /// left by the previous ones, closing larger gaps.
+    pub radii: Vec<f32>,
+    /// Maximum number of neighbors tried with each point when searching seed triangles.
+    pub max_seed_neighbors: usize,
+}
+
+impl ReconParams {
+    /// Creates the parameters with a single ball radius. It should be slightly larger
+    /// than the spacing between the points.
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radii: vec![radius],
+            max_seed_neighbors: 16,
+        }
+    }
+
+    /// Adds a larger ball radius, used after the previous ones.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radii.push(radius);
+        self
+    }
+}
+
+/// Uniform grid for searching the points inside a ball.
+struct PointGrid {
+    cell_size: f32,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl PointGrid {
+    fn new(points: &Array1<Vector3<f32>>, cell_size: f32) -> Self {
+        let mut grid = Self {
+            cell_size,
+            cells: HashMap::new(),
+        };
+        for (index, point) in points.iter().enumerate() {
+            grid.cells.entry(grid.cell(point)).or_default().push(index);
+        }
+        grid
+    }
+
+    fn cell(&self, point: &Vector3<f32>) -> [i32; 3] {
+        let cell = (point / self.cell_size).map(|c| c.floor() as i32);
+        [cell[0], cell[1], cell[2]]
+    }
+
+    /// Indices of the points within `radius` of `center`. `radius` must not exceed the cell size.
+    fn neighbors(
+        &self,
+        points: &Array1<Vector3<f32>>,
+        center: &Vector3<f32>,
+        radius: f32,
+    ) -> Vec<usize> {
+        let [x, y, z] = self.cell(center);
+        let radius_sqr = radius * radius;
+        let mut neighbors = Vec::new();
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if let Some(cell) = self.cells.get(&[x + dx, y + dy, z + dz]) {
+                        neighbors.extend(cell.iter().copied().filter(|index| {
+                            (points[*index] - center).norm_squared() <= radius_sqr
+                        }));
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+/// Edge on the front of the mesh being grown. Its triangle is `(a, b, opposite)`,
+/// counter-clockwise around the outward normal, where `(a, b)` is the edge key.
+struct FrontEdge {
+    opposite: usize,
+    center: Vector3<f32>,
+    active: bool,
+}
+
+/// Ball-pivoting state, see [`PointCloud::reconstruct_mesh`].
+struct BallPivoting<'a> {
+    points: &'a Array1<Vector3<f32>>,
+    normals: &'a Array1<Vector3<f32>>,
+    grid: PointGrid,
+    radius: f32,
+    used: Vec<bool>,
+    /// Number of front edges incident to each vertex.
+    front_degree: Vec<usize>,
+    front: HashMap<(usize, usize), FrontEdge>,
+    /// Number of triangles of each undirected edge, keyed by (min, max) vertex.
+    edge_triangles: HashMap<(usize, usize), u8>,
+    /// Front edges to pivot.
+    queue: Vec<(usize, usize)>,
+    faces: Vec<[usize; 3]>,
+}
+
+impl<'a> BallPivoting<'a> {
+    fn new(
+        points: &'a Array1<Vector3<f32>>,
+        normals: &'a Array1<Vector3<f32>>,
+        max_radius: f32,
+    ) -> Self {
+        Self {
+            points,
+            normals,
+            grid: PointGrid::new(points, 2.0 * max_radius),
+            radius: max_radius,
+            used: vec![false; points.len()],
+            front_degree: vec![0; points.len()],
+            front: HashMap::new(),
+            edge_triangles: HashMap::new(),
+            queue: Vec::new(),
+            faces: Vec::new(),
+        }
+    }
+
+    /// Center of the ball touching the counter-clockwise triangle `(a, b, c)` on its outer side.
+    /// None if the ball is too small or the triangle disagrees with the vertex normals.
+    fn ball_center(&self, a: usize, b: usize, c: usize) -> Option<Vector3<f32>> {
+        let (pa, pb, pc) = (self.points[a], self.points[b], self.points[c]);
+        let (ab, ac) = (pb - pa, pc - pa);
+        let w = ab.cross(&ac);
+        let w_norm_sqr = w.norm_squared();
+        if w_norm_sqr < 1e-12 {
+            return None;
+        }
+        let normal = w / w_norm_sqr.sqrt();
+        if [a, b, c]
+            .iter()
+            .any(|index| normal.dot(&self.normals[*index]) <= 0.0)
+        {
+            return None;
+        }
+
+        let circumcenter = pa
+            + (w.cross(&ab) * ac.norm_squared() + ac.cross(&w) * ab.norm_squared())
+                / (2.0 * w_norm_sqr);
+        let height_sqr = self.radius * self.radius - (circumcenter - pa).norm_squared();
+        if height_sqr < 0.0 {
+            return None;
+        }
+        Some(circumcenter + normal * height_sqr.sqrt())
+    }
+
+    /// Whether the ball has no points other than the triangle's vertices.
+    fn is_empty_ball(&self, center: &Vector3<f32>, triangle: [usize; 3]) -> bool {
+        self.grid
+            .neighbors(self.points, center, self.radius * (1.0 - 1e-4))
+            .iter()
+            .all(|index| triangle.contains(index))
+    }
+
+    fn edge_key(a: usize, b: usize) -> (usize, usize) {
+        (a.min(b), a.max(b))
+    }
+
+    /// Whether the triangle `(a, b, c)` can be added without making the mesh non-manifold.
+    fn can_add_triangle(&self, a: usize, b: usize, c: usize) -> bool {
+        [(a, b), (b, c), (c, a)].iter().all(|(v0, v1)| {
+            self.edge_triangles
+                .get(&Self::edge_key(*v0, *v1))
+                .is_none_or(|count| {
+                    // An edge in one triangle can only be shared in the opposite direction.
+                    *count == 1 && self.front.contains_key(&(*v1, *v0))
+                })
+        })
+    }
+
+    /// Adds the counter-clockwise triangle `(a, b, c)`, updating the front.
+    fn add_triangle(&mut self, a: usize, b: usize, c: usize, center: Vector3<f32>) {
+        self.faces.push([a, b, c]);
+        for (v0, v1, opposite) in [(a, b, c), (b, c, a), (c, a, b)] {
+            *self
+                .edge_triangles
+                .entry(Self::edge_key(v0, v1))
+                .or_default() += 1;
+
+            if self.front.remove(&(v1, v0)).is_some() {
+                // Glues with the neighbor triangle.
+                self.front_degree[v0] -= 1;
+                self.front_degree[v1] -= 1;
+            } else {
+                self.front.insert(
+                    (v0, v1),
+                    FrontEdge {
+                        opposite,
+                        center,
+                        active: true,
+                    },
+                );
+                self.front_degree[v0] += 1;
+                self.front_degree[v1] += 1;
+                self.queue.push((v0, v1));
+            }
+        }
+        for vertex in [a, b, c] {
+            self.used[vertex] = true;
+        }
+    }
+
+    /// Finds a triangle around an unused vertex whose ball is empty.
+    fn find_seed(&self, vertex: usize, max_neighbors: usize) -> Option<([usize; 3], Vector3<f32>)> {
+        let point = self.points[vertex];
+        let mut neighbors = self
+            .grid
+            .neighbors(self.points, &point, 2.0 * self.radius)
+            .into_iter()
+            .filter(|index| *index != vertex && !self.used[*index])
+            .collect::<Vec<_>>();
+        neighbors.sort_by(|a, b| {
+            (self.points[*a] - point)
+                .norm_squared()
+                .total_cmp(&(self.points[*b] - point).norm_squared())
+        });
+        neighbors.truncate(max_neighbors);
+
+        for (i, b) in neighbors.iter().enumerate() {
+            for c in neighbors[i + 1..].iter() {
+                for triangle in [[vertex, *b, *c], [vertex, *c, *b]] {
+                    if let Some(center) = self.ball_center(triangle[0], triangle[1], triangle[2]) {
+                        if self.is_empty_ball(&center, triangle) {
+                            return Some((triangle, center));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Pivots the ball around the front edge `(a, b)` until it touches another point.
+    fn pivot(&self, a: usize, b: usize, edge: &FrontEdge) -> Option<(usize, Vector3<f32>)> {
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let middle = (pa + pb) * 0.5;
+        let axis = (pb - pa).normalize();
+
+        // Basis of the pivoting plane: from the current center, turning away from the triangle.
+        let start = edge.center - middle;
+        let start = (start - axis * axis.dot(&start)).try_normalize(1e-12)?;
+        let mut turn = axis.cross(&start);
+        if turn.dot(&(middle - self.points[edge.opposite])) < 0.0 {
+            turn = -turn;
+        }
+
+        let mut best: Option<(f32, usize, Vector3<f32>)> = None;
+        for candidate in self.grid.neighbors(self.points, &middle, 2.0 * self.radius) {
+            if candidate == a || candidate == b || candidate == edge.opposite {
+                continue;
+            }
+            let Some(center) = self.ball_center(b, a, candidate) else {
+                continue;
+            };
+            let offset = center - middle;
+            let mut angle = offset.dot(&turn).atan2(offset.dot(&start));
+            if angle < 0.0 {
+                angle += 2.0 * std::f32::consts::PI;
+            }
+            if best.is_none_or(|(best_angle, _, _)| angle < best_angle) {
+                best = Some((angle, candidate, center));
+            }
+        }
+
+        let (_, candidate, center) = best?;
+        if !self.is_empty_ball(&center, [a, b, candidate]) {
+            return None;
+        }
+        Some((candidate, center))
+    }
+
+    /// Pivots the active front edges until none is left.
+    fn expand_front(&mut self) {
+        while let Some((a, b)) = self.queue.pop() {
+            let Some(edge) = self.front.get(&(a, b)) else {
+                continue;
+            };
+            if !edge.active {
+                continue;
+            }
+
+            let next = self.pivot(a, b, edge).filter(|(candidate, _)| {
+                // Points inside the mesh can't be reached again.
+                (!self.used[*candidate] || self.front_degree[*candidate] > 0)
+                    && self.can_add_triangle(b, a, *candidate)
+            });
+            match next {
+                Some((candidate, center)) => self.add_triangle(b, a, candidate, center),
+                None => self.front.get_mut(&(a, b)).unwrap().active = false,
+            }
+        }
+    }
+
+    /// Runs a pass with the given ball radius.
+    fn run(&mut self, radius: f32, max_seed_neighbors: usize) {
+        self.radius = radius;
+
+        // Retries the boundary left by smaller balls.
+        let boundary = self
+            .front
+            .iter()
+            .map(|(key, edge)| (*key, edge.opposite))
+            .collect::<Vec<_>>();
+        for ((a, b), opposite) in boundary {
+            if let Some(center) = self.ball_center(a, b, opposite) {
+                if self.is_empty_ball(&center, [a, b, opposite]) {
+                    let edge = self.front.get_mut(&(a, b)).unwrap();
+                    edge.center = center;
+                    edge.active = true;
+                    self.queue.push((a, b));
+                }
+            }
+        }
+        self.expand_front();
+
+        for vertex in 0..self.points.len() {
+            if self.used[vertex] {
+                continue;
+            }
+            if let Some(([a, b, c], center)) = self.find_seed(vertex, max_seed_neighbors) {
+                self.add_triangle(a, b, c, center);
+                self.expand_front();
+            }
+        }
+    }
+}
+
+impl PointCloud {
+    /// Reconstructs a triangle mesh with the ball-pivoting algorithm, as in Bernardini et al.,
+    /// The Ball-Pivoting Algorithm for Surface Reconstruction, TVCG 1999. A ball rolls over the
+    /// points, and each triplet it touches without containing other points becomes a triangle.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The ball radii.
+    ///
+    /// # Returns
+    ///
+    /// The mesh, with the same vertices and attributes of the point cloud. Faces are
+    /// counter-clockwise around the point normals. Returns an error if the point cloud has
+    /// no normals or `params` has no positive radius.
+    pub fn reconstruct_mesh(&self, params: ReconParams) -> Result<Geometry, A3dError> {
+        let normals = self
+            .normals
+            .as_ref()
+            .ok_or_else(|| A3dError::invalid_parameter("Mesh reconstruction requires normals."))?;
+        let max_radius = params.radii.iter().copied().fold(0.0, f32::max);
+        if max_radius <= 0.0 || params.radii.iter().any(|radius| *radius <= 0.0) {
+            return Err(A3dError::invalid_parameter(
+                "Ball radii must be positive and at least one must be given.",
+            ));
+        }
+
+        let normals = normals.map(|normal| normal.normalize());
+        let mut pivoting = BallPivoting::new(&self.points, &normals, max_radius);
+        for radius in params.radii.iter() {
+            pivoting.run(*radius, params.max_seed_neighbors);
+        }
+
+        let faces = pivoting.faces.iter().flatten().copied().collect::<Vec<_>>();
+        let mut builder = GeometryBuilder::new(self.points.clone())
+            .with_normals(self.normals.clone().unwrap())
+            .with_faces(Array2::from_shape_vec((faces.len() / 3, 3), faces).unwrap());
+        if let Some(colors) = self.colors.as_ref() {
+            builder = builder.with_colors(colors.clone());
+        }
+        if let Some(confidences) = self.confidences.as_ref() {
+            builder = builder.with_confidences(confidences.clone());
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use nalgebra::Vector3;
+    use ndarray::Array1;
+
+    use super::ReconParams;
+    use crate::{error::A3dError, io::write_ply, pointcloud::PointCloud};
+
+    /// Points evenly spread over the unit sphere (Fibonacci lattice), with outward normals.
+    fn sample_sphere(n: usize) -> PointCloud {
+        let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+        let points = (0..n)
+            .map(|i| {
+                let z = 1.0 - 2.0 * (i as f32 + 0.5) / n as f32;
+                let radius = (1.0 - z * z).sqrt();
+                let theta = golden_angle * i as f32;
+                Vector3::new(radius * theta.cos(), radius * theta.sin(), z)
+            })
+            .collect::<Array1<_>>();
+        let mut pcl = PointCloud::zeros(n);
+        pcl.normals = Some(points.clone());
+        pcl.points = points;
+        pcl.colors = None;
+        pcl
+    }
+
+    #[test]
+    fn test_reconstruct_sphere() {
+        const NUM_POINTS: usize = 1000;
+        let pcl = sample_sphere(NUM_POINTS);
+        let spacing = (4.0 * std::f32::consts::PI / NUM_POINTS as f32).sqrt();
+
+        let mesh = pcl
+            .reconstruct_mesh(ReconParams::new(spacing).with_radius(spacing * 1.5))
+            .unwrap();
+        write_ply("tests/outputs/out-ball-pivoting-sphere.ply", &mesh).unwrap();
+
+        // Closed: every edge is shared by two faces in opposite directions.
+        let faces = mesh.faces.as_ref().unwrap();
+        let mut edges = HashMap::new();
+        for face in faces.rows() {
+            for (a, b) in [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])] {
+                assert!(edges.insert((a, b), ()).is_none(), "Repeated edge {a} {b}");
+            }
+        }
+        assert!(edges.keys().all(|(a, b)| edges.contains_key(&(*b, *a))));
+        // Euler characteristic of a sphere.
+        assert_eq!(faces.nrows(), 2 * NUM_POINTS - 4);
+
+        for face in faces.rows() {
+            let (p0, p1, p2) = (
+                mesh.points[face[0]],
+                mesh.points[face[1]],
+                mesh.points[face[2]],
+            );
+            let centroid = (p0 + p1 + p2) / 3.0;
+            assert!((centroid.norm() - 1.0).abs() < 0.02);
+            assert!((p1 - p0).cross(&(p2 - p0)).dot(&centroid) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_requires_normals() {
+        let mut pcl = sample_sphere(10);
+        pcl.normals = None;
+        assert!(matches!(
+            pcl.reconstruct_mesh(ReconParams::new(0.5)),
+            Err(A3dError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,482 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use nalgebra::{Matrix4, Vector2, Vector3, Vector4};
+use ndarray::{Array1, Array2, Axis};
+use ordered_float::OrderedFloat;
+
+use crate::io::Geometry;
+
+/// Error quadric of a vertex, i.e., the sum of $pp^t$ for the planes $p$ around it.
+type Quadric = Matrix4<f64>;
+
+/// Edge collapse candidate: cost, the edge's vertices and their versions when it was queued.
+/// The order is reversed, so the binary heap pops the smallest cost first.
+type CollapseCandidate = (Reverse<OrderedFloat<f64>>, usize, usize, u32, u32);
+
+/// Weight of the planes that keep the boundary edges in place, relative to the face planes.
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// Quadric of the squared distance to a plane.
+fn plane_quadric(normal: &Vector3<f64>, point: &Vector3<f64>, weight: f64) -> Quadric {
+    let plane = Vector4::new(normal[0], normal[1], normal[2], -normal.dot(point));
+    plane * plane.transpose() * weight
+}
+
+/// Evaluates the quadric error at a position.
+fn quadric_error(quadric: &Quadric, position: &Vector3<f64>) -> f64 {
+    let v = position.push(1.0);
+    (v.transpose() * quadric * v)[0].max(0.0)
+}
+
+/// Vertex attributes that are interpolated when collapsing edges.
+struct VertexAttributes {
+    colors: Option<Vec<Vector3<f32>>>,
+    normals: Option<Vec<Vector3<f32>>>,
+    confidences: Option<Vec<f32>>,
+    texcoords: Option<Vec<Vector2<f32>>>,
+}
+
+impl VertexAttributes {
+    /// Sets the attributes of `dest` to the interpolation between `dest` and `src`.
+    fn interpolate(&mut self, dest: usize, src: usize, t: f32) {
+        if let Some(colors) = self.colors.as_mut() {
+            colors[dest] = colors[dest].lerp(&colors[src], t);
+        }
+        if let Some(normals) = self.normals.as_mut() {
+            normals[dest] = normals[dest].lerp(&normals[src], t);
+        }
+        if let Some(confidences) = self.confidences.as_mut() {
+            confidences[dest] += (confidences[src] - confidences[dest]) * t;
+        }
+        if let Some(texcoords) = self.texcoords.as_mut() {
+            texcoords[dest] = texcoords[dest].lerp(&texcoords[src], t);
+        }
+    }
+}
+
+/// Mesh state during the edge collapses.
+struct Decimator {
+    positions: Vec<Vector3<f64>>,
+    quadrics: Vec<Quadric>,
+    removed: Vec<bool>,
+    /// Incremented when a vertex changes, invalidating its collapses in the queue.
+    versions: Vec<u32>,
+    faces: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    num_faces: usize,
+    vertex_faces: Vec<Vec<usize>>,
+    attributes: VertexAttributes,
+    queue: BinaryHeap<CollapseCandidate>,
+}
+
+impl Decimator {
+    fn new(geometry: &Geometry, faces: &Array2<usize>) -> Self {
+        let positions = geometry
+            .points
+            .iter()
+            .map(|p| nalgebra::convert::<_, Vector3<f64>>(*p))
+            .collect::<Vec<_>>();
+        let faces = faces
+            .axis_iter(Axis(0))
+            .map(|face| [face[0], face[1], face[2]])
+            .collect::<Vec<_>>();
+
+        let mut quadrics = vec![Quadric::zeros(); positions.len()];
+        let mut vertex_faces = vec![Vec::new(); positions.len()];
+        let mut edge_faces = HashMap::<(usize, usize), Vec<usize>>::new();
+        for (face_index, face) in faces.iter().enumerate() {
+            let (p0, p1, p2) = (positions[face[0]], positions[face[1]], positions[face[2]]);
+            let cross = (p1 - p0).cross(&(p2 - p0));
+            if let Some(normal) = cross.try_normalize(0.0) {
+                // Weights by the area.
+                let quadric = plane_quadric(&normal, &p0, cross.norm() * 0.5);
+                for vertex in face {
+                    quadrics[*vertex] += quadric;
+                }
+            }
+
+            for (i, vertex) in face.iter().enumerate() {
+                vertex_faces[*vertex].push(face_index);
+                let next = face[(i + 1) % 3];
+                edge_faces
+                    .entry((*vertex.min(&next), *vertex.max(&next)))
+                    .or_default()
+                    .push(face_index);
+            }
+        }
+
+        // Boundary edges get a plane perpendicular to their face, so they
+        // don't collapse inwards.
+        for ((a, b), edge_faces) in edge_faces.iter() {
+            if edge_faces.len() != 1 {
+                continue;
+            }
+            let face = faces[edge_faces[0]];
+            let (p0, p1, p2) = (positions[face[0]], positions[face[1]], positions[face[2]]);
+            let edge = positions[*b] - positions[*a];
+            if let Some(normal) = (p1 - p0).cross(&(p2 - p0)).cross(&edge).try_normalize(0.0) {
+                let quadric = plane_quadric(
+                    &normal,
+                    &positions[*a],
+                    BOUNDARY_WEIGHT * edge.norm_squared(),
+                );
+                quadrics[*a] += quadric;
+                quadrics[*b] += quadric;
+            }
+        }
+
+        let attributes = VertexAttributes {
+            colors: geometry
+                .colors
+                .as_ref()
+                .map(|colors| colors.iter().map(|c| nalgebra::convert(*c)).collect()),
+            normals: geometry.normals.as_ref().map(|normals| normals.to_vec()),
+            confidences: geometry.confidences.as_ref().map(|conf| conf.to_vec()),
+            texcoords: geometry
+                .texcoords
+                .as_ref()
+                .map(|texcoords| texcoords.to_vec()),
+        };
+
+        let num_vertices = positions.len();
+        let mut decimator = Self {
+            positions,
+            quadrics,
+            removed: vec![false; num_vertices],
+            versions: vec![0; num_vertices],
+            num_faces: faces.len(),
+            face_alive: vec![true; faces.len()],
+            faces,
+            vertex_faces,
+            attributes,
+            queue: BinaryHeap::new(),
+        };
+        for (a, b) in edge_faces.into_keys() {
+            decimator.push_edge(a, b);
+        }
+        decimator
+    }
+
+    /// Finds the position that minimizes the quadric error of collapsing an edge.
+    ///
+    /// # Returns
+    ///
+    /// The position and its error.
+    fn collapse_position(&self, a: usize, b: usize) -> (Vector3<f64>, f64) {
+        let quadric = self.quadrics[a] + self.quadrics[b];
+        let optimal = quadric
+            .fixed_slice::<3, 3>(0, 0)
+            .into_owned()
+            .try_inverse()
+            .map(|inverse| -(inverse * quadric.fixed_slice::<3, 1>(0, 3)));
+
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        optimal
+            .into_iter()
+            .chain([pa, pb, (pa + pb) * 0.5])
+            .map(|position| (position, quadric_error(&quadric, &position)))
+            .min_by(|(_, e1), (_, e2)| e1.total_cmp(e2))
+            .unwrap()
+    }
+
+    fn push_edge(&mut self, a: usize, b: usize) {
+        let (_, cost) = self.collapse_position(a, b);
+        self.queue.push((
+            Reverse(OrderedFloat(cost)),
+            a,
+            b,
+            self.versions[a],
+            self.versions[b],
+        ));
+    }
+
+    fn alive_faces(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_faces[vertex]
+            .iter()
+            .copied()
+            .filter(|face| self.face_alive[*face])
+    }
+
+    fn neighbors(&self, vertex: usize) -> Vec<usize> {
+        let mut neighbors = self
+            .alive_faces(vertex)
+            .flat_map(|face| self.faces[face])
+            .filter(|other| *other != vertex)
+            .collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Checks whether collapsing the edge keeps the mesh manifold and doesn't flip faces.
+    fn can_collapse(&self, a: usize, b: usize, position: &Vector3<f64>) -> bool {
+        // Link condition: the common neighbors must be the opposite vertices of the edge's faces.
+        let neighbors_a = self.neighbors(a);
+        let num_common = self
+            .neighbors(b)
+            .iter()
+            .filter(|n| neighbors_a.binary_search(n).is_ok())
+            .count();
+        let num_edge_faces = self
+            .alive_faces(a)
+            .filter(|face| self.faces[*face].contains(&b))
+            .count();
+        if num_common != num_edge_faces {
+            return false;
+        }
+
+        self.alive_faces(a)
+            .chain(self.alive_faces(b))
+            .filter(|face| !(self.faces[*face].contains(&a) && self.faces[*face].contains(&b)))
+            .all(|face| {
+                let vertices = self.faces[face];
+                let old = vertices.map(|v| self.positions[v]);
+                let new = vertices.map(|v| {
+                    if v == a || v == b {
+                        *position
+                    } else {
+                        self.positions[v]
+                    }
+                });
+                let old_normal = (old[1] - old[0]).cross(&(old[2] - old[0]));
+                let new_normal = (new[1] - new[0]).cross(&(new[2] - new[0]));
+                old_normal.dot(&new_normal) > 0.0
+            })
+    }
+
+    /// Moves `a` to `position` and merges `b` into it.
+    fn collapse(&mut self, a: usize, b: usize, position: Vector3<f64>) {
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let edge = pb - pa;
+        let t = if edge.norm_squared() > 0.0 {
+            ((position - pa).dot(&edge) / edge.norm_squared()).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.attributes.interpolate(a, b, t as f32);
+
+        self.positions[a] = position;
+        self.quadrics[a] = self.quadrics[a] + self.quadrics[b];
+        self.removed[b] = true;
+        self.versions[a] += 1;
+        self.versions[b] += 1;
+
+        let b_faces = std::mem::take(&mut self.vertex_faces[b]);
+        for face in b_faces {
+            if !self.face_alive[face] {
+                continue;
+            }
+            if self.faces[face].contains(&a) {
+                self.face_alive[face] = false;
+                self.num_faces -= 1;
+            } else {
+                for vertex in self.faces[face].iter_mut() {
+                    if *vertex == b {
+                        *vertex = a;
+                    }
+                }
+                self.vertex_faces[a].push(face);
+            }
+        }
+        let face_alive = &self.face_alive;
+        self.vertex_faces[a].retain(|face| face_alive[*face]);
+
+        for neighbor in self.neighbors(a) {
+            self.push_edge(a, neighbor);
+        }
+    }
+
+    fn run(&mut self, target_faces: usize) {
+        while self.num_faces > target_faces {
+            let Some((_, a, b, version_a, version_b)) = self.queue.pop() else {
+                break;
+            };
+            if self.removed[a]
+                || self.removed[b]
+                || self.versions[a] != version_a
+                || self.versions[b] != version_b
+            {
+                continue;
+            }
+
+            let (position, _) = self.collapse_position(a, b);
+            if self.can_collapse(a, b, &position) {
+                self.collapse(a, b, position);
+            }
+        }
+    }
+
+    /// Creates the geometry with the remaining vertices and faces.
+    fn into_geometry(self) -> Geometry {
+        let mut new_indices = vec![usize::MAX; self.positions.len()];
+        let mut vertices = Vec::new();
+        let mut faces = Vec::with_capacity(self.num_faces * 3);
+        for (face, alive) in self.faces.iter().zip(self.face_alive.iter()) {
+            if !alive {
+                continue;
+            }
+            for vertex in face {
+                if new_indices[*vertex] == usize::MAX {
+                    new_indices[*vertex] = vertices.len();
+                    vertices.push(*vertex);
+                }
+                faces.push(new_indices[*vertex]);
+            }
+        }
+
+        let attributes = self.attributes;
+        Geometry {
+            points: vertices
+                .iter()
+                .map(|v| nalgebra::convert(self.positions[*v]))
+                .collect(),
+            colors: attributes.colors.map(|colors| {
+                vertices
+                    .iter()
+                    .map(|v| colors[*v].map(|c| c.round().clamp(0.0, 255.0) as u8))
+                    .collect()
+            }),
+            normals: attributes.normals.map(|normals| {
+                vertices
+                    .iter()
+                    .map(|v| normals[*v].try_normalize(0.0).unwrap_or(normals[*v]))
+                    .collect()
+            }),
+            confidences: attributes
+                .confidences
+                .map(|confidences| vertices.iter().map(|v| confidences[*v]).collect()),
+            faces: Some(Array2::from_shape_vec((faces.len() / 3, 3), faces).unwrap()),
+            texcoords: attributes.texcoords.map(|texcoords| {
+                vertices
+                    .iter()
+                    .map(|v| texcoords[*v])
+                    .collect::<Array1<_>>()
+            }),
+        }
+    }
+}
+
+impl Geometry {
+    /// Reduces the number of faces by collapsing edges with the quadric error metric, as in
+    /// Garland and Heckbert, Surface Simplification Using Quadric Error Metrics, SIGGRAPH 1997.
+    /// Boundary edges are constrained to stay in place, and collapses that would flip faces or
+    /// make the mesh non-manifold are skipped. Colors, normals, confidences and texture
+    /// coordinates are interpolated at the collapsed vertices.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_faces` - Desired number of faces.
+    ///
+    /// # Returns
+    ///
+    /// The decimated geometry, with at most `target_faces` faces unless no more edges can be
+    /// collapsed. Unused vertices are removed. A copy of `self` if it has no faces.
+    pub fn decimate(&self, target_faces: usize) -> Geometry {
+        let Some(faces) = self.faces.as_ref() else {
+            return self.clone();
+        };
+
+        let mut decimator = Decimator::new(self, faces);
+        decimator.run(target_faces);
+        decimator.into_geometry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use ndarray::{Array1, Array2};
+
+    use crate::{
+        io::{Geometry, GeometryBuilder},
+        metrics::hausdorff_distance,
+        pointcloud::PointCloud,
+    };
+
+    /// Height of the test surface, a smooth bump over the unit square.
+    fn height(x: f32, y: f32) -> f32 {
+        0.1 * (x * std::f32::consts::PI).sin() * (y * std::f32::consts::PI).sin()
+    }
+
+    /// Grid over the unit square with `cells x cells` quads split into two triangles.
+    fn subdivided_plane(cells: usize) -> Geometry {
+        let mut points = Vec::new();
+        let mut colors = Vec::new();
+        for i in 0..=cells {
+            for j in 0..=cells {
+                let (x, y) = (j as f32 / cells as f32, i as f32 / cells as f32);
+                points.push(Vector3::new(x, y, height(x, y)));
+                colors.push(Vector3::new((x * 255.0) as u8, 0, 0));
+            }
+        }
+
+        let mut faces = Vec::new();
+        for i in 0..cells {
+            for j in 0..cells {
+                let v00 = i * (cells + 1) + j;
+                let (v01, v10, v11) = (v00 + 1, v00 + cells + 1, v00 + cells + 2);
+                faces.extend([v00, v01, v11, v00, v11, v10]);
+            }
+        }
+
+        GeometryBuilder::new(Array1::from_vec(points))
+            .with_colors(Array1::from_vec(colors))
+            .with_faces(Array2::from_shape_vec((cells * cells * 2, 3), faces).unwrap())
+            .build()
+    }
+
+    #[test]
+    fn test_decimate() {
+        let plane = subdivided_plane(20);
+        assert_eq!(plane.len_faces(), 800);
+
+        let decimated = plane.decimate(100);
+        let num_faces = decimated.len_faces();
+        assert!((90..=100).contains(&num_faces), "{num_faces}");
+        assert!(decimated.len_vertices() < plane.len_vertices());
+        assert!(decimated
+            .faces
+            .as_ref()
+            .unwrap()
+            .iter()
+            .all(|index| *index < decimated.len_vertices()));
+
+        // Keeps the boundary and the shape.
+        let (min, max) = decimated.bounding_box().unwrap();
+        assert!((min - Vector3::new(0.0, 0.0, 0.0)).xy().norm() < 1e-3);
+        assert!((max - Vector3::new(1.0, 1.0, 0.0)).xy().norm() < 1e-3);
+        for point in decimated.points.iter() {
+            assert!(
+                (point[2] - height(point[0], point[1])).abs() < 0.02,
+                "{point}"
+            );
+        }
+        let hausdorff = hausdorff_distance(
+            &PointCloud::from_geometry(plane.clone()),
+            &PointCloud::from_geometry(decimated.clone()),
+        );
+        assert!(hausdorff < 0.2, "{hausdorff}");
+
+        // Interpolates the colors.
+        for (point, color) in decimated
+            .points
+            .iter()
+            .zip(decimated.colors.as_ref().unwrap().iter())
+        {
+            assert!(
+                (color[0] as f32 - point[0] * 255.0).abs() < 16.0,
+                "{point} {color}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decimate_without_faces() {
+        let mut plane = subdivided_plane(2);
+        plane.faces = None;
+        assert_eq!(plane.decimate(1).len_vertices(), plane.len_vertices());
+    }
+}
@@ -1,3 +1,9 @@
+mod ball_pivoting;
+mod decimation;
+mod surface_sampling;
+
+pub use ball_pivoting::ReconParams;
+
 use nalgebra::Vector3;
 use ndarray::{Array1, ArrayView1, ArrayView2, Axis};
 
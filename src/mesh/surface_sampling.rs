@@ -0,0 +1,178 @@
+use nalgebra::Vector3;
+use ndarray::{Array1, Axis};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{io::Geometry, pointcloud::PointCloud};
+
+impl Geometry {
+    /// Samples points uniformly on the surface of the triangles. Each triangle is chosen
+    /// with probability proportional to its area, and the point uniformly inside it.
+    /// Use it to compare meshes against point clouds, e.g., with Chamfer distance or ICP.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Number of points.
+    /// * `seed` - Seed of the random generator, the same seed samples the same points.
+    ///
+    /// # Returns
+    ///
+    /// The point cloud. Normals are interpolated from the vertex normals, or are the face
+    /// normals if the geometry has no normals. Colors and confidences are interpolated if
+    /// present. Empty if the geometry has no faces or their area is zero.
+    pub fn sample_surface(&self, n: usize, seed: u64) -> PointCloud {
+        let faces = match self.faces.as_ref() {
+            Some(faces) => faces
+                .axis_iter(Axis(0))
+                .map(|f| [f[0], f[1], f[2]])
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // Cumulative triangle areas (doubled).
+        let cumulative_areas = faces
+            .iter()
+            .scan(0.0, |total, face: &[usize; 3]| {
+                let [p0, p1, p2] = face.map(|v| self.points[v]);
+                *total += (p1 - p0).cross(&(p2 - p0)).norm();
+                Some(*total)
+            })
+            .collect::<Vec<f32>>();
+        let total_area = cumulative_areas.last().copied().unwrap_or(0.0);
+        let n = if total_area > 0.0 { n } else { 0 };
+
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let samples = (0..n)
+            .map(|_| {
+                let area = rng.gen_range(0.0..total_area);
+                let face_index = cumulative_areas
+                    .partition_point(|cumulative| *cumulative <= area)
+                    .min(faces.len() - 1);
+                // Uniform barycentric coordinates.
+                let (r1, r2) = (rng.gen::<f32>().sqrt(), rng.gen::<f32>());
+                (faces[face_index], [1.0 - r1, r1 * (1.0 - r2), r1 * r2])
+            })
+            .collect::<Vec<_>>();
+
+        let interpolate = |values: &Array1<Vector3<f32>>, face: &[usize; 3], weights: &[f32; 3]| {
+            values[face[0]] * weights[0]
+                + values[face[1]] * weights[1]
+                + values[face[2]] * weights[2]
+        };
+
+        PointCloud {
+            points: samples
+                .iter()
+                .map(|(face, weights)| interpolate(&self.points, face, weights))
+                .collect(),
+            normals: Some(
+                samples
+                    .iter()
+                    .map(|(face, weights)| {
+                        let normal = match self.normals.as_ref() {
+                            Some(normals) => interpolate(normals, face, weights),
+                            None => {
+                                let [p0, p1, p2] = face.map(|v| self.points[v]);
+                                (p1 - p0).cross(&(p2 - p0))
+                            }
+                        };
+                        normal.try_normalize(0.0).unwrap_or(normal)
+                    })
+                    .collect(),
+            ),
+            colors: self.colors.as_ref().map(|colors| {
+                samples
+                    .iter()
+                    .map(|(face, weights)| {
+                        let color = face
+                            .iter()
+                            .zip(weights)
+                            .map(|(v, w)| nalgebra::convert::<_, Vector3<f32>>(colors[*v]) * *w)
+                            .sum::<Vector3<f32>>();
+                        color.map(|c| c.round().clamp(0.0, 255.0) as u8)
+                    })
+                    .collect()
+            }),
+            confidences: self.confidences.as_ref().map(|confidences| {
+                samples
+                    .iter()
+                    .map(|(face, weights)| {
+                        face.iter()
+                            .zip(weights)
+                            .map(|(v, w)| confidences[*v] * w)
+                            .sum()
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use ndarray::{array, Array1};
+
+    use crate::io::GeometryBuilder;
+
+    #[test]
+    fn test_sample_surface() {
+        // Unit square on the XY plane, with a red gradient along X. Its triangles have different areas.
+        let square = GeometryBuilder::new(array![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.5, 0.0, 0.0),
+        ])
+        .with_colors(array![
+            Vector3::new(0, 0, 0),
+            Vector3::new(200, 0, 0),
+            Vector3::new(200, 0, 0),
+            Vector3::new(0, 0, 0),
+            Vector3::new(100, 0, 0),
+        ])
+        .with_faces(array![[0, 4, 2], [4, 1, 2], [0, 2, 3]])
+        .build();
+
+        let pcl = square.sample_surface(4000, 5);
+        assert_eq!(pcl.len(), 4000);
+        assert!(pcl
+            .points
+            .iter()
+            .all(|p| (0.0..=1.0).contains(&p[0]) && (0.0..=1.0).contains(&p[1]) && p[2] == 0.0));
+        assert!(pcl
+            .normals
+            .as_ref()
+            .unwrap()
+            .iter()
+            .all(|n| (n - Vector3::z()).norm() < 1e-6));
+        for (point, color) in pcl.points.iter().zip(pcl.colors.as_ref().unwrap().iter()) {
+            assert!((color[0] as f32 - point[0] * 200.0).abs() <= 1.0);
+        }
+
+        // Roughly uniform density: each cell of a 4x4 grid gets about 1/16 of the points.
+        let mut counts = Array1::<usize>::zeros(16);
+        for point in pcl.points.iter() {
+            let (i, j) = (
+                ((point[0] * 4.0) as usize).min(3),
+                ((point[1] * 4.0) as usize).min(3),
+            );
+            counts[i * 4 + j] += 1;
+        }
+        assert!(
+            counts.iter().all(|count| (200..=300).contains(count)),
+            "{counts}"
+        );
+
+        assert_eq!(
+            square.sample_surface(10, 5).points,
+            square.sample_surface(10, 5).points
+        );
+    }
+
+    #[test]
+    fn test_sample_surface_without_faces() {
+        let points = GeometryBuilder::new(array![Vector3::new(0.0, 0.0, 0.0)]).build();
+        assert!(points.sample_surface(10, 5).is_empty());
+    }
+}
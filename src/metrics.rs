@@ -1,4 +1,7 @@
-use crate::{error::A3dError, trajectory::Trajectory, transform::Transform};
+use crate::{
+    error::A3dError, kdtree::R3dTree, pointcloud::PointCloud, trajectory::Trajectory,
+    transform::Transform,
+};
 
 /// Metrics for comparing two transforms.
 #[derive(Clone, Debug)]
@@ -68,11 +71,159 @@ impl std::fmt::Display for TransformMetrics {
     }
 }
 
+/// Returns the squared distances from each point of `source` to its nearest neighbor in `target`.
+fn nearest_squared_distances<'a>(
+    source: &'a PointCloud,
+    target: &PointCloud,
+) -> impl Iterator<Item = f32> + 'a {
+    let tree = R3dTree::new(&target.points.view());
+    source
+        .points
+        .iter()
+        .map(move |point| tree.nearest_exact(point).1)
+}
+
+/// Mean distance from the points of `source` to their nearest neighbors in `target`.
+///
+/// # Arguments
+///
+/// * `source` - The query point cloud.
+/// * `target` - The reference point cloud.
+///
+/// # Returns
+///
+/// The mean distance, or `f32::INFINITY` if any of the clouds is empty.
+pub fn chamfer_distance_oneway(source: &PointCloud, target: &PointCloud) -> f32 {
+    if source.is_empty() || target.is_empty() {
+        return f32::INFINITY;
+    }
+    nearest_squared_distances(source, target)
+        .map(f32::sqrt)
+        .sum::<f32>()
+        / source.len() as f32
+}
+
+/// Symmetric Chamfer distance, i.e., the mean of the nearest neighbor distances
+/// from `a` to `b` and from `b` to `a`. Used to compare a reconstruction against its ground truth.
+///
+/// # Arguments
+///
+/// * `a` - A point cloud.
+/// * `b` - Another point cloud.
+///
+/// # Returns
+///
+/// The Chamfer distance, or `f32::INFINITY` if any of the clouds is empty.
+pub fn chamfer_distance(a: &PointCloud, b: &PointCloud) -> f32 {
+    (chamfer_distance_oneway(a, b) + chamfer_distance_oneway(b, a)) * 0.5
+}
+
+/// Symmetric Chamfer distance using squared nearest neighbor distances.
+/// See [`chamfer_distance`].
+pub fn chamfer_distance_squared(a: &PointCloud, b: &PointCloud) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return f32::INFINITY;
+    }
+    let oneway = |source, target| {
+        nearest_squared_distances(source, target).sum::<f32>() / source.len() as f32
+    };
+    (oneway(a, b) + oneway(b, a)) * 0.5
+}
+
+/// Directed Hausdorff distance, i.e., the largest distance from a point of `source`
+/// to its nearest neighbor in `target`.
+///
+/// # Arguments
+///
+/// * `source` - The query point cloud.
+/// * `target` - The reference point cloud.
+///
+/// # Returns
+///
+/// The directed Hausdorff distance, or `f32::INFINITY` if any of the clouds is empty.
+pub fn hausdorff_directed(source: &PointCloud, target: &PointCloud) -> f32 {
+    if source.is_empty() || target.is_empty() {
+        return f32::INFINITY;
+    }
+    nearest_squared_distances(source, target)
+        .fold(0.0, f32::max)
+        .sqrt()
+}
+
+/// Hausdorff distance, the maximum of the directed Hausdorff distances in both directions.
+/// Unlike [`chamfer_distance`], it captures the worst case error, like outliers or missing geometry.
+///
+/// # Arguments
+///
+/// * `a` - A point cloud.
+/// * `b` - Another point cloud.
+///
+/// # Returns
+///
+/// The Hausdorff distance, or `f32::INFINITY` if any of the clouds is empty.
+pub fn hausdorff_distance(a: &PointCloud, b: &PointCloud) -> f32 {
+    hausdorff_directed(a, b).max(hausdorff_directed(b, a))
+}
+
 #[cfg(test)]
 mod tests {
     use nalgebra::{Quaternion, Vector3};
+    use rstest::rstest;
 
     use super::*;
+    use crate::unit_test::sample_teapot_pointcloud;
+
+    #[rstest]
+    fn test_chamfer_distance(sample_teapot_pointcloud: PointCloud) {
+        assert_eq!(
+            chamfer_distance(&sample_teapot_pointcloud, &sample_teapot_pointcloud),
+            0.0
+        );
+
+        let translation = Vector3::new(0.001, 0.0, 0.0);
+        let translated =
+            &Transform::new(&translation, &Quaternion::identity()) * &sample_teapot_pointcloud;
+        let distance = chamfer_distance(&sample_teapot_pointcloud, &translated);
+        assert!((distance - translation.norm()).abs() < 1e-4, "{distance}");
+        let distance = chamfer_distance_squared(&sample_teapot_pointcloud, &translated);
+        assert!(
+            (distance - translation.norm_squared()).abs() < 1e-6,
+            "{distance}"
+        );
+
+        assert_eq!(
+            chamfer_distance(&sample_teapot_pointcloud, &PointCloud::zeros(0)),
+            f32::INFINITY
+        );
+    }
+
+    #[rstest]
+    fn test_hausdorff_distance(sample_teapot_pointcloud: PointCloud) {
+        let mut with_flyer = PointCloud::zeros(sample_teapot_pointcloud.len() + 1);
+        with_flyer
+            .points
+            .slice_mut(ndarray::s![..-1])
+            .assign(&sample_teapot_pointcloud.points);
+        with_flyer.points[sample_teapot_pointcloud.len()] = Vector3::new(10.0, 10.0, 10.0);
+
+        assert_eq!(
+            hausdorff_distance(&sample_teapot_pointcloud, &sample_teapot_pointcloud),
+            0.0
+        );
+        assert_eq!(
+            hausdorff_directed(&with_flyer, &sample_teapot_pointcloud),
+            hausdorff_distance(&sample_teapot_pointcloud, &with_flyer)
+        );
+        assert_eq!(
+            hausdorff_directed(&sample_teapot_pointcloud, &with_flyer),
+            0.0
+        );
+
+        let hausdorff = hausdorff_distance(&sample_teapot_pointcloud, &with_flyer);
+        let chamfer = chamfer_distance(&sample_teapot_pointcloud, &with_flyer);
+        assert!(hausdorff > 15.0, "{hausdorff}");
+        assert!(chamfer < 0.02, "{chamfer}");
+    }
 
     #[test]
     fn test_transform_metrics() {
@@ -6,6 +6,7 @@ use num::Zero;
 /// # Type parameters
 ///
 /// * `DIM` - The dimension of the problem.
+#[derive(Clone)]
 pub struct GaussNewton<const DIM: usize> {
     hessian: SMatrix<f32, DIM, DIM>,
     gradient: SVector<f32, DIM>,
@@ -80,7 +81,8 @@ impl<const DIM: usize> GaussNewton<DIM> {
     ///
     /// # Returns
     ///
-    /// The update vector.
+    /// The update vector, or `None` if there are no constraints or the system is singular,
+    /// e.g., under-constrained.
     pub fn solve(&self) -> Option<SVector<f32, DIM>> {
         if self.count == 0 {
             return None;
@@ -92,6 +94,24 @@ impl<const DIM: usize> GaussNewton<DIM> {
             .map(|cholesky| nalgebra::convert(cholesky.solve(&gradient)))
     }
 
+    /// Approximates the covariance of the solution as the inverse of the accumulated
+    /// $J^tJ$ scaled by the residual variance.
+    ///
+    /// # Returns
+    ///
+    /// The covariance matrix, or `None` if the Hessian is singular.
+    pub fn covariance(&self) -> Option<SMatrix<f32, DIM, DIM>> {
+        if self.count == 0 {
+            return None;
+        }
+        let hessian: SMatrix<f64, DIM, DIM> = nalgebra::convert(self.hessian);
+        let variance =
+            self.squared_residual_sum as f64 / self.count.saturating_sub(DIM).max(1) as f64;
+
+        Cholesky::<f64, Const<DIM>>::new(hessian)
+            .map(|cholesky| nalgebra::convert(cholesky.inverse() * variance))
+    }
+
     /// Adds the values of another optimizer to this one.
     /// Use this to combine the state of sub optimizers.
     ///
@@ -105,19 +125,59 @@ impl<const DIM: usize> GaussNewton<DIM> {
         self.count += other.count;
     }
 
-    /// Adds the values of another optimizer to this one.
-    /// Use this to combine the state of sub optimizers.
+    /// Adds the values of another optimizer to this one, scaled by a cost weight.
+    /// The Hessian, gradient, and squared residual sum of `other` are scaled linearly,
+    /// so the result is the system of the cost `E_self + scale * E_other`.
+    /// Use this to combine cost terms (e.g., depth, color, edges) with independent weights,
+    /// see [`Self::combine`].
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other optimizer.
+    /// * `scale` - The weight of the other optimizer's cost.
+    pub fn add_scaled(&mut self, other: &Self, scale: f32) {
+        self.hessian += other.hessian * scale;
+        self.gradient += other.gradient * scale;
+        self.squared_residual_sum += other.squared_residual_sum * scale;
+        self.count += other.count;
+    }
+
+    /// Combines multiple cost terms into a single system, as in [`Self::add_scaled`].
+    ///
+    /// # Arguments
+    ///
+    /// * `terms` - Pairs of optimizer and the weight of its cost.
+    ///
+    /// # Returns
+    ///
+    /// The system of the cost `sum(weight_i * E_i)`.
+    pub fn combine<'a, I>(terms: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a Self, f32)>,
+    {
+        terms
+            .into_iter()
+            .fold(Self::new(), |mut combined, (term, scale)| {
+                combined.add_scaled(term, scale);
+                combined
+            })
+    }
+
+    /// Adds the values of another optimizer to this one, weighting both costs.
+    /// The system of each optimizer is scaled linearly by its weight, as in
+    /// [`Self::add_scaled`], so the result is the system of the cost
+    /// `weight1 * E_self + weight2 * E_other`.
+    ///
     /// # Arguments
     ///
     /// * `other` - The other optimizer.
     /// * `weight1` - The weight of this optimizer.
     /// * `weight2` - The weight of the other optimizer.
     pub fn add_weighted(&mut self, other: &Self, weight1: f32, weight2: f32) {
-        self.hessian = self.hessian * (weight1 * weight1) + other.hessian * (weight2 * weight2);
-        self.gradient = self.gradient * weight1 + other.gradient * weight2;
-        self.squared_residual_sum =
-            self.squared_residual_sum * weight1 + other.squared_residual_sum * weight2;
-        self.count += other.count;
+        self.hessian *= weight1;
+        self.gradient *= weight1;
+        self.squared_residual_sum *= weight1;
+        self.add_scaled(other, weight2);
     }
 
     /// Weights the optimizer.
@@ -131,6 +191,32 @@ impl<const DIM: usize> GaussNewton<DIM> {
     pub fn mean_squared_residual(&self) -> f32 {
         self.squared_residual_sum / self.count as f32
     }
+
+    /// Returns the number of constraints, i.e., calls to [`Self::step`] since the last reset.
+    /// Use it to detect under-constrained systems, which need at least `DIM` constraints.
+    pub fn num_constraints(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the sum of the squared residuals.
+    pub fn squared_residual_sum(&self) -> f32 {
+        self.squared_residual_sum
+    }
+
+    /// Returns the norm of the residual vector, i.e., the square root of [`Self::squared_residual_sum`].
+    pub fn residual_norm(&self) -> f32 {
+        self.squared_residual_sum.sqrt()
+    }
+
+    /// Returns the accumulated approximated Hessian, i.e., $J^tJ$.
+    pub fn hessian(&self) -> &SMatrix<f32, DIM, DIM> {
+        &self.hessian
+    }
+
+    /// Returns the accumulated gradient, i.e., $J^tr$.
+    pub fn gradient(&self) -> &SVector<f32, DIM> {
+        &self.gradient
+    }
 }
 
 #[cfg(test)]
@@ -165,4 +251,117 @@ mod tests {
         let expected_gradient = array![6.0, 12.0, 18.0, 24.0, 30.0, 36.0].into_nalgebra();
         assert_eq!(gradient, expected_gradient);
     }
+
+    #[test]
+    fn test_covariance() {
+        use super::*;
+
+        let mut gn = GaussNewton::<3>::new();
+        for i in 0..30 {
+            let residual = if i % 2 == 0 { 0.01 } else { -0.01 };
+            gn.step(residual, &[1.0, 0.0, 0.0]);
+            gn.step(residual, &[0.0, 1.0, 0.0]);
+            gn.step(residual, &[0.0, 0.0, 1.0]);
+        }
+        let covariance = gn.covariance().unwrap();
+        assert!(covariance.trace() < 1e-4);
+
+        let mut gn = GaussNewton::<3>::new();
+        for _ in 0..30 {
+            gn.step(0.01, &[1.0, 1.0, 0.0]);
+        }
+        assert!(gn.covariance().is_none());
+    }
+
+    #[test]
+    fn test_solve_without_constraints() {
+        use super::*;
+
+        let mut gn = GaussNewton::<3>::new();
+        assert_eq!(gn.num_constraints(), 0);
+        assert!(gn.solve().is_none());
+
+        gn.step(3.0, &[1.0, 0.0, 0.0]);
+        gn.step(4.0, &[0.0, 1.0, 0.0]);
+        assert_eq!(gn.num_constraints(), 2);
+        assert_eq!(gn.squared_residual_sum(), 25.0);
+        assert_eq!(gn.residual_norm(), 5.0);
+        // Under-constrained.
+        assert!(gn.solve().is_none());
+
+        gn.reset();
+        assert_eq!(gn.num_constraints(), 0);
+        assert_eq!(gn.residual_norm(), 0.0);
+    }
+
+    #[test]
+    fn test_add_scaled() {
+        use super::*;
+
+        // Each term alone moves the solution to its own target.
+        let mut first = GaussNewton::<1>::new();
+        first.step(2.0, &[1.0]);
+        let mut second = GaussNewton::<1>::new();
+        second.step(6.0, &[1.0]);
+        assert_eq!(first.solve().unwrap()[0], 2.0);
+        assert_eq!(second.solve().unwrap()[0], 6.0);
+
+        // Combined, the solution is the weighted mean of the targets.
+        for scale in [0.0, 0.5, 1.0, 3.0] {
+            let mut combined = first.clone();
+            combined.add_scaled(&second, scale);
+            let expected = (2.0 + 6.0 * scale) / (1.0 + scale);
+            assert!((combined.solve().unwrap()[0] - expected).abs() < 1e-6);
+            assert_eq!(combined.hessian()[(0, 0)], 1.0 + scale);
+            assert_eq!(combined.gradient()[0], 2.0 + 6.0 * scale);
+            assert_eq!(combined.squared_residual_sum(), 4.0 + 36.0 * scale);
+            assert_eq!(combined.num_constraints(), 2);
+        }
+
+        // Scaling every term by the same factor doesn't change the solution.
+        let mut third = GaussNewton::<1>::new();
+        third.step(-1.0, &[2.0]);
+        let combined = GaussNewton::combine([(&first, 1.0), (&second, 2.0), (&third, 4.0)]);
+        let scaled = GaussNewton::combine([(&first, 10.0), (&second, 20.0), (&third, 40.0)]);
+        let expected = (2.0 + 2.0 * 6.0 + 4.0 * -2.0) / (1.0 + 2.0 + 4.0 * 4.0);
+        assert!((combined.solve().unwrap()[0] - expected).abs() < 1e-6);
+        assert!((scaled.solve().unwrap()[0] - expected).abs() < 1e-6);
+        assert_eq!(combined.num_constraints(), 3);
+    }
+
+    #[test]
+    fn test_add_weighted() {
+        use super::*;
+
+        let mut first = GaussNewton::<2>::new();
+        first.step(2.0, &[1.0, 0.5]);
+        first.step(-1.0, &[0.0, 1.0]);
+        let mut second = GaussNewton::<2>::new();
+        second.step(6.0, &[1.0, 0.0]);
+        second.step(3.0, &[0.5, 2.0]);
+
+        let weighted = |weight1, weight2| {
+            let mut combined = first.clone();
+            combined.add_weighted(&second, weight1, weight2);
+            combined
+        };
+
+        // Same system as combining the scaled costs.
+        let combined = weighted(2.0, 0.5);
+        let expected = GaussNewton::combine([(&first, 2.0), (&second, 0.5)]);
+        assert_eq!(combined.hessian(), expected.hessian());
+        assert_eq!(combined.gradient(), expected.gradient());
+        assert_eq!(
+            combined.squared_residual_sum(),
+            expected.squared_residual_sum()
+        );
+        assert_eq!(combined.num_constraints(), 4);
+
+        // Scaling both terms by the same factor doesn't change the solution.
+        let solution = combined.solve().unwrap();
+        for factor in [0.1, 10.0] {
+            let scaled = weighted(2.0 * factor, 0.5 * factor).solve().unwrap();
+            assert!((scaled - solution).norm() < 1e-5, "{scaled} vs {solution}");
+        }
+    }
 }
@@ -0,0 +1,170 @@
+use nalgebra::{Cholesky, Const, SMatrix, SVector};
+
+use super::GaussNewton;
+
+/// Implements the Levenberg-Marquardt optimization. It augments the
+/// Gauss Newton Hessian diagonal with a damping factor `lambda`, that is
+/// adapted according to whether the residual decreased or not.
+///
+/// # Type parameters
+///
+/// * `DIM` - The dimension of the problem.
+pub struct LevenbergMarquardt<const DIM: usize> {
+    system: GaussNewton<DIM>,
+    accepted_system: Option<GaussNewton<DIM>>,
+    accepted_residual: Option<f32>,
+    lambda: f32,
+    /// Multiplier used to increase or decrease `lambda`.
+    pub lambda_factor: f32,
+}
+
+impl<const DIM: usize> Default for LevenbergMarquardt<DIM> {
+    fn default() -> Self {
+        Self::new(1.0e-3)
+    }
+}
+
+impl<const DIM: usize> LevenbergMarquardt<DIM> {
+    /// Creates a new Levenberg-Marquardt optimizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `lambda` - The initial damping factor.
+    pub fn new(lambda: f32) -> Self {
+        Self {
+            system: GaussNewton::new(),
+            accepted_system: None,
+            accepted_residual: None,
+            lambda,
+            lambda_factor: 10.0,
+        }
+    }
+
+    /// Returns the current damping factor.
+    pub fn lambda(&self) -> f32 {
+        self.lambda
+    }
+
+    /// Resets the accumulated system. The damping factor and the last
+    /// accepted step are kept.
+    pub fn reset(&mut self) {
+        self.system.reset();
+    }
+
+    /// Adds a new step to the optimizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `residual` - The residual of the step.
+    /// * `jacobian` - The jacobian of the step.
+    pub fn step(&mut self, residual: f32, jacobian: &[f32; DIM]) {
+        self.system.step(residual, jacobian);
+    }
+
+    /// Adds the system accumulated by a Gauss Newton optimizer to this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The Gauss Newton optimizer.
+    pub fn add(&mut self, other: &GaussNewton<DIM>) {
+        self.system.add(other);
+    }
+
+    /// Solve the current damped system, i.e., `(H + lambda * diag(H)) x = g`.
+    ///
+    /// # Returns
+    ///
+    /// The update vector.
+    pub fn solve(&self) -> Option<SVector<f32, DIM>> {
+        let mut hessian: SMatrix<f64, DIM, DIM> = nalgebra::convert(*self.system.hessian());
+        let gradient: SVector<f64, DIM> = nalgebra::convert(*self.system.gradient());
+        for i in 0..DIM {
+            hessian[(i, i)] *= 1.0 + self.lambda as f64;
+        }
+
+        Cholesky::<f64, Const<DIM>>::new(hessian)
+            .map(|cholesky| nalgebra::convert(cholesky.solve(&gradient)))
+    }
+
+    /// Accepts or rejects the estimate on which the current system was accumulated.
+    ///
+    /// If `current_residual` is lower than the residual of the last accepted estimate,
+    /// the step is accepted, `lambda` is decreased, and the update is returned.
+    /// Otherwise, `lambda` is increased, the system of the last accepted estimate is
+    /// restored and `None` is returned. In that case, the caller should go back to the
+    /// last accepted estimate, and it may call [`Self::solve`] for a more damped update.
+    ///
+    /// # Arguments
+    ///
+    /// * `current_residual` - The residual at the current estimate.
+    ///
+    /// # Returns
+    ///
+    /// The update vector if the step was accepted.
+    pub fn try_step(&mut self, current_residual: f32) -> Option<SVector<f32, DIM>> {
+        let improved = self
+            .accepted_residual
+            .is_none_or(|accepted| current_residual < accepted);
+
+        if improved {
+            self.accepted_residual = Some(current_residual);
+            self.accepted_system = Some(self.system.clone());
+            self.lambda /= self.lambda_factor;
+            self.solve()
+        } else {
+            self.lambda *= self.lambda_factor;
+            if let Some(accepted_system) = &self.accepted_system {
+                self.system = accepted_system.clone();
+            }
+            None
+        }
+    }
+
+    /// Returns the mean squared residual.
+    pub fn mean_squared_residual(&self) -> f32 {
+        self.system.mean_squared_residual()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LevenbergMarquardt;
+    use crate::optim::GaussNewton;
+
+    // The residual `-atan(x)` has its root at zero, but Gauss Newton
+    // overshoots when starting far from it, since its derivative is flat.
+    fn residual_jacobian(x: f32) -> (f32, [f32; 1]) {
+        (-x.atan(), [1.0 / (1.0 + x * x)])
+    }
+
+    #[test]
+    fn test_ill_conditioned() {
+        let mut x = 2.0;
+        let mut gn = GaussNewton::<1>::new();
+        for _ in 0..5 {
+            let (residual, jacobian) = residual_jacobian(x);
+            gn.step(residual, &jacobian);
+            x += gn.solve().unwrap()[0];
+            gn.reset();
+        }
+        assert!(x.abs() > 2.0);
+
+        let mut x = 2.0;
+        let mut accepted_x = x;
+        let mut lm = LevenbergMarquardt::<1>::default();
+        for _ in 0..50 {
+            let (residual, jacobian) = residual_jacobian(x);
+            lm.step(residual, &jacobian);
+            x = match lm.try_step(lm.mean_squared_residual()) {
+                Some(update) => {
+                    accepted_x = x;
+                    x + update[0]
+                }
+                None => accepted_x + lm.solve().unwrap()[0],
+            };
+            lm.reset();
+        }
+
+        assert!(x.abs() < 1e-4);
+    }
+}
@@ -1,5 +1,8 @@
 mod gaussnewton;
 pub use gaussnewton::GaussNewton;
 
+mod levenberg_marquardt;
+pub use levenberg_marquardt::LevenbergMarquardt;
+
 mod robust_estimator;
 pub use robust_estimator::{HuberEstimator, RobustEstimator};
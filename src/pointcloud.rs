@@ -1,14 +1,45 @@
 use crate::{
+    camera::PinholeCamera,
+    error::A3dError,
+    extra_math,
     io::Geometry,
+    range_image::RangeImage,
     transform::{Transform, Transformable},
 };
-use nalgebra::Vector3;
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
 use ndarray::prelude::*;
+use rand::{rngs::SmallRng, SeedableRng};
 
+/// Plane of the points `p` where `normal.dot(p) + d == 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaneModel {
+    /// Unit normal.
+    pub normal: Vector3<f32>,
+    pub d: f32,
+}
+
+impl PlaneModel {
+    /// Signed distance from a point to the plane, positive on the normal's side.
+    pub fn distance(&self, point: &Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.d
+    }
+
+    /// Plane passing through three points. None if they're collinear.
+    fn from_points(p0: &Vector3<f32>, p1: &Vector3<f32>, p2: &Vector3<f32>) -> Option<Self> {
+        let normal = (p1 - p0).cross(&(p2 - p0)).try_normalize(1e-12)?;
+        Some(Self {
+            normal,
+            d: -normal.dot(p0),
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct PointCloud {
     pub points: Array1<Vector3<f32>>,
     pub normals: Option<Array1<Vector3<f32>>>,
     pub colors: Option<Array1<Vector3<u8>>>,
+    pub confidences: Option<Array1<f32>>,
 }
 
 impl PointCloud {
@@ -17,6 +48,7 @@ impl PointCloud {
             points: geometry.points,
             normals: geometry.normals,
             colors: geometry.colors,
+            confidences: geometry.confidences,
         }
     }
 
@@ -25,6 +57,7 @@ impl PointCloud {
             points: Array1::zeros(len),
             normals: Some(Array1::zeros(len)),
             colors: Some(Array1::zeros(len)),
+            confidences: None,
         }
     }
 
@@ -35,6 +68,259 @@ impl PointCloud {
     pub fn is_empty(&self) -> bool {
         self.points.is_empty()
     }
+
+    /// Concatenates multiple point clouds into a single one.
+    ///
+    /// # Arguments
+    ///
+    /// * `clouds` - The point clouds to merge.
+    ///
+    /// # Returns
+    ///
+    /// The merged point cloud, or an error if the clouds don't have the same optional
+    /// attributes (normals, colors and confidences) or if `clouds` is empty.
+    pub fn concat(clouds: &[PointCloud]) -> Result<PointCloud, A3dError> {
+        let first = clouds
+            .first()
+            .ok_or_else(|| A3dError::invalid_parameter("No point clouds to concatenate."))?;
+        if clouds.iter().any(|cloud| {
+            cloud.normals.is_some() != first.normals.is_some()
+                || cloud.colors.is_some() != first.colors.is_some()
+                || cloud.confidences.is_some() != first.confidences.is_some()
+        }) {
+            return Err(A3dError::invalid_parameter(
+                "Point clouds must have the same optional attributes to be concatenated.",
+            ));
+        }
+
+        fn concat_attr<T: Clone>(
+            clouds: &[PointCloud],
+            attr: impl Fn(&PointCloud) -> Option<&Array1<T>>,
+        ) -> Option<Array1<T>> {
+            attr(&clouds[0])?;
+            let views = clouds
+                .iter()
+                .map(|cloud| attr(cloud).unwrap().view())
+                .collect::<Vec<_>>();
+            Some(ndarray::concatenate(Axis(0), &views).unwrap())
+        }
+
+        Ok(PointCloud {
+            points: concat_attr(clouds, |cloud| Some(&cloud.points)).unwrap(),
+            normals: concat_attr(clouds, |cloud| cloud.normals.as_ref()),
+            colors: concat_attr(clouds, |cloud| cloud.colors.as_ref()),
+            confidences: concat_attr(clouds, |cloud| cloud.confidences.as_ref()),
+        })
+    }
+
+    /// Selects a random subset of the points, without replacement. Normals, colors and
+    /// confidences are kept aligned with the points.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of points to select. If it's greater or equal to the number of
+    ///   points, a copy of the point cloud is returned.
+    /// * `seed` - Seed of the random generator, the same seed selects the same points.
+    pub fn random_subsample(&self, count: usize, seed: u64) -> PointCloud {
+        if count >= self.len() {
+            return self.clone();
+        }
+
+        let mut indices =
+            rand::seq::index::sample(&mut SmallRng::seed_from_u64(seed), self.len(), count)
+                .into_vec();
+        indices.sort_unstable();
+        self.select(&indices)
+    }
+
+    /// Keeps only the points whose confidence is above a threshold, e.g., `0.0` drops the
+    /// unreliable points of a range image, see [`crate::range_image::RangeImage::confidence`].
+    ///
+    /// # Arguments
+    ///
+    /// * `min_confidence` - Points with confidence less or equal to it are dropped.
+    ///
+    /// # Returns
+    ///
+    /// The selected points, or a copy of the point cloud if it has no confidences.
+    pub fn filter_by_confidence(&self, min_confidence: f32) -> PointCloud {
+        match self.confidences.as_ref() {
+            Some(confidences) => self.select(
+                &confidences
+                    .indexed_iter()
+                    .filter_map(|(index, confidence)| {
+                        (*confidence > min_confidence).then_some(index)
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            None => self.clone(),
+        }
+    }
+
+    /// Selects the points at `indices`, keeping the other attributes aligned with them.
+    fn select(&self, indices: &[usize]) -> PointCloud {
+        PointCloud {
+            points: self.points.select(Axis(0), indices),
+            normals: self
+                .normals
+                .as_ref()
+                .map(|normals| normals.select(Axis(0), indices)),
+            colors: self
+                .colors
+                .as_ref()
+                .map(|colors| colors.select(Axis(0), indices)),
+            confidences: self
+                .confidences
+                .as_ref()
+                .map(|confidences| confidences.select(Axis(0), indices)),
+        }
+    }
+
+    /// Returns the axis-aligned bounding box as the (min, max) corners.
+    /// None if the point cloud is empty.
+    pub fn bounding_box(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        extra_math::bounding_box(self.points.iter())
+    }
+
+    /// Returns the mean of the points. Zero if the point cloud is empty.
+    pub fn centroid(&self) -> Vector3<f32> {
+        if self.is_empty() {
+            return Vector3::zeros();
+        }
+        self.points.iter().sum::<Vector3<f32>>() / self.len() as f32
+    }
+
+    /// Fits the dominant plane with RANSAC, e.g., for removing the floor before registration.
+    /// The plane with most inliers among random triplets of points is refined with
+    /// a least-squares fit of its inliers.
+    ///
+    /// # Arguments
+    ///
+    /// * `distance_threshold` - Maximum distance from a point to the plane to be an inlier.
+    /// * `iterations` - Number of random triplets to try.
+    /// * `seed` - Seed of the random generator, the same seed fits the same plane.
+    ///
+    /// # Returns
+    ///
+    /// The plane and the indices of its inliers, in increasing order. No inliers if the
+    /// point cloud has fewer than 3 points or they're all collinear.
+    pub fn segment_plane(
+        &self,
+        distance_threshold: f32,
+        iterations: usize,
+        seed: u64,
+    ) -> (PlaneModel, Vec<usize>) {
+        let inliers = |plane: &PlaneModel| {
+            self.points
+                .iter()
+                .enumerate()
+                .filter(|(_, point)| plane.distance(point).abs() <= distance_threshold)
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+
+        let mut best = (
+            PlaneModel {
+                normal: Vector3::z(),
+                d: 0.0,
+            },
+            Vec::new(),
+        );
+        if self.len() < 3 {
+            return best;
+        }
+
+        let mut rng = SmallRng::seed_from_u64(seed);
+        for _ in 0..iterations {
+            let triplet = rand::seq::index::sample(&mut rng, self.len(), 3);
+            let Some(plane) = PlaneModel::from_points(
+                &self.points[triplet.index(0)],
+                &self.points[triplet.index(1)],
+                &self.points[triplet.index(2)],
+            ) else {
+                continue;
+            };
+            let plane_inliers = inliers(&plane);
+            if plane_inliers.len() > best.1.len() {
+                best = (plane, plane_inliers);
+            }
+        }
+
+        if best.1.len() >= 3 {
+            // Least-squares refinement: the normal is the direction of least variance.
+            let centroid =
+                best.1.iter().map(|i| self.points[*i]).sum::<Vector3<f32>>() / best.1.len() as f32;
+            let covariance = best.1.iter().fold(Matrix3::zeros(), |acc, i| {
+                let centered = self.points[*i] - centroid;
+                acc + centered * centered.transpose()
+            });
+            let eigen = SymmetricEigen::new(covariance);
+            let mut normal: Vector3<f32> =
+                eigen.eigenvectors.column(eigen.eigenvalues.imin()).into();
+            if normal.dot(&best.0.normal) < 0.0 {
+                normal = -normal;
+            }
+            let refined = PlaneModel {
+                normal,
+                d: -normal.dot(&centroid),
+            };
+            let refined_inliers = inliers(&refined);
+            if refined_inliers.len() >= best.1.len() {
+                best = (refined, refined_inliers);
+            }
+        }
+
+        best
+    }
+
+    /// Renders the point cloud into a range image on the CPU. Points are projected
+    /// into the camera, and when multiple points hit the same pixel, the closest one is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - The camera to render from.
+    ///
+    /// # Returns
+    ///
+    /// A range image with the points (and normals, if any) in the camera frame, its colors
+    /// and the camera pose.
+    pub fn to_range_image(&self, camera: &PinholeCamera) -> RangeImage {
+        let intrinsics = &camera.intrinsics;
+        let mut depth_buffer = Array2::from_elem((intrinsics.height, intrinsics.width), f32::MAX);
+        let mut index_buffer = Array2::<Option<usize>>::from_elem(depth_buffer.dim(), None);
+
+        for (index, point) in self.points.iter().enumerate() {
+            if let Some((x, y, z)) = camera.project_to_image(point) {
+                let pixel = (y as usize, x as usize);
+                if z > 0.0 && z < depth_buffer[pixel] {
+                    depth_buffer[pixel] = z;
+                    index_buffer[pixel] = Some(index);
+                }
+            }
+        }
+
+        let world_to_camera = camera.camera_to_world.inverse();
+        let mut range_image = RangeImage::from_intrinsics_fn(
+            intrinsics,
+            |i, j| index_buffer[(i, j)].map(|k| world_to_camera.transform_vector(&self.points[k])),
+            |i, j| {
+                let normals = self.normals.as_ref()?;
+                index_buffer[(i, j)].map(|k| world_to_camera.transform_normal(&normals[k]))
+            },
+            |i, j| {
+                let colors = self.colors.as_ref()?;
+                index_buffer[(i, j)].map(|k| colors[k])
+            },
+        );
+        if self.normals.is_none() {
+            range_image.normals = None;
+        }
+        if self.colors.is_none() {
+            range_image.colors = None;
+        }
+        range_image.camera_to_world = Some(camera.camera_to_world.clone());
+        range_image
+    }
 }
 
 impl std::ops::Mul<&PointCloud> for &Transform {
@@ -47,6 +333,7 @@ impl std::ops::Mul<&PointCloud> for &Transform {
                 .as_ref()
                 .map(|normals| self.transform_normals(normals.clone())),
             colors: rhs.colors.clone(),
+            confidences: rhs.confidences.clone(),
         }
     }
 }
@@ -60,6 +347,7 @@ impl Transformable<PointCloud> for Transform {
                 .as_ref()
                 .map(|normals| self.transform_normals(normals.clone())),
             colors: pcl.colors.clone(),
+            confidences: pcl.confidences.clone(),
         }
     }
 }
@@ -70,6 +358,7 @@ impl From<PointCloud> for Geometry {
             points: pcl.points,
             normals: pcl.normals,
             colors: pcl.colors,
+            confidences: pcl.confidences,
             faces: None,
             texcoords: None,
         }
@@ -80,6 +369,12 @@ impl From<PointCloud> for Geometry {
 mod tests {
     use super::super::io::read_off;
     use super::PointCloud;
+    use crate::{
+        camera::{CameraIntrinsics, PinholeCamera},
+        transform::Transform,
+    };
+    use nalgebra::{Quaternion, Vector3};
+    use ndarray::{array, Array1};
     use rstest::*;
     #[fixture]
     fn sample_pcl1() -> PointCloud {
@@ -90,4 +385,160 @@ mod tests {
     fn test_point_cloud_from_file(sample_pcl1: PointCloud) {
         assert_eq!(sample_pcl1.len(), 480);
     }
+
+    #[rstest]
+    fn test_concat(sample_pcl1: PointCloud) {
+        let pcl2 = PointCloud::zeros(20);
+        let mut pcl3 = PointCloud::zeros(30);
+        pcl3.normals = None;
+
+        assert!(PointCloud::concat(&[sample_pcl1, pcl2.clone(), pcl3.clone()]).is_err());
+        assert!(PointCloud::concat(&[]).is_err());
+
+        pcl3.normals = Some(Array1::from_elem(30, Vector3::new(0.0, 0.0, 1.0)));
+        let merged = PointCloud::concat(&[pcl2, pcl3]).unwrap();
+        assert_eq!(merged.len(), 50);
+        assert_eq!(merged.normals.as_ref().unwrap().len(), 50);
+        assert_eq!(merged.colors.as_ref().unwrap().len(), 50);
+        assert_eq!(merged.normals.unwrap()[25], Vector3::new(0.0, 0.0, 1.0));
+        assert!(merged.confidences.is_none());
+    }
+
+    #[rstest]
+    fn test_random_subsample(mut sample_pcl1: PointCloud) {
+        // Uses the points as normals to check if they're kept aligned.
+        sample_pcl1.normals = Some(sample_pcl1.points.clone());
+
+        let subsample = sample_pcl1.random_subsample(100, 5);
+        assert_eq!(subsample.len(), 100);
+        assert_eq!(subsample.normals.as_ref().unwrap(), &subsample.points);
+        assert!(subsample.colors.is_none());
+        assert!(subsample
+            .points
+            .iter()
+            .all(|point| sample_pcl1.points.iter().any(|p| p == point)));
+
+        assert_eq!(
+            sample_pcl1.random_subsample(100, 5).points,
+            subsample.points
+        );
+        assert_ne!(
+            sample_pcl1.random_subsample(100, 6).points,
+            subsample.points
+        );
+        assert_eq!(
+            sample_pcl1.random_subsample(1000, 5).len(),
+            sample_pcl1.len()
+        );
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let mut pcl = PointCloud::zeros(3);
+        pcl.points = array![
+            Vector3::new(1.0, -2.0, 3.0),
+            Vector3::new(-1.0, 5.0, 0.5),
+            Vector3::new(0.0, 0.0, 4.0)
+        ];
+        assert_eq!(
+            pcl.bounding_box(),
+            Some((Vector3::new(-1.0, -2.0, 0.5), Vector3::new(1.0, 5.0, 4.0)))
+        );
+        assert_eq!(PointCloud::zeros(0).bounding_box(), None);
+    }
+
+    #[test]
+    fn test_centroid() {
+        let mut pcl = PointCloud::zeros(4);
+        pcl.points = array![
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::new(3.0, 2.0, 3.0),
+            Vector3::new(2.0, 1.0, 5.0),
+            Vector3::new(2.0, 3.0, 1.0)
+        ];
+        assert_eq!(pcl.centroid(), Vector3::new(2.0, 2.0, 3.0));
+        assert_eq!(PointCloud::zeros(0).centroid(), Vector3::zeros());
+    }
+
+    #[test]
+    fn test_to_range_image() {
+        let intrinsics = CameraIntrinsics::from_simple_intrinsic(50.0, 50.0, 32.0, 24.0, 64, 48);
+        let camera_to_world =
+            Transform::new(&Vector3::new(0.5, 0.0, -1.0), &Quaternion::identity());
+        let camera = PinholeCamera::new(intrinsics, camera_to_world.clone());
+
+        // A plane at z = 1 in world (2 in camera), and a farther one behind it.
+        // Points are denser than the pixels, so the far plane is fully occluded.
+        let mut pcl = PointCloud::zeros(2 * 101 * 101);
+        for k in 0..pcl.len() {
+            let (plane, j) = (k / (101 * 101), k % (101 * 101));
+            pcl.points[k] = Vector3::new(
+                (j % 101) as f32 * 0.02,
+                (j / 101) as f32 * 0.02 - 1.0,
+                1.0 + plane as f32,
+            );
+            pcl.colors.as_mut().unwrap()[k] = Vector3::new(255 * (1 - plane as u8), 0, 0);
+        }
+        pcl.normals = None;
+
+        let range_image = pcl.to_range_image(&camera);
+        assert!(range_image.normals.is_none());
+        assert_eq!(
+            range_image.camera_to_world.unwrap().translation(),
+            camera_to_world.translation()
+        );
+
+        // The principal point sees the near plane around the world point (0.5, 0, 1).
+        let point = range_image.points[(24, 32)];
+        assert_eq!(range_image.mask[(24, 32)], 1);
+        assert_eq!(point[2], 2.0);
+        assert!(
+            point[0].abs() < 0.025 && point[1].abs() < 0.025,
+            "{point:?}"
+        );
+        assert_eq!(
+            range_image.colors.as_ref().unwrap()[(24, 32)],
+            Vector3::new(255, 0, 0)
+        );
+        assert!(range_image
+            .points
+            .iter()
+            .zip(range_image.mask.iter())
+            .all(|(point, mask)| *mask == 0 || (point[2] - 2.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_segment_plane() {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        // Plane z = 0.3 * x - 0.2 * y + 1 with noise, and outliers above it.
+        let mut rng = SmallRng::seed_from_u64(7);
+        let height = |x: f32, y: f32| 0.3 * x - 0.2 * y + 1.0;
+        let mut points = (0..600)
+            .map(|_| {
+                let (x, y) = (rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+                Vector3::new(x, y, height(x, y) + rng.gen_range(-0.005..0.005))
+            })
+            .collect::<Vec<_>>();
+        points.extend((0..400).map(|_| {
+            let (x, y) = (rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            Vector3::new(x, y, height(x, y) + rng.gen_range(0.1..1.0))
+        }));
+        let mut pcl = PointCloud::zeros(points.len());
+        pcl.points = Array1::from_vec(points);
+
+        let (plane, inliers) = pcl.segment_plane(0.02, 100, 3);
+        let expected_normal = Vector3::new(0.3, -0.2, -1.0).normalize();
+        assert!(
+            plane.normal.dot(&expected_normal).abs() > 0.999,
+            "{plane:?}"
+        );
+        assert_eq!(inliers, (0..600).collect::<Vec<_>>());
+        assert!(inliers
+            .iter()
+            .all(|i| plane.distance(&pcl.points[*i]).abs() <= 0.02));
+
+        let (_, inliers) = PointCloud::zeros(2).segment_plane(0.02, 100, 3);
+        assert!(inliers.is_empty());
+    }
 }
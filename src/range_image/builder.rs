@@ -1,13 +1,16 @@
 use crate::{bilateral::BilateralFilter, image::RgbdFrame};
 
-use super::RangeImage;
+use super::{NormalMethod, RangeImage};
 
 #[derive(Debug, Clone)]
 /// Builder for multiple range images from RGB-D data.
 pub struct RangeImageBuilder {
     with_normals: bool,
+    normal_method: NormalMethod,
     with_intensity: bool,
+    with_pose: bool,
     bilateral_filter: Option<BilateralFilter<u16>>,
+    filter_confidence_sigma: Option<f64>,
     // bilateral_data: Array2Recycle<u16>,
     pyramid_levels: usize,
     blur_sigma: f32,
@@ -18,8 +21,11 @@ impl Default for RangeImageBuilder {
     fn default() -> Self {
         Self {
             with_normals: true,
+            normal_method: NormalMethod::default(),
             with_intensity: true,
+            with_pose: true,
             bilateral_filter: None,
+            filter_confidence_sigma: None,
             pyramid_levels: 3,
             blur_sigma: 1.0,
         }
@@ -34,6 +40,19 @@ impl RangeImageBuilder {
         self
     }
 
+    /// Attaches a confidence map computed from how much the bilateral filter changed each depth,
+    /// used only if the bilateral filter is enabled.
+    /// See [`BilateralFilter::filter_with_confidence`] and [`RangeImage::confidence`].
+    ///
+    /// # Arguments
+    ///
+    /// * `change_sigma` - Depth change, in the depth image units, that reduces the confidence
+    ///   to about 0.6. `None` disables the confidence map.
+    pub fn with_filter_confidence(mut self, change_sigma: Option<f64>) -> Self {
+        self.filter_confidence_sigma = change_sigma;
+        self
+    }
+
     /// Computes the normals of the range image.
     /// See [`RangeImage::compute_normals`].
     pub fn with_normals(mut self, value: bool) -> Self {
@@ -41,6 +60,13 @@ impl RangeImageBuilder {
         self
     }
 
+    /// Sets the normal estimation method, used if normals are enabled.
+    /// See [`RangeImage::compute_normals_with`].
+    pub fn with_normal_method(mut self, method: NormalMethod) -> Self {
+        self.normal_method = method;
+        self
+    }
+
     /// Computes the intensity of the range image.
     /// See [`RangeImage::compute_intensity`] and [`RangeImage::intensity_map`].
     pub fn with_intensity(mut self, value: bool) -> Self {
@@ -48,6 +74,13 @@ impl RangeImageBuilder {
         self
     }
 
+    /// Attaches the frame's camera pose to the range images.
+    /// See [`RangeImage::camera_to_world`].
+    pub fn with_pose(mut self, value: bool) -> Self {
+        self.with_pose = value;
+        self
+    }
+
     /// Sets the number of pyramid levels to use, this corresponds to the output length of [`build`].
     /// See [`RangeImage::pyramid`].
     pub fn pyramid_levels(mut self, levels: usize) -> Self {
@@ -72,12 +105,25 @@ impl RangeImageBuilder {
     ///
     /// A vector of range images, the length of the vector depends on the number of pyramid levels.
     pub fn build(&self, mut frame: RgbdFrame) -> Vec<RangeImage> {
+        let mut confidence = None;
         if let Some(filter) = &self.bilateral_filter {
-            frame.image.depth = filter.filter(&frame.image.depth);
+            frame.image.depth = match self.filter_confidence_sigma {
+                Some(change_sigma) => {
+                    let (depth, depth_confidence) =
+                        filter.filter_with_confidence(&frame.image.depth, change_sigma);
+                    confidence = Some(depth_confidence);
+                    depth
+                }
+                None => filter.filter(&frame.image.depth),
+            };
         }
         let mut first_image = RangeImage::from_rgbd_frame(&frame);
+        first_image.confidence = confidence;
+        if !self.with_pose {
+            first_image.camera_to_world = None;
+        }
         if self.with_normals {
-            first_image.compute_normals();
+            first_image.compute_normals_with(self.normal_method);
         }
         let mut range_images = first_image.pyramid(self.pyramid_levels, self.blur_sigma);
         for range_image in range_images.iter_mut() {
@@ -90,3 +136,69 @@ impl RangeImageBuilder {
         range_images
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use nalgebra::{Quaternion, Vector3};
+
+    use super::RangeImageBuilder;
+    use crate::{
+        bilateral::BilateralFilter,
+        io::dataset::{RgbdDataset, SlamTbDataset},
+        transform::Transform,
+    };
+
+    #[test]
+    fn should_attach_pose() {
+        let dataset = SlamTbDataset::load("tests/data/rgbd/sample1").unwrap();
+        let camera_to_world = Transform::new(
+            &Vector3::new(1.0, 2.0, 3.0),
+            &Quaternion::new(0.9, 0.1, 0.2, 0.3),
+        );
+
+        let mut frame = dataset.get(0).unwrap();
+        frame.camera_to_world = Some(camera_to_world.clone());
+        let range_images = RangeImageBuilder::default()
+            .with_intensity(false)
+            .build(frame);
+        for range_image in range_images.iter() {
+            assert_eq!(
+                range_image.camera_to_world.as_ref().unwrap().0,
+                camera_to_world.0
+            );
+        }
+        assert!(range_images[0].pinhole_camera().is_some());
+
+        let range_images = RangeImageBuilder::default()
+            .with_intensity(false)
+            .with_pose(false)
+            .build(dataset.get(0).unwrap());
+        assert!(range_images[0].camera_to_world.is_none());
+    }
+
+    #[test]
+    fn should_attach_filter_confidence() {
+        let dataset = SlamTbDataset::load("tests/data/rgbd/sample1").unwrap();
+        let frame = dataset.get(0).unwrap();
+        let depth_dim = frame.image.depth.dim();
+
+        let builder = RangeImageBuilder::default()
+            .with_intensity(false)
+            .with_bilateral_filter(Some(BilateralFilter::default()));
+        let range_images = builder.clone().build(frame.clone());
+        assert!(range_images[0].confidence.is_none());
+
+        let range_images = builder.with_filter_confidence(Some(10.0)).build(frame);
+        let confidence = range_images[0].confidence.as_ref().unwrap();
+        assert_eq!(confidence.dim(), depth_dim);
+        assert!(confidence
+            .iter()
+            .zip(range_images[0].mask.iter())
+            .all(|(confidence, mask)| (0.0..=1.0).contains(confidence)
+                && (*mask != 0 || *confidence == 0.0)));
+        assert_eq!(
+            range_images[1].confidence.as_ref().unwrap().dim(),
+            range_images[1].mask.dim()
+        );
+    }
+}
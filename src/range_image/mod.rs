@@ -1,7 +1,7 @@
 mod resize;
 
 mod structure;
-pub use structure::RangeImage;
+pub use structure::{NormalMethod, RangeImage};
 
 mod builder;
 pub use builder::RangeImageBuilder;
@@ -103,7 +103,7 @@ pub fn resize_range_normals(
     dst_points
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "serde"))]
 mod tests {
     use rstest::rstest;
 
@@ -1,20 +1,45 @@
-use crate::camera::CameraIntrinsics;
+use crate::camera::{CameraIntrinsics, PinholeCamera};
+use crate::error::A3dError;
 
 use crate::image::{rgb_to_luma_u8, RgbdFrame, RgbdImage, ToImageRgb8};
 use crate::intensity_map::IntensityMap;
 
+use image::error::{ParameterError, ParameterErrorKind};
 use image::imageops::blur;
-use image::{ImageBuffer, Rgb};
-use nalgebra::Vector3;
+use image::{ImageBuffer, ImageError, ImageResult, Luma, Rgb};
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
 
-use ndarray::{Array1, Array2, Axis};
+use ndarray::{s, Array1, Array2, Axis, Zip};
 use rayon::prelude::{ParallelBridge, ParallelIterator};
+use std::path::Path;
 
 use crate::io::Geometry;
 use crate::pointcloud::PointCloud;
+use crate::transform::Transform;
 
 use super::resize::{resize_range_normals, resize_range_points};
 
+/// Method for estimating the normals of a range image. See [`RangeImage::compute_normals_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalMethod {
+    /// Cross product of the vectors between the left/right and the top/bottom neighbors.
+    /// When a neighbor is more than `ratio_threshold` times farther than the opposite one,
+    /// the vector between the center and the closest one is used instead, avoiding depth
+    /// discontinuities.
+    CrossProduct { ratio_threshold: f32 },
+    /// Normal of the plane fitted to the valid points in the `(2 * radius + 1)` squared
+    /// window around each pixel. Smoother on noisy depth than the cross product, but slower.
+    PlaneFit { radius: usize },
+}
+
+impl Default for NormalMethod {
+    fn default() -> Self {
+        Self::CrossProduct {
+            ratio_threshold: 2.0,
+        }
+    }
+}
+
 /// A point cloud that comes from an image-based measurement. It representation holds its grid structure.
 #[derive(Debug, Clone)]
 pub struct RangeImage {
@@ -28,10 +53,15 @@ pub struct RangeImage {
     pub colors: Option<Array2<Vector3<u8>>>,
     /// Camera parameters that originated the image.
     pub intrinsics: CameraIntrinsics,
+    /// Pose of the camera that originated the image, if known.
+    pub camera_to_world: Option<Transform>,
     /// Intensities of the points, as array with shape: (height*width)
     pub intensities: Option<Array1<u8>>,
     /// Intensity map of the points, as array with shape: (height, width)
     pub intensity_map: Option<IntensityMap>,
+    /// Confidence of the points in [0, 1], e.g., from the sensor or from filtering,
+    /// as array with shape: (height, width)
+    pub confidence: Option<Array2<f32>>,
     valid_points: usize,
 }
 
@@ -48,6 +78,7 @@ fn py_scale_down2(src_img: &ImageBuffer<Rgb<u8>, Vec<u8>>, sigma: f32) -> Array2
 
 impl RangeImage {
     /// Creates a new range image from a depth image and camera parameters.
+    /// The image's depth bias and maximum depth are applied, see [`RgbdImage`].
     ///
     /// # Arguments
     ///
@@ -56,31 +87,21 @@ impl RangeImage {
     pub fn from_rgbd_image(camera: &CameraIntrinsics, rgbd_image: &RgbdImage) -> Self {
         let (width, height) = (rgbd_image.width(), rgbd_image.height());
         let depth_scale = rgbd_image.depth_scale.unwrap() as f32;
-        let mut points = Array2::zeros((height, width));
-        let mut mask = Array2::<u8>::zeros((height, width));
+        let (points, mask) = camera.backproject_image_with(
+            &rgbd_image.depth.view(),
+            depth_scale,
+            rgbd_image.depth_bias as f32,
+            rgbd_image.depth_max.map(|depth_max| depth_max as f32),
+        );
+        let valid_points = Zip::from(&mask).par_fold(
+            || 0,
+            |count, mask| count + *mask as usize,
+            |count1, count2| count1 + count2,
+        );
         let mut colors = Array2::<Vector3<u8>>::zeros((height, width));
-        let mut valid_points = 0;
-
-        for x in 0..width {
-            for y in 0..height {
-                let z = rgbd_image.depth[[y, x]];
-                if z > 0 {
-                    let z = rgbd_image.depth[[y, x]] as f32 * depth_scale;
-                    let point3d = camera.backproject(x as f32, y as f32, z);
-                    points[[y, x]] = point3d;
-
-                    mask[[y, x]] = 1;
-                    valid_points += 1;
-                }
-
-                //colors.slice_mut(s![y, x, ..]).assign(&rgbd_image.color.slice(s![y, x, ..]));
-                colors[[y, x]] = Vector3::<u8>::new(
-                    rgbd_image.color[[y, x, 0]],
-                    rgbd_image.color[[y, x, 1]],
-                    rgbd_image.color[[y, x, 2]],
-                );
-            }
-        }
+        Zip::from(&mut colors)
+            .and(rgbd_image.color.lanes(Axis(2)))
+            .par_for_each(|color, rgb| *color = Vector3::new(rgb[0], rgb[1], rgb[2]));
 
         Self {
             points,
@@ -88,15 +109,20 @@ impl RangeImage {
             normals: None,
             colors: Some(colors),
             intrinsics: camera.clone(),
+            camera_to_world: None,
             intensities: None,
             intensity_map: None,
+            confidence: None,
             valid_points,
         }
     }
 
-    /// Creates a new range image from a depth image and camera parameters.
+    /// Creates a new range image from a RGB-D frame. The range image carries the frame's
+    /// camera pose.
     pub fn from_rgbd_frame(frame: &RgbdFrame) -> Self {
-        Self::from_rgbd_image(&frame.camera, &frame.image)
+        let mut range_image = Self::from_rgbd_image(&frame.camera, &frame.image);
+        range_image.camera_to_world = frame.camera_to_world.clone();
+        range_image
     }
 
     pub fn from_intrinsics_fn(
@@ -118,11 +144,13 @@ impl RangeImage {
         let valid_points = mask.fold(0, |sum, &mask| sum + mask as usize);
         RangeImage {
             intrinsics: camera.clone(),
+            camera_to_world: None,
             points,
             mask,
             valid_points,
             intensities: None,
             intensity_map: None,
+            confidence: None,
             normals: Some(Array2::from_shape_fn(
                 (camera.height, camera.width),
                 |(i, j)| normal_fn(i, j).unwrap_or(Vector3::zeros()),
@@ -144,6 +172,14 @@ impl RangeImage {
         self.points.shape()[0]
     }
 
+    /// Returns the pinhole camera, i.e., intrinsics and pose, that originated the image.
+    /// None if the pose is unknown.
+    pub fn pinhole_camera(&self) -> Option<PinholeCamera> {
+        self.camera_to_world.as_ref().map(|camera_to_world| {
+            PinholeCamera::new(self.intrinsics.clone(), camera_to_world.clone())
+        })
+    }
+
     /// Number of valid points in the image, this is not the same as
     /// width*height, since some points may be invalid (i.e, 0 valued depth).
     pub fn valid_points_count(&self) -> usize {
@@ -180,12 +216,83 @@ impl RangeImage {
         }
     }
 
-    /// Updates the image with normals computed from the 3D points.
+    /// Returns the 3D point at the given pixel, accepting coordinates outside the image,
+    /// like the ones from projecting arbitrary points.
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - Column of the point.
+    /// * `v` - Row of the point.
+    ///
+    /// # Returns
+    ///
+    /// * The 3D point, or None if `(u, v)` is out of the image or the point is invalid.
+    pub fn get_point_checked(&self, u: i32, v: i32) -> Option<Vector3<f32>> {
+        if u < 0 || v < 0 {
+            return None;
+        }
+        self.get_point(v as usize, u as usize)
+    }
+
+    /// Returns the 3D point at a subpixel position, bilinearly interpolated from its 4 nearest
+    /// pixels. Pixel centers are at integer coordinates, like in [`Self::get_point`].
+    ///
+    /// # Arguments
+    ///
+    /// * `u` - Column coordinate.
+    /// * `v` - Row coordinate.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vector3<f32>>` - The interpolated point, or None if the position is outside
+    ///   the image or any of the pixels used in the interpolation is invalid.
+    pub fn get_point_bilinear(&self, u: f32, v: f32) -> Option<Vector3<f32>> {
+        if !(u >= 0.0 && v >= 0.0) {
+            return None;
+        }
+        let (col, row) = (u as usize, v as usize);
+        let (u_frac, v_frac) = (u - col as f32, v - row as f32);
+        // Skips the next pixel when it has no weight, so the last row and column can be sampled.
+        let next_col = if u_frac > 0.0 { col + 1 } else { col };
+        let next_row = if v_frac > 0.0 { row + 1 } else { row };
+
+        let p00 = self.get_point(row, col)?;
+        let p10 = self.get_point(row, next_col)?;
+        let p01 = self.get_point(next_row, col)?;
+        let p11 = self.get_point(next_row, next_col)?;
+
+        let top = p00 * (1.0 - u_frac) + p10 * u_frac;
+        let bottom = p01 * (1.0 - u_frac) + p11 * u_frac;
+        Some(top * (1.0 - v_frac) + bottom * v_frac)
+    }
+
+    /// Updates the image with normals computed from the 3D points, using the default
+    /// [`NormalMethod`].
     pub fn compute_normals(&mut self) -> &mut Self {
+        self.compute_normals_with(NormalMethod::default())
+    }
+
+    /// Updates the image with normals computed from the 3D points. Normals point towards the camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - The normal estimation method.
+    pub fn compute_normals_with(&mut self, method: NormalMethod) -> &mut Self {
+        let normals = match method {
+            NormalMethod::CrossProduct { ratio_threshold } => {
+                self.cross_product_normals(ratio_threshold)
+            }
+            NormalMethod::PlaneFit { radius } => self.plane_fit_normals(radius),
+        };
+        self.normals = Some(normals);
+        self
+    }
+
+    /// Normals from the cross product of the vectors between the horizontal and vertical neighbors.
+    fn cross_product_normals(&self, ratio_threshold: f32) -> Array2<Vector3<f32>> {
         let height = self.height();
         let width = self.width();
 
-        let ratio_threshold = 2f32;
         let ratio_threshold_squared = ratio_threshold * ratio_threshold;
 
         let mut normals = Array2::<Vector3<f32>>::zeros((height, width));
@@ -256,11 +363,211 @@ impl RangeImage {
                 });
             });
 
-        self.normals = Some(normals);
+        normals
+    }
 
+    /// Normals of the planes fitted to the valid points of each pixel's window.
+    fn plane_fit_normals(&self, radius: usize) -> Array2<Vector3<f32>> {
+        let (height, width) = (self.height(), self.width());
+        let mut normals = Array2::<Vector3<f32>>::zeros((height, width));
+
+        Zip::indexed(&mut normals).par_for_each(|(row, col), normal| {
+            let center = match self.get_point(row, col) {
+                Some(center) => center,
+                None => return,
+            };
+            let neighbors = (row.saturating_sub(radius)..(row + radius + 1).min(height))
+                .flat_map(|n_row| {
+                    (col.saturating_sub(radius)..(col + radius + 1).min(width))
+                        .map(move |n_col| (n_row, n_col))
+                })
+                .filter_map(|(n_row, n_col)| self.get_point(n_row, n_col))
+                .collect::<Vec<_>>();
+            if neighbors.len() < 3 {
+                return;
+            }
+
+            let mean = neighbors.iter().sum::<Vector3<f32>>() / neighbors.len() as f32;
+            let covariance = neighbors
+                .iter()
+                .map(|point| {
+                    let centered = point - mean;
+                    centered * centered.transpose()
+                })
+                .sum::<Matrix3<f32>>();
+
+            let eigen = SymmetricEigen::new(covariance);
+            let plane_normal = eigen
+                .eigenvectors
+                .column(eigen.eigenvalues.imin())
+                .into_owned();
+            *normal = if plane_normal.dot(&center) > 0.0 {
+                -plane_normal
+            } else {
+                plane_normal
+            };
+        });
+
+        normals
+    }
+
+    /// Estimates the surface curvature of each pixel as the divergence of the normal field,
+    /// i.e., the change of the normals per unit of distance along the rows and the columns.
+    /// Useful for weighting residuals towards regions with more geometric information or for
+    /// filtering flat areas. For a cylinder of radius `r`, it's `1/r`.
+    ///
+    /// Central differences are used when both neighbors are valid, otherwise the
+    /// valid one.
+    ///
+    /// # Returns
+    ///
+    /// The absolute curvature with shape (height, width), 0 at invalid pixels or pixels without
+    /// valid neighbors, or an error if the image has no normals.
+    pub fn compute_curvature(&self) -> Result<Array2<f32>, A3dError> {
+        let normals = self.normals.as_ref().ok_or_else(|| {
+            A3dError::invalid_parameter("Range image has no normals to compute the curvature.")
+        })?;
+        let (height, width) = (self.height(), self.width());
+
+        let point_normal = |row: usize, col: usize| {
+            self.get_point(row, col)
+                .map(|point| (point, normals[(row, col)]))
+        };
+        let divergence_term =
+            |center: (Vector3<f32>, Vector3<f32>),
+             prev: Option<(Vector3<f32>, Vector3<f32>)>,
+             next: Option<(Vector3<f32>, Vector3<f32>)>| {
+                let (first, last) = match (prev, next) {
+                    (Some(prev), Some(next)) => (prev, next),
+                    (Some(prev), None) => (prev, center),
+                    (None, Some(next)) => (center, next),
+                    (None, None) => return 0.0,
+                };
+                let point_diff = last.0 - first.0;
+                let squared_distance = point_diff.norm_squared();
+                if squared_distance < 1e-12 {
+                    return 0.0;
+                }
+                (last.1 - first.1).dot(&point_diff) / squared_distance
+            };
+
+        Ok(Array2::from_shape_fn((height, width), |(row, col)| {
+            let center = match point_normal(row, col) {
+                Some(center) => center,
+                None => return 0.0,
+            };
+            let horizontal = divergence_term(
+                center,
+                col.checked_sub(1).and_then(|col| point_normal(row, col)),
+                point_normal(row, col + 1),
+            );
+            let vertical = divergence_term(
+                center,
+                row.checked_sub(1).and_then(|row| point_normal(row, col)),
+                point_normal(row + 1, col),
+            );
+            (horizontal + vertical).abs()
+        }))
+    }
+
+    /// Erodes the mask of valid points, invalidating points that have an invalid neighbor
+    /// or a large depth jump in their 3x3 neighborhood. Points at depth discontinuities
+    /// produce unreliable normals, so this is useful before computing normals or using
+    /// the image in ICP. Pixels outside the image aren't considered invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `iterations` - Number of erosion passes, each one removes one pixel of border.
+    /// * `depth_jump_threshold` - Maximum absolute difference of depth (z) between
+    ///   a point and its neighbors to keep it valid.
+    pub fn erode_mask(&mut self, iterations: usize, depth_jump_threshold: f32) -> &mut Self {
+        let (height, width) = (self.height(), self.width());
+
+        for _ in 0..iterations {
+            let mask = &self.mask;
+            let points = &self.points;
+            let eroded = Array2::from_shape_fn((height, width), |(row, col)| {
+                if mask[(row, col)] == 0 {
+                    return 0;
+                }
+
+                let center_z = points[(row, col)][2];
+                for n_row in row.saturating_sub(1)..(row + 2).min(height) {
+                    for n_col in col.saturating_sub(1)..(col + 2).min(width) {
+                        if mask[(n_row, n_col)] == 0
+                            || (points[(n_row, n_col)][2] - center_z).abs() > depth_jump_threshold
+                        {
+                            return 0;
+                        }
+                    }
+                }
+                1
+            });
+            self.mask = eroded;
+        }
+
+        self.valid_points = self.mask.iter().map(|x| (*x == 1) as usize).sum();
         self
     }
 
+    /// Saves the depth (z) of the points as a 16-bit grayscale PNG. Invalid points are
+    /// written as 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the output image.
+    /// * `depth_scale` - Scale multiplied to the depth before converting to u16,
+    ///   e.g., 1000.0 for millimeters when the points are in meters.
+    pub fn save_depth_png<P: AsRef<Path>>(&self, path: P, depth_scale: f32) -> ImageResult<()> {
+        ImageBuffer::<Luma<u16>, Vec<u16>>::from_fn(
+            self.width() as u32,
+            self.height() as u32,
+            |x, y| {
+                let (row, col) = (y as usize, x as usize);
+                if self.mask[(row, col)] == 0 {
+                    Luma([0])
+                } else {
+                    let depth = (self.points[(row, col)][2] * depth_scale).round();
+                    Luma([depth.clamp(0.0, u16::MAX as f32) as u16])
+                }
+            },
+        )
+        .save(path)
+    }
+
+    /// Saves the normals as a RGB PNG, mapping them to colors with `(n*0.5 + 0.5)*255`.
+    /// Invalid points are written as black.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the output image.
+    ///
+    /// # Returns
+    ///
+    /// An error if the image has no normals or the file couldn't be written.
+    pub fn save_normals_png<P: AsRef<Path>>(&self, path: P) -> ImageResult<()> {
+        let normals = self.normals.as_ref().ok_or_else(|| {
+            ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+                "Range image has no normals".to_string(),
+            )))
+        })?;
+
+        ImageBuffer::<Rgb<u8>, Vec<u8>>::from_fn(
+            self.width() as u32,
+            self.height() as u32,
+            |x, y| {
+                let (row, col) = (y as usize, x as usize);
+                if self.mask[(row, col)] == 0 {
+                    Rgb([0, 0, 0])
+                } else {
+                    let color = normals[(row, col)].map(|n| ((n * 0.5 + 0.5) * 255.0) as u8);
+                    Rgb([color[0], color[1], color[2]])
+                }
+            },
+        )
+        .save(path)
+    }
+
     /// By default, range image have only the RGB colors, this method
     /// will convert them into luma values, which are used as color optimization term in ICP.
     pub fn compute_intensity(&mut self) -> &mut Self {
@@ -326,6 +633,15 @@ impl RangeImage {
             None
         };
 
+        // Keeps the lowest confidence of each 2x2 block.
+        let confidence = self.confidence.as_ref().map(|confidence| {
+            Array2::from_shape_fn((height, width), |(row, col)| {
+                confidence
+                    .slice(s![row * 2..row * 2 + 2, col * 2..col * 2 + 2])
+                    .fold(f32::MAX, |min, value| min.min(*value))
+            })
+        });
+
         let valid_points = mask.iter().map(|x| (*x == 1) as usize).sum();
         RangeImage {
             points,
@@ -333,8 +649,10 @@ impl RangeImage {
             normals,
             colors,
             intrinsics: self.intrinsics.scale(0.5),
+            camera_to_world: self.camera_to_world.clone(),
             intensities: None,
             intensity_map: None,
+            confidence,
             valid_points,
         }
     }
@@ -350,8 +668,9 @@ impl RangeImage {
         pyramid
     }
 
+    /// Whether the pixel at column `u` and row `v` has a valid point. False if out of the image.
     pub fn is_valid(&self, u: usize, v: usize) -> bool {
-        self.mask[(v, u)] != 0
+        self.mask.get((v, u)).is_some_and(|mask| *mask != 0)
     }
 
     pub fn indexed_iter(
@@ -373,34 +692,38 @@ impl RangeImage {
 }
 
 impl From<&RangeImage> for PointCloud {
+    /// Converts the valid points of the range image into a point cloud, in row-major order.
+    /// When the image has confidence, it becomes the points' confidences. Use
+    /// [`PointCloud::filter_by_confidence`] to drop the unreliable points.
     fn from(image_pcl: &RangeImage) -> PointCloud {
-        let points = image_pcl
-            .points
+        let keep = image_pcl
+            .mask
             .iter()
-            .zip(image_pcl.mask.iter())
-            .filter_map(|(point, mask)| if *mask != 0 { Some(*point) } else { None })
-            .collect();
+            .map(|mask| *mask != 0)
+            .collect::<Vec<_>>();
 
-        let normals = image_pcl.normals.as_ref().map(|normals| {
-            normals
+        fn select<T: Copy>(values: &Array2<T>, keep: &[bool]) -> Array1<T> {
+            values
                 .iter()
-                .zip(image_pcl.mask.iter())
-                .filter_map(|(normal, mask)| if *mask != 0 { Some(*normal) } else { None })
+                .zip(keep.iter())
+                .filter_map(|(value, keep)| if *keep { Some(*value) } else { None })
                 .collect()
-        });
-
-        let colors = image_pcl.colors.as_ref().map(|colors| {
-            colors
-                .iter()
-                .zip(image_pcl.mask.iter())
-                .filter_map(|(color, mask)| if *mask != 0 { Some(*color) } else { None })
-                .collect()
-        });
+        }
 
         PointCloud {
-            points,
-            normals,
-            colors,
+            points: select(&image_pcl.points, &keep),
+            normals: image_pcl
+                .normals
+                .as_ref()
+                .map(|normals| select(normals, &keep)),
+            colors: image_pcl
+                .colors
+                .as_ref()
+                .map(|colors| select(colors, &keep)),
+            confidences: image_pcl
+                .confidence
+                .as_ref()
+                .map(|confidence| select(confidence, &keep)),
         }
     }
 }
@@ -414,24 +737,30 @@ impl From<&RangeImage> for Geometry {
 
 #[cfg(test)]
 mod tests {
-    use std::time::Instant;
-
     use super::*;
-    use crate::{
-        image::IntoLumaImage,
-        io::{
-            dataset::{RgbdDataset, SlamTbDataset},
-            write_ply,
+
+    // Imports of the tests on the sample dataset, which needs `SlamTbDataset`.
+    #[cfg(feature = "serde")]
+    use {
+        crate::{
+            image::IntoLumaImage,
+            io::{
+                dataset::{RgbdDataset, SlamTbDataset},
+                write_ply,
+            },
         },
+        nshare::ToNdarray2,
+        rstest::*,
+        std::time::Instant,
     };
-    use nshare::ToNdarray2;
-    use rstest::*;
 
+    #[cfg(feature = "serde")]
     #[fixture]
     fn sample1() -> SlamTbDataset {
         SlamTbDataset::load("tests/data/rgbd/sample1").unwrap()
     }
 
+    #[cfg(feature = "serde")]
     #[rstest]
     fn should_backproject_rgbd_image(sample1: SlamTbDataset) {
         use crate::io::write_ply;
@@ -449,6 +778,102 @@ mod tests {
         .expect("Error while writing results");
     }
 
+    /// Serial reference of [`RangeImage::from_rgbd_image`].
+    #[cfg(feature = "serde")]
+    fn serial_from_rgbd_image(camera: &CameraIntrinsics, rgbd_image: &RgbdImage) -> RangeImage {
+        let (width, height) = (rgbd_image.width(), rgbd_image.height());
+        let depth_scale = rgbd_image.depth_scale.unwrap() as f32;
+        let depth_bias = rgbd_image.depth_bias as f32;
+        let depth_max = rgbd_image.depth_max.unwrap_or(f64::INFINITY) as f32;
+        RangeImage::from_intrinsics_fn(
+            &CameraIntrinsics {
+                width,
+                height,
+                ..camera.clone()
+            },
+            |y, x| {
+                let z = rgbd_image.depth[(y, x)];
+                let z = z as f32 * depth_scale + depth_bias;
+                if rgbd_image.depth[(y, x)] == 0 || z <= 0.0 || z > depth_max {
+                    return None;
+                }
+                let (u, v) = camera.undistort(x as f32, y as f32);
+                Some(camera.backproject(u, v, z))
+            },
+            |_, _| None,
+            |y, x| {
+                Some(Vector3::new(
+                    rgbd_image.color[(y, x, 0)],
+                    rgbd_image.color[(y, x, 1)],
+                    rgbd_image.color[(y, x, 2)],
+                ))
+            },
+        )
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn should_match_serial_backprojection(sample1: SlamTbDataset) {
+        let (cam, rgbd_image, _) = sample1.get(0).unwrap().into_parts();
+        let expected = serial_from_rgbd_image(&cam, &rgbd_image);
+        let range_image = RangeImage::from_rgbd_image(&cam, &rgbd_image);
+
+        assert_eq!(range_image.points, expected.points);
+        assert_eq!(range_image.mask, expected.mask);
+        assert_eq!(range_image.colors, expected.colors);
+        assert_eq!(
+            range_image.valid_points_count(),
+            expected.valid_points_count()
+        );
+        assert!(range_image.valid_points_count() > 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[ignore]
+    #[rstest]
+    fn bench_from_rgbd_image(sample1: SlamTbDataset) {
+        let (cam, rgbd_image, _) = sample1.get(0).unwrap().into_parts();
+        assert_eq!(rgbd_image.depth.dim(), (480, 640));
+
+        let now = Instant::now();
+        for _ in 0..20 {
+            serial_from_rgbd_image(&cam, &rgbd_image);
+        }
+        let serial_time = now.elapsed();
+
+        let now = Instant::now();
+        for _ in 0..20 {
+            RangeImage::from_rgbd_image(&cam, &rgbd_image);
+        }
+        let parallel_time = now.elapsed();
+        println!(
+            "from_rgbd_image: serial {:?}, parallel {:?} per frame",
+            serial_time / 20,
+            parallel_time / 20
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn should_backproject_image_as_per_pixel(sample1: SlamTbDataset) {
+        let (cam, rgbd_image, _) = sample1.get(0).unwrap().into_parts();
+        let depth_scale = rgbd_image.depth_scale.unwrap() as f32;
+        let (points, mask) = cam.backproject_image(&rgbd_image.depth.view(), depth_scale);
+
+        for ((y, x), z) in rgbd_image.depth.indexed_iter() {
+            if *z > 0 {
+                assert_eq!(mask[(y, x)], 1);
+                assert_eq!(
+                    points[(y, x)],
+                    cam.backproject(x as f32, y as f32, *z as f32 * depth_scale)
+                );
+            } else {
+                assert_eq!(mask[(y, x)], 0);
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
     #[rstest]
     fn should_compute_normals(sample1: SlamTbDataset) {
         let (cam, rgbd_image, _) = sample1.get(0).unwrap().into_parts();
@@ -475,6 +900,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serde")]
     #[rstest]
     fn should_convert_into_pointcloud(sample1: SlamTbDataset) {
         let (cam, rgbd_image, _) = sample1.get(0).unwrap().into_parts();
@@ -484,6 +910,7 @@ mod tests {
         assert_eq!(pcl.len(), 270213);
     }
 
+    #[cfg(feature = "serde")]
     #[rstest]
     fn verify_pyramid(sample1: SlamTbDataset) {
         let mut pyramid = RangeImage::from_rgbd_frame(&sample1.get(0).unwrap()).pyramid(3, 1.0);
@@ -510,4 +937,288 @@ mod tests {
         }
         assert_eq!(pyramid.len(), 3);
     }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn should_save_depth_and_normals_png(sample1: SlamTbDataset) {
+        let mut im_pcl = RangeImage::from_rgbd_frame(&sample1.get(0).unwrap());
+        assert!(im_pcl
+            .save_normals_png("tests/outputs/out-range-image-normals.png")
+            .is_err());
+
+        im_pcl.compute_normals();
+        im_pcl
+            .save_depth_png("tests/outputs/out-range-image-depth.png", 1000.0)
+            .unwrap();
+        im_pcl
+            .save_normals_png("tests/outputs/out-range-image-normals.png")
+            .unwrap();
+
+        let depth = image::open("tests/outputs/out-range-image-depth.png")
+            .unwrap()
+            .into_luma16();
+        assert_eq!(depth.dimensions(), (640, 480));
+        assert!(depth.pixels().any(|p| p[0] > 0));
+
+        let normals = image::open("tests/outputs/out-range-image-normals.png")
+            .unwrap()
+            .into_rgb8();
+        assert_eq!(normals.dimensions(), (640, 480));
+        assert!(normals.pixels().any(|p| p[0] > 0 || p[1] > 0 || p[2] > 0));
+    }
+
+    #[test]
+    fn should_erode_step_edge() {
+        let camera = CameraIntrinsics::from_simple_intrinsic(10.0, 10.0, 4.0, 4.0, 8, 8);
+        let step_range_image = || {
+            RangeImage::from_intrinsics_fn(
+                &camera,
+                |row, col| {
+                    let z = if col < 4 { 1.0 } else { 2.0 };
+                    Some(camera.backproject(col as f32, row as f32, z))
+                },
+                |_, _| None,
+                |_, _| None,
+            )
+        };
+
+        let mut range_image = step_range_image();
+        range_image.erode_mask(1, 0.1);
+        assert_eq!(range_image.valid_points_count(), 48);
+        for row in 0..8 {
+            for col in 0..8 {
+                assert_eq!(
+                    range_image.get_point(row, col).is_none(),
+                    col == 3 || col == 4
+                );
+            }
+        }
+
+        let mut range_image = step_range_image();
+        range_image.erode_mask(2, 0.1);
+        assert_eq!(range_image.valid_points_count(), 32);
+
+        let mut range_image = step_range_image();
+        range_image.erode_mask(3, 2.0);
+        assert_eq!(range_image.valid_points_count(), 64);
+    }
+
+    #[test]
+    fn should_compute_curvature() {
+        // Left half is a plane at z = 2, the right half is a cylinder along the y axis.
+        const RADIUS: f32 = 0.5;
+        const STEP: f32 = 0.02;
+        let camera = CameraIntrinsics::from_simple_intrinsic(10.0, 10.0, 32.0, 16.0, 64, 32);
+        let axis_x = 48.0 * STEP;
+        let cylinder_z =
+            |col: usize| (RADIUS.powi(2) - (col as f32 * STEP - axis_x).powi(2)).sqrt();
+
+        let mut range_image = RangeImage::from_intrinsics_fn(
+            &camera,
+            |row, col| {
+                let z = if col < 32 { 2.0 } else { 2.0 - cylinder_z(col) };
+                Some(Vector3::new(col as f32 * STEP, row as f32 * STEP, z))
+            },
+            |_, col| {
+                if col < 32 {
+                    Some(Vector3::new(0.0, 0.0, -1.0))
+                } else {
+                    Some(Vector3::new(col as f32 * STEP - axis_x, 0.0, -cylinder_z(col)) / RADIUS)
+                }
+            },
+            |_, _| None,
+        );
+
+        let curvature = range_image.compute_curvature().unwrap();
+        assert_eq!(curvature.dim(), (32, 64));
+        for row in 0..32 {
+            for col in (1..30).chain(34..63) {
+                let expected = if col < 32 { 0.0 } else { 1.0 / RADIUS };
+                assert!(
+                    (curvature[(row, col)] - expected).abs() < 0.05,
+                    "{} at ({row}, {col})",
+                    curvature[(row, col)]
+                );
+            }
+        }
+
+        range_image.normals = None;
+        assert!(matches!(
+            range_image.compute_curvature(),
+            Err(A3dError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn should_convert_confidence_into_pointcloud() {
+        let camera = CameraIntrinsics::from_simple_intrinsic(10.0, 10.0, 4.0, 4.0, 8, 8);
+        let mut range_image = RangeImage::from_intrinsics_fn(
+            &camera,
+            |row, col| Some(camera.backproject(col as f32, row as f32, 1.0)),
+            |_, _| None,
+            |_, _| None,
+        );
+        assert!(PointCloud::from(&range_image).confidences.is_none());
+
+        // Zero confidence on the left half.
+        range_image.confidence = Some(Array2::from_shape_fn((8, 8), |(_, col)| {
+            if col < 4 {
+                0.0
+            } else {
+                col as f32 / 8.0
+            }
+        }));
+        // The conversion keeps every valid point.
+        let pcl = PointCloud::from(&range_image);
+        assert_eq!(pcl.len(), 64);
+        assert_eq!(
+            pcl.confidences.as_ref().unwrap(),
+            range_image
+                .confidence
+                .as_ref()
+                .unwrap()
+                .view()
+                .into_shape(64)
+                .unwrap()
+        );
+
+        let confident = pcl.filter_by_confidence(0.0);
+        assert_eq!(confident.len(), 32);
+        assert!(confident.points.iter().all(|point| point[0] >= 0.0));
+        assert!(confident
+            .confidences
+            .unwrap()
+            .iter()
+            .all(|confidence| *confidence >= 0.5));
+
+        let scaled = range_image.pyr_scale_down(1.0);
+        let confidence = scaled.confidence.unwrap();
+        assert_eq!(confidence.dim(), (4, 4));
+        assert_eq!(confidence[(0, 1)], 0.0);
+        assert_eq!(confidence[(0, 3)], 6.0 / 8.0);
+    }
+
+    #[test]
+    fn should_apply_depth_bias_and_max() {
+        let camera = CameraIntrinsics::from_simple_intrinsic(10.0, 10.0, 2.0, 2.0, 4, 4);
+        let depth = Array2::from_shape_fn((4, 4), |(row, _)| [1000, 1500, 2500, 0][row] as u16);
+        let rgbd_image =
+            RgbdImage::with_depth_scale(ndarray::Array3::zeros((4, 4, 3)), depth, 0.001);
+
+        let range_image = RangeImage::from_rgbd_image(&camera, &rgbd_image);
+        assert_eq!(range_image.valid_points_count(), 12);
+        assert_eq!(range_image.points[(2, 0)][2], 2.5);
+
+        let range_image = RangeImage::from_rgbd_image(
+            &camera,
+            &rgbd_image.with_depth_bias(0.1).with_depth_max(Some(2.0)),
+        );
+        assert_eq!(range_image.valid_points_count(), 8);
+        for col in 0..4 {
+            assert!((range_image.points[(0, col)][2] - 1.1).abs() < 1e-6);
+            assert!((range_image.points[(1, col)][2] - 1.6).abs() < 1e-6);
+            assert!(range_image.get_point(2, col).is_none());
+            assert!(range_image.get_point(3, col).is_none());
+        }
+    }
+
+    #[test]
+    fn should_compute_smoother_normals_with_plane_fit() {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        // Noisy plane facing the camera.
+        let camera = CameraIntrinsics::from_simple_intrinsic(50.0, 50.0, 32.0, 24.0, 64, 48);
+        let mut rng = SmallRng::seed_from_u64(6);
+        let depth = Array2::from_shape_fn((48, 64), |_| 1.0 + rng.gen_range(-0.005..0.005));
+        let mut range_image = RangeImage::from_intrinsics_fn(
+            &camera,
+            |row, col| Some(camera.backproject(col as f32, row as f32, depth[(row, col)])),
+            |_, _| None,
+            |_, _| None,
+        );
+
+        let mut mean_error = |method| {
+            range_image.compute_normals_with(method);
+            let normals = range_image.normals.as_ref().unwrap();
+            for normal in normals.iter() {
+                assert!((normal.norm() - 1.0).abs() < 1e-5);
+            }
+            normals
+                .iter()
+                .map(|normal| normal.angle(&Vector3::new(0.0, 0.0, -1.0)))
+                .sum::<f32>()
+                / normals.len() as f32
+        };
+
+        let cross_product_error = mean_error(NormalMethod::default());
+        let plane_fit_error = mean_error(NormalMethod::PlaneFit { radius: 2 });
+        assert!(
+            plane_fit_error * 2.0 < cross_product_error,
+            "{plane_fit_error} vs {cross_product_error}"
+        );
+        assert!(plane_fit_error < 0.1);
+    }
+
+    #[test]
+    fn should_interpolate_points_on_plane() {
+        use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+        // Tilted plane z = 1 + 0.5 * x.
+        let camera = CameraIntrinsics::from_simple_intrinsic(20.0, 20.0, 8.0, 6.0, 16, 12);
+        let range_image = RangeImage::from_intrinsics_fn(
+            &camera,
+            |row, col| {
+                if (row, col) == (5, 5) {
+                    return None;
+                }
+                let ray = camera.backproject(col as f32, row as f32, 1.0);
+                Some(ray / (1.0 - 0.5 * ray[0]))
+            },
+            |_, _| None,
+            |_, _| None,
+        );
+
+        let mut rng = SmallRng::seed_from_u64(3);
+        for _ in 0..100 {
+            let (u, v) = (rng.gen_range(0.0..15.0), rng.gen_range(0.0..11.0));
+            if let Some(point) = range_image.get_point_bilinear(u, v) {
+                assert!((point[2] - 1.0 - 0.5 * point[0]).abs() < 1e-5, "{point:?}");
+            }
+        }
+
+        assert_eq!(
+            range_image.get_point_bilinear(15.0, 11.0),
+            range_image.get_point(11, 15)
+        );
+        assert_eq!(
+            range_image.get_point_bilinear(2.0, 3.0),
+            range_image.get_point(3, 2)
+        );
+        assert!(range_image.get_point_bilinear(4.5, 4.5).is_none());
+        assert!(range_image.get_point_bilinear(5.0, 5.0).is_none());
+        assert!(range_image.get_point_bilinear(-0.5, 1.0).is_none());
+        assert!(range_image.get_point_bilinear(15.5, 1.0).is_none());
+    }
+
+    #[test]
+    fn should_check_point_bounds() {
+        let camera = CameraIntrinsics::from_simple_intrinsic(20.0, 20.0, 8.0, 6.0, 16, 12);
+        let range_image = RangeImage::from_intrinsics_fn(
+            &camera,
+            |row, col| Some(camera.backproject(col as f32, row as f32, 1.0)),
+            |_, _| None,
+            |_, _| None,
+        );
+
+        assert_eq!(
+            range_image.get_point_checked(3, 4),
+            range_image.get_point(4, 3)
+        );
+        assert!(range_image.get_point_checked(15, 11).is_some());
+        for (u, v) in [(-1, 0), (0, -1), (16, 0), (0, 12), (i32::MIN, i32::MAX)] {
+            assert_eq!(range_image.get_point_checked(u, v), None);
+        }
+        assert!(!range_image.is_valid(16, 0));
+        assert!(!range_image.is_valid(0, 12));
+    }
 }
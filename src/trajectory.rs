@@ -66,17 +66,80 @@ impl Trajectory {
             return self.clone();
         }
 
-        let first_inv = self.camera_to_world[0].inverse();
+        self.relative_to(&self.camera_to_world[0])
+    }
+
+    /// Creates a new trajectory with the poses expressed in the frame of `base`.
+    /// The relative transforms between the poses are preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - Transform from the new frame to the world.
+    ///
+    /// # Returns
+    ///
+    /// New trajectory with the poses transforming from camera to `base`'s frame.
+    pub fn relative_to(&self, base: &Transform) -> Self {
+        let base_inv = base.inverse();
         Self {
             camera_to_world: self
                 .camera_to_world
                 .iter()
-                .map(|transform| &first_inv * transform)
+                .map(|transform| &base_inv * transform)
                 .collect::<Vec<Transform>>(),
             times: self.times.clone(),
         }
     }
 
+    /// Concatenates two trajectories, e.g., the ones of consecutive submaps.
+    /// `other` is moved so its first pose matches the last pose of `self`, which are
+    /// the same pose, so it's added only once. Its timestamps are offset to continue from
+    /// the last one of `self`, see [`Self::concat_with_times`] for keeping them.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The trajectory to append.
+    ///
+    /// # Returns
+    ///
+    /// New trajectory with the poses of `self` followed by the ones of `other`.
+    pub fn concat(&self, other: &Trajectory) -> Self {
+        self.concat_with_times(other, true)
+    }
+
+    /// Concatenates two trajectories like [`Self::concat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The trajectory to append.
+    /// * `offset_times` - If true, offsets the timestamps of `other` to continue
+    ///   from the last timestamp of `self`. Otherwise, they're kept as they are.
+    ///
+    /// # Returns
+    ///
+    /// New trajectory with the poses of `self` followed by the ones of `other`.
+    pub fn concat_with_times(&self, other: &Trajectory, offset_times: bool) -> Self {
+        if other.is_empty() {
+            return self.clone();
+        }
+        let Some((last_pose, last_time)) = self.last() else {
+            return other.clone();
+        };
+
+        let other_to_self = &last_pose * &other.camera_to_world[0].inverse();
+        let time_offset = if offset_times {
+            last_time - other.times[0]
+        } else {
+            0.0
+        };
+
+        let mut trajectory = self.clone();
+        for (camera_to_world, time) in other.iter().skip(1) {
+            trajectory.push(&other_to_self * &camera_to_world, time + time_offset);
+        }
+        trajectory
+    }
+
     /// Creates a new trajectory with the given range.
     ///
     /// # Arguments
@@ -94,6 +157,125 @@ impl Trajectory {
         }
     }
 
+    /// Interpolates the pose at the given time. The translation is linearly interpolated
+    /// and the rotation is spherically interpolated between the two nearest poses.
+    /// The timestamps must be sorted.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Timestamp of the pose.
+    ///
+    /// # Returns
+    ///
+    /// The interpolated pose, or `None` if `time` is outside the trajectory's time range
+    /// or isn't finite.
+    pub fn pose_at(&self, time: f32) -> Option<Transform> {
+        let (first, last) = (*self.times.first()?, *self.times.last()?);
+        if !time.is_finite() || time < first || time > last {
+            return None;
+        }
+
+        // First pose at or after `time`. Always exists, and it's not the first pose unless it matches `time`.
+        let next = self.times.partition_point(|t| *t < time);
+        if self.times[next] == time {
+            return Some(self.camera_to_world[next].clone());
+        }
+
+        let (t0, t1) = (self.times[next - 1], self.times[next]);
+        let alpha = (time - t0) / (t1 - t0);
+        Some(Transform(
+            self.camera_to_world[next - 1]
+                .0
+                .lerp_slerp(&self.camera_to_world[next].0, alpha),
+        ))
+    }
+
+    /// Creates a new trajectory with poses at regular time steps, interpolated with [`Self::pose_at`].
+    /// Use it to compare trajectories captured at different frame rates.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - Time step between poses.
+    ///
+    /// # Returns
+    ///
+    /// New trajectory with poses from the first timestamp until the last one, every `dt`.
+    /// The last timestamp is only included if the time range is a multiple of `dt`.
+    /// `None` if `dt` isn't positive and finite, if it's too small to tell apart the
+    /// trajectory's timestamps, or if the first or last timestamp isn't finite.
+    pub fn resample(&self, dt: f32) -> Option<Self> {
+        if !dt.is_finite() || dt <= 0.0 {
+            return None;
+        }
+        if self.is_empty() {
+            return Some(self.clone());
+        }
+
+        let (first, last) = (self.times[0], self.times[self.len() - 1]);
+        if !first.is_finite() || !last.is_finite() {
+            return None;
+        }
+        // Smaller steps would repeat timestamps, this also bounds the number of poses.
+        if dt <= first.abs().max(last.abs()) * f32::EPSILON {
+            return None;
+        }
+
+        // Tolerates the rounding of the time range.
+        let num_poses = ((last - first) / dt + 1e-4).floor() as usize + 1;
+        (0..num_poses)
+            .map(|i| {
+                let time = (first + i as f32 * dt).min(last);
+                Some((self.pose_at(time)?, time))
+            })
+            .collect()
+    }
+
+    /// Total distance traveled by the camera, i.e., the sum of the distances
+    /// between consecutive camera centers.
+    pub fn path_length(&self) -> f32 {
+        self.camera_to_world
+            .windows(2)
+            .map(|poses| (poses[1].translation() - poses[0].translation()).norm())
+            .sum()
+    }
+
+    /// Computes a value for each pair of consecutive poses divided by their time difference.
+    /// Pairs with non-positive time differences get zero.
+    fn velocities(&self, delta: impl Fn(&Transform, &Transform) -> f32) -> Vec<f32> {
+        self.camera_to_world
+            .windows(2)
+            .zip(self.times.windows(2))
+            .map(|(poses, times)| {
+                let dt = times[1] - times[0];
+                if dt > 0.0 {
+                    delta(&poses[0], &poses[1]) / dt
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// Linear speed of the camera between consecutive poses.
+    ///
+    /// # Returns
+    ///
+    /// The speeds in distance units per time unit, with `len() - 1` elements. Pairs
+    /// of poses with zero or negative time differences get zero speed.
+    pub fn linear_velocities(&self) -> Vec<f32> {
+        self.velocities(|from, to| (to.translation() - from.translation()).norm())
+    }
+
+    /// Angular speed of the camera between consecutive poses.
+    ///
+    /// # Returns
+    ///
+    /// The speeds in radians per time unit, with `len() - 1` elements. Pairs
+    /// of poses with zero or negative time differences get zero speed.
+    pub fn angular_velocities(&self) -> Vec<f32> {
+        self.velocities(|from, to| (&from.inverse() * to).angle())
+    }
+
     /// Gets the last pose and timestamp.
     /// If the trajectory is empty, it returns `None`.
     pub fn last(&self) -> Option<(Transform, f32)> {
@@ -134,6 +316,7 @@ pub struct TrajectoryBuilder {
     trajectory: Trajectory,
     last: Transform,
     last_time: f32,
+    last_relative: Transform,
 }
 
 impl Default for TrajectoryBuilder {
@@ -144,6 +327,7 @@ impl Default for TrajectoryBuilder {
             trajectory: Trajectory::default(),
             last: Transform::eye(),
             last_time: 0.0,
+            last_relative: Transform::eye(),
         }
     }
 }
@@ -156,15 +340,79 @@ impl TrajectoryBuilder {
             trajectory,
             last: start_transform,
             last_time: start_time,
+            last_relative: Transform::eye(),
         }
     }
 
     /// Accumulates the given transform and timestamp into the previous ones and adds
     /// it to the trajectory being build.
     pub fn accumulate(&mut self, now_to_previous: &Transform, timestamp: Option<f32>) {
+        self.advance(now_to_previous, timestamp);
+        self.trajectory.push(self.last.clone(), self.last_time);
+    }
+
+    fn advance(&mut self, now_to_previous: &Transform, timestamp: Option<f32>) {
         self.last = now_to_previous * &self.last;
         self.last_time = timestamp.unwrap_or(self.last_time + 1.0);
+        self.last_relative = now_to_previous.clone();
+    }
+
+    /// Predicts the next relative transform with a constant velocity model, e.g., to
+    /// seed the ICP with [`crate::icp::multiscale::MultiscaleAlign::align_with_initial`].
+    ///
+    /// # Returns
+    ///
+    /// * The last accumulated transform, i.e., the motion between the last two poses.
+    ///   Identity if fewer than two poses were accumulated.
+    pub fn predict_next(&self) -> Transform {
+        self.last_relative.clone()
+    }
+
+    /// Whether a pose moved enough from the last recorded one to be a new keyframe.
+    ///
+    /// # Arguments
+    ///
+    /// * `candidate` - Candidate camera to world pose.
+    /// * `min_translation` - Minimum translation from the last keyframe.
+    /// * `min_rotation` - Minimum rotation angle from the last keyframe, in radians.
+    ///
+    /// # Returns
+    ///
+    /// * True if either threshold is reached or no pose was recorded yet.
+    pub fn should_insert_keyframe(
+        &self,
+        candidate: &Transform,
+        min_translation: f32,
+        min_rotation: f32,
+    ) -> bool {
+        match self.trajectory.last() {
+            Some((keyframe, _)) => {
+                let motion = &keyframe.inverse() * candidate;
+                motion.translation().norm() >= min_translation || motion.angle() >= min_rotation
+            }
+            None => true,
+        }
+    }
+
+    /// Accumulates the given transform like [`Self::accumulate`], but only adds the
+    /// resulting pose to the trajectory if it's a keyframe, see [`Self::should_insert_keyframe`].
+    ///
+    /// # Returns
+    ///
+    /// * True if the pose was added as a keyframe.
+    pub fn accumulate_keyframe(
+        &mut self,
+        now_to_previous: &Transform,
+        timestamp: Option<f32>,
+        min_translation: f32,
+        min_rotation: f32,
+    ) -> bool {
+        self.advance(now_to_previous, timestamp);
+        if !self.should_insert_keyframe(&self.last, min_translation, min_rotation) {
+            return false;
+        }
         self.trajectory.push(self.last.clone(), self.last_time);
+        true
     }
 
     /// Creates the trajectory at its current state.
@@ -182,3 +430,220 @@ impl TrajectoryBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    use super::{Trajectory, TrajectoryBuilder};
+    use crate::{metrics::TransformMetrics, transform::Transform};
+
+    /// Trajectory moving along a curve, with a pose every 0.5 time units.
+    fn sample_trajectory(len: usize) -> Trajectory {
+        (0..len)
+            .map(|i| {
+                let i = i as f32;
+                (
+                    Transform::new(
+                        &Vector3::new(i * 0.1, (i * 0.3).sin(), i * 0.05),
+                        &UnitQuaternion::from_euler_angles(0.0, i * 0.1, i * 0.02).into_inner(),
+                    ),
+                    i * 0.5,
+                )
+            })
+            .collect()
+    }
+
+    fn assert_same_pose(actual: &Transform, expected: &Transform) {
+        let metrics = TransformMetrics::new(actual, expected);
+        assert!(
+            metrics.angle < 1e-4 && metrics.translation < 1e-4,
+            "{metrics}"
+        );
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let trajectory = sample_trajectory(5);
+        let base = trajectory[2].clone();
+        let relative = trajectory.relative_to(&base);
+
+        assert_same_pose(&relative[2], &Transform::eye());
+        assert_eq!(relative.times, trajectory.times);
+        for i in 1..trajectory.len() {
+            assert_same_pose(
+                &relative.get_relative_transform(i - 1, i).unwrap(),
+                &trajectory.get_relative_transform(i - 1, i).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_concat() {
+        let trajectory = sample_trajectory(10);
+        let first = trajectory.slice(0, 5);
+        // Second submap, starting at the origin and with its own timestamps.
+        let mut second = trajectory.slice(4, 10).first_frame_at_origin();
+        second.times.iter_mut().for_each(|time| *time -= 2.0);
+
+        let concat = first.concat(&second);
+        assert_eq!(concat.len(), trajectory.len());
+        assert_eq!(concat.times, trajectory.times);
+        for (actual, expected) in concat
+            .camera_to_world
+            .iter()
+            .zip(trajectory.camera_to_world.iter())
+        {
+            assert_same_pose(actual, expected);
+        }
+
+        let concat = first.concat_with_times(&second, false);
+        assert_eq!(concat.times[5..], second.times[1..]);
+
+        assert_eq!(first.concat(&Trajectory::default()).len(), first.len());
+        assert_eq!(Trajectory::default().concat(&second).len(), second.len());
+    }
+
+    #[test]
+    fn test_path_length_and_velocities() {
+        let direction = Vector3::new(1.0, 2.0, -2.0) / 3.0;
+        let mut trajectory = (0..6)
+            .map(|i| {
+                let i = i as f32;
+                (
+                    Transform::new(
+                        &(direction * i * 0.2),
+                        &UnitQuaternion::from_euler_angles(0.0, i * 0.1, 0.0).into_inner(),
+                    ),
+                    i * 0.5,
+                )
+            })
+            .collect::<Trajectory>();
+
+        let endpoint_distance = (trajectory[5].translation() - trajectory[0].translation()).norm();
+        assert!((trajectory.path_length() - endpoint_distance).abs() < 1e-5);
+
+        let linear = trajectory.linear_velocities();
+        assert_eq!(linear.len(), 5);
+        assert!(linear.iter().all(|v| (v - 0.4).abs() < 1e-5), "{linear:?}");
+        let angular = trajectory.angular_velocities();
+        assert!(
+            angular.iter().all(|v| (v - 0.2).abs() < 1e-4),
+            "{angular:?}"
+        );
+
+        trajectory.times[3] = trajectory.times[2];
+        assert_eq!(trajectory.linear_velocities()[2], 0.0);
+        assert_eq!(trajectory.angular_velocities()[2], 0.0);
+
+        assert_eq!(Trajectory::default().path_length(), 0.0);
+        assert!(Trajectory::default().linear_velocities().is_empty());
+    }
+
+    #[test]
+    fn test_pose_at() {
+        let trajectory = sample_trajectory(5);
+
+        assert!(trajectory.pose_at(-0.1).is_none());
+        assert!(trajectory.pose_at(2.1).is_none());
+        assert!(trajectory.pose_at(f32::NAN).is_none());
+        assert!(trajectory.pose_at(f32::INFINITY).is_none());
+        assert!(Trajectory::default().pose_at(0.0).is_none());
+        assert!(trajectory.slice(0, 1).pose_at(0.0).is_some());
+        for (pose, time) in trajectory.iter() {
+            assert_same_pose(&trajectory.pose_at(time).unwrap(), &pose);
+        }
+
+        let middle = trajectory.pose_at(0.75).unwrap();
+        assert!(
+            (middle.translation()
+                - (trajectory[1].translation() + trajectory[2].translation()) * 0.5)
+                .norm()
+                < 1e-5
+        );
+        let angle_to_prev = (&trajectory[1].inverse() * &middle).angle();
+        let angle_to_next = (&middle.inverse() * &trajectory[2]).angle();
+        assert!((angle_to_prev - angle_to_next).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_resample() {
+        // Time range is 4.5.
+        let trajectory = sample_trajectory(10);
+
+        let resampled = trajectory.resample(0.1).unwrap();
+        assert_eq!(resampled.len(), 46);
+        assert_same_pose(&resampled[0], &trajectory[0]);
+        assert_same_pose(&resampled[45], &trajectory[9]);
+        assert!((resampled.times[45] - 4.5).abs() < 1e-5);
+        assert_same_pose(&resampled[5], &trajectory[1]);
+
+        let resampled = trajectory.resample(2.0).unwrap();
+        assert_eq!(resampled.times, vec![0.0, 2.0, 4.0]);
+        assert_same_pose(&resampled[1], &trajectory[4]);
+
+        for dt in [0.0, -0.1, f32::NAN, f32::INFINITY, 1e-12] {
+            assert!(trajectory.resample(dt).is_none(), "{dt}");
+        }
+
+        let nan_start = trajectory
+            .iter()
+            .enumerate()
+            .map(|(i, (pose, time))| (pose, if i == 0 { f32::NAN } else { time }))
+            .collect::<Trajectory>();
+        assert!(nan_start.resample(0.1).is_none());
+    }
+
+    #[test]
+    fn test_accumulate_keyframe() {
+        let step = Transform::new(
+            &Vector3::new(0.01, 0.0, 0.0),
+            &UnitQuaternion::from_euler_angles(0.0, 0.0, 0.0).into_inner(),
+        );
+        let mut builder = TrajectoryBuilder::with_start(Transform::eye(), 0.0);
+        let inserted = (0..25)
+            .map(|_| builder.accumulate_keyframe(&step, None, 0.095, 0.2))
+            .collect::<Vec<_>>();
+
+        // Every 10th step crosses the translation threshold.
+        let expected = (1..=25).map(|i| i % 10 == 0).collect::<Vec<_>>();
+        assert_eq!(inserted, expected);
+
+        let trajectory = builder.build();
+        assert_eq!(trajectory.len(), 3);
+        assert_eq!(trajectory.times, vec![0.0, 10.0, 20.0]);
+
+        let rotation = Transform::new(
+            &Vector3::zeros(),
+            &UnitQuaternion::from_euler_angles(0.0, 0.05, 0.0).into_inner(),
+        );
+        let mut builder = TrajectoryBuilder::with_start(Transform::eye(), 0.0);
+        assert!(!builder.should_insert_keyframe(&rotation, 0.1, 0.2));
+        let inserted = (0..5)
+            .filter(|_| builder.accumulate_keyframe(&rotation, None, 0.1, 0.2))
+            .count();
+        assert_eq!(inserted, 1);
+    }
+
+    #[test]
+    fn test_predict_next() {
+        let mut builder = TrajectoryBuilder::with_start(Transform::eye(), 0.0);
+        assert_same_pose(&builder.predict_next(), &Transform::eye());
+
+        // Repeats the same motion.
+        let step = Transform::new(
+            &Vector3::new(0.1, 0.02, 0.0),
+            &UnitQuaternion::from_euler_angles(0.0, 0.05, 0.0).into_inner(),
+        );
+        let pose = |i: usize| (0..i).fold(Transform::eye(), |pose, _| &step * &pose);
+        for i in 1..5 {
+            builder.accumulate(&(&pose(i) * &pose(i - 1).inverse()), None);
+        }
+
+        let expected = &pose(5) * &pose(4).inverse();
+        assert_same_pose(&builder.predict_next(), &expected);
+
+        builder.accumulate(&builder.predict_next(), None);
+        assert_same_pose(&builder.current_camera_to_world().unwrap(), &pose(5));
+    }
+}
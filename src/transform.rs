@@ -1,8 +1,10 @@
 use nalgebra::{
-    Isometry3, Matrix3, Matrix4, Quaternion, Rotation3, Translation3, UnitQuaternion, UnitVector3,
-    Vector3, Vector6,
+    Isometry3, Matrix3, Matrix4, Matrix6, Quaternion, Rotation3, Translation3, UnitQuaternion,
+    UnitVector3, Vector3, Vector6,
 };
-use ndarray::Array1;
+use ndarray::{parallel::prelude::*, Array1};
+
+use crate::pointcloud::PointCloud;
 
 use std::ops;
 
@@ -15,8 +17,42 @@ pub enum LieGroup {
 /// This wraps Isometry3 from nalgebra and provides methods for working with
 /// Align3d's data structures.
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_derive::Serialize, serde_derive::Deserialize),
+    serde(from = "TransformSerde", into = "TransformSerde")
+)]
 pub struct Transform(pub Isometry3<f32>);
 
+/// Serialized form of `Transform`: translation and unit quaternion, as (i, j, k, w).
+#[cfg(feature = "serde")]
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct TransformSerde {
+    translation: [f32; 3],
+    rotation: [f32; 4],
+}
+
+#[cfg(feature = "serde")]
+impl From<Transform> for TransformSerde {
+    fn from(transform: Transform) -> Self {
+        let translation = transform.0.translation.vector;
+        let rotation = transform.0.rotation.coords;
+        Self {
+            translation: [translation[0], translation[1], translation[2]],
+            rotation: [rotation[0], rotation[1], rotation[2], rotation[3]],
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<TransformSerde> for Transform {
+    fn from(transform: TransformSerde) -> Self {
+        let [x, y, z] = transform.translation;
+        let [i, j, k, w] = transform.rotation;
+        Transform::new(&Vector3::new(x, y, z), &Quaternion::new(w, i, j, k))
+    }
+}
+
 impl Default for Transform {
     /// Create a new transform with zero translation and zero rotation.
     fn default() -> Self {
@@ -117,6 +153,103 @@ impl Transform {
         }
     }
 
+    /// Logarithm map, the inverse of [`Transform::exp`] with [`LieGroup::Se3`].
+    ///
+    /// # Returns
+    ///
+    /// * 6D vector of the form [x, y, z, rx, ry, rz], where rx, ry, and rz are the rotation
+    ///   as a scaled axis.
+    pub fn log(&self) -> Vector6<f32> {
+        const EPSILON: f32 = 1e-8;
+
+        let omega = self.0.rotation.scaled_axis();
+        let theta_sq = omega.norm_squared();
+        let big_omega = omega.cross_matrix();
+
+        let inv_left_jacobian = if theta_sq < EPSILON {
+            Matrix3::identity() - big_omega * 0.5 + big_omega * big_omega * (1.0 / 12.0)
+        } else {
+            let theta = theta_sq.sqrt();
+            Matrix3::identity() - big_omega * 0.5
+                + (1.0 - theta * theta.sin() / (2.0 * (1.0 - theta.cos()))) / theta_sq
+                    * big_omega
+                    * big_omega
+        };
+
+        let xyz = inv_left_jacobian * self.0.translation.vector;
+        Vector6::new(xyz[0], xyz[1], xyz[2], omega[0], omega[1], omega[2])
+    }
+
+    /// Computes the weighted mean of transforms by iteratively averaging them
+    /// in the tangent space of the current estimate.
+    ///
+    /// # Arguments
+    ///
+    /// * transforms - Transforms to average. Must not be empty.
+    /// * weights - Optional non-negative weight per transform. Uniform if `None`.
+    ///
+    /// # Returns
+    ///
+    /// * The mean transform.
+    pub fn average(transforms: &[Transform], weights: Option<&[f32]>) -> Self {
+        const MAX_ITERATIONS: usize = 32;
+        const EPSILON: f32 = 1e-7;
+
+        assert!(!transforms.is_empty(), "No transforms to average");
+        let weights = match weights {
+            Some(weights) => {
+                assert_eq!(
+                    weights.len(),
+                    transforms.len(),
+                    "Weights and transforms must have the same length"
+                );
+                weights.to_vec()
+            }
+            None => vec![1.0; transforms.len()],
+        };
+        let weight_sum: f32 = weights.iter().sum();
+        assert!(weight_sum > 0.0, "Weights must not sum to zero");
+
+        let mut mean = transforms[0].clone();
+        for _ in 0..MAX_ITERATIONS {
+            let inv_mean = mean.inverse();
+            let delta = transforms
+                .iter()
+                .zip(weights.iter())
+                .fold(Vector6::zeros(), |acc, (transform, weight)| {
+                    acc + (&inv_mean * transform).log() * *weight
+                })
+                / weight_sum;
+
+            mean = &mean * &Transform::exp(&LieGroup::Se3(delta));
+            if delta.norm() < EPSILON {
+                break;
+            }
+        }
+
+        mean
+    }
+
+    /// Adjoint matrix, which maps twists of the form [x, y, z, rx, ry, rz] from the
+    /// local frame of this transform to its reference frame, i.e.,
+    /// `exp(adjoint * twist) == self * exp(twist) * self^-1`.
+    ///
+    /// # Returns
+    ///
+    /// * 6x6 matrix `[[R, [t]x R], [0, R]]`.
+    pub fn adjoint(&self) -> Matrix6<f32> {
+        let rotation = self.0.rotation.to_rotation_matrix().into_inner();
+        let translation = self.0.translation.vector;
+
+        let mut adjoint = Matrix6::zeros();
+        adjoint.fixed_slice_mut::<3, 3>(0, 0).copy_from(&rotation);
+        adjoint
+            .fixed_slice_mut::<3, 3>(0, 3)
+            .copy_from(&(translation.cross_matrix() * rotation));
+        adjoint.fixed_slice_mut::<3, 3>(3, 3).copy_from(&rotation);
+        adjoint
+    }
+
     /// Create a transform from a 4x4 matrix homogeneous matrix.
     pub fn from_matrix4(matrix: &Matrix4<f32>) -> Self {
         let translation = Translation3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
@@ -186,6 +319,23 @@ impl Transform {
         rhs
     }
 
+    /// Transforms the points and normals of a point cloud in place, without allocating
+    /// a new one. Colors and confidences are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * pcl - Point cloud to transform.
+    pub fn apply_to_point_cloud(&self, pcl: &mut PointCloud) {
+        pcl.points
+            .par_iter_mut()
+            .for_each(|point| *point = self.transform_vector(point));
+        if let Some(normals) = pcl.normals.as_mut() {
+            normals
+                .par_iter_mut()
+                .for_each(|normal| *normal = self.transform_normal(normal));
+        }
+    }
+
     /// Inverts the transform.
     pub fn inverse(&self) -> Self {
         Self(self.0.inverse())
@@ -291,17 +441,85 @@ pub trait TransformableMove<Type> {
     fn transform(&self, value: Type) -> Type;
 }
 
+/// Computes the least-squares rigid or similarity transform between two sets of
+/// corresponding points, using the closed-form solution from Umeyama,
+/// Least-squares estimation of transformation parameters between two point patterns, TPAMI 1991.
+///
+/// # Arguments
+///
+/// * `src` - Source points.
+/// * `dst` - Destination points, `dst[i]` corresponds to `src[i]`. Must have the same length as `src`.
+/// * `with_scale` - Whether to estimate a scale factor, i.e., a similarity transform.
+///
+/// # Returns
+///
+/// * The transform and the scale `s` minimizing the sum of `||dst[i] - (s * R * src[i] + t)||^2`.
+///   The scale is 1.0 if `with_scale` is false. The transform's translation already includes the scale,
+///   i.e., the points map as `s * R * src[i] + t`.
+pub fn umeyama(src: &[Vector3<f32>], dst: &[Vector3<f32>], with_scale: bool) -> (Transform, f32) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "Source and destination must have the same number of points."
+    );
+    if src.is_empty() {
+        return (Transform::eye(), 1.0);
+    }
+
+    let n = src.len() as f32;
+    let src_mean = src.iter().sum::<Vector3<f32>>() / n;
+    let dst_mean = dst.iter().sum::<Vector3<f32>>() / n;
+
+    let mut covariance = Matrix3::zeros();
+    let mut src_variance = 0.0;
+    for (s, d) in src.iter().zip(dst.iter()) {
+        let s = s - src_mean;
+        covariance += (d - dst_mean) * s.transpose();
+        src_variance += s.norm_squared();
+    }
+    covariance /= n;
+    src_variance /= n;
+
+    let svd = covariance.svd(true, true);
+    let (u, v_t) = (svd.u.unwrap(), svd.v_t.unwrap());
+    let mut sign = Vector3::new(1.0, 1.0, 1.0);
+    if u.determinant() * v_t.determinant() < 0.0 {
+        // Reflection, flips the axis of the smallest singular value.
+        sign[2] = -1.0;
+    }
+    let rotation = u * Matrix3::from_diagonal(&sign) * v_t;
+
+    let scale = if with_scale && src_variance > 0.0 {
+        svd.singular_values.dot(&sign) / src_variance
+    } else {
+        1.0
+    };
+    let translation = dst_mean - scale * rotation * src_mean;
+
+    (
+        Transform(Isometry3::from_parts(
+            Translation3::from(translation),
+            UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(rotation)),
+        )),
+        scale,
+    )
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::pointcloud::PointCloud;
     use crate::transform::LieGroup;
     use crate::unit_test::access::FlattenVector3;
+    use crate::unit_test::sample_teapot_pointcloud;
 
-    use super::Transform;
+    use super::{umeyama, Transform};
     use nalgebra::Vector6;
     use nalgebra::{Isometry3, Matrix4, Translation3, UnitQuaternion, Vector3, Vector4};
     use ndarray::array;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
 
     use ndarray::prelude::*;
+    use rstest::*;
 
     fn assert_array(f1: &Array2<f32>, f2: &Array2<f32>) -> bool {
         if f1.shape() != f2.shape() {
@@ -409,4 +627,111 @@ mod tests {
             &array![[2.9999998, 2.0, 5.0], [2.9999998, 2.0, 5.0]]
         ));
     }
+
+    #[test]
+    fn test_log() {
+        let params = Vector6::new(1.0, 2.0, 3.0, 0.4, 0.5, 0.3);
+        let log = Transform::exp(&LieGroup::Se3(params)).log();
+        assert!((log - params).norm() < 1e-5);
+
+        let params = Vector6::new(1.0, 2.0, 3.0, 0.0, 0.0, 0.0);
+        let log = Transform::exp(&LieGroup::Se3(params)).log();
+        assert!((log - params).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_average() {
+        let transform = Transform::exp(&LieGroup::Se3(Vector6::new(1.0, 2.0, 3.0, 0.4, 0.5, 0.3)));
+        let mean = Transform::average(&[transform.clone(), transform.clone()], None);
+        assert!((mean.log() - transform.log()).norm() < 1e-5);
+
+        let rot1 = Transform::exp(&LieGroup::Se3(Vector6::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.1)));
+        let rot2 = Transform::exp(&LieGroup::Se3(Vector6::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.3)));
+        let mean = Transform::average(&[rot1.clone(), rot2.clone()], None);
+        assert!((mean.log() - Vector6::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.2)).norm() < 1e-5);
+
+        let mean = Transform::average(&[rot1, rot2], Some(&[3.0, 1.0]));
+        assert!((mean.log() - Vector6::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.15)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn test_adjoint() {
+        let transform = Transform::exp(&LieGroup::Se3(Vector6::new(1.0, 2.0, 3.0, 0.4, 0.5, 0.3)));
+        let twist = Vector6::new(0.3, -0.2, 0.5, 0.1, -0.4, 0.2);
+
+        let adjoint_exp = Transform::exp(&LieGroup::Se3(transform.adjoint() * twist));
+        let conjugated =
+            &(&transform * &Transform::exp(&LieGroup::Se3(twist))) * &transform.inverse();
+
+        assert!((Matrix4::from(&adjoint_exp) - Matrix4::from(&conjugated)).norm() < 1e-5);
+    }
+
+    #[rstest]
+    fn test_apply_to_point_cloud(sample_teapot_pointcloud: PointCloud) {
+        let mut pcl = sample_teapot_pointcloud;
+        pcl.normals = Some(pcl.points.map(|point| point.normalize()));
+        let transform = Transform::exp(&LieGroup::Se3(Vector6::new(1.0, 2.0, 3.0, 0.4, 0.5, 0.3)));
+
+        let expected = &transform * &pcl;
+        transform.apply_to_point_cloud(&mut pcl);
+
+        for (point, expected_point) in pcl.points.iter().zip(expected.points.iter()) {
+            assert!((point - expected_point).norm() < 1e-5);
+        }
+        for (normal, expected_normal) in pcl
+            .normals
+            .unwrap()
+            .iter()
+            .zip(expected.normals.unwrap().iter())
+        {
+            assert!((normal - expected_normal).norm() < 1e-5);
+            assert!((normal.norm() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let transform = Transform::exp(&LieGroup::Se3(Vector6::new(1.0, 2.0, 3.0, 0.4, 0.5, 0.3)));
+        let json = serde_json::to_string(&transform).unwrap();
+        let restored: Transform = serde_json::from_str(&json).unwrap();
+
+        assert!((Matrix4::from(&transform) - Matrix4::from(&restored)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_umeyama() {
+        let mut rng = SmallRng::seed_from_u64(4);
+        let src = (0..50)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        let transform = Transform(Isometry3::from_parts(
+            Translation3::new(0.5, -1.0, 2.0),
+            UnitQuaternion::from_scaled_axis(Vector3::new(0.3, -0.6, 0.2)),
+        ));
+
+        let dst = src
+            .iter()
+            .map(|p| transform.transform_vector(p))
+            .collect::<Vec<_>>();
+        let (actual, scale) = umeyama(&src, &dst, false);
+        assert_eq!(scale, 1.0);
+        assert!((&actual.inverse() * &transform).angle() < 1e-4);
+        assert!((actual.translation() - transform.translation()).norm() < 1e-4);
+
+        let dst = src
+            .iter()
+            .map(|p| transform.transform_normal(p) * 1.7 + transform.translation())
+            .collect::<Vec<_>>();
+        let (actual, scale) = umeyama(&src, &dst, true);
+        assert!((scale - 1.7).abs() < 1e-4, "{scale}");
+        assert!((&actual.inverse() * &transform).angle() < 1e-4);
+        assert!((actual.translation() - transform.translation()).norm() < 1e-4);
+    }
 }
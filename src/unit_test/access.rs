@@ -3,6 +3,7 @@ use ndarray::{Array1, Array2, ArrayBase, Ix1, ViewRepr};
 
 use crate::error::A3dError;
 
+#[allow(dead_code)]
 pub trait ToVector3<T> {
     fn to_vector3(&self) -> Vector3<T>;
 }
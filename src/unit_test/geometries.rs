@@ -1,9 +1,9 @@
 use nalgebra::Vector3;
-use ndarray::Array1;
+use ndarray::{Array1, Array2};
 use rstest::fixture;
 
 use crate::{
-    io::{read_off, Geometry},
+    io::{read_off, Geometry, GeometryBuilder},
     mesh::compute_normals,
 };
 
@@ -26,3 +26,33 @@ pub fn sample_teapot_geometry() -> Geometry {
 
     geometry
 }
+
+/// Unit cube centered at the origin. Each side has its own 4 vertices, so vertices
+/// aren't shared between sides, and 2 counter-clockwise triangles.
+#[fixture]
+pub fn sample_cube_geometry() -> Geometry {
+    let mut points = Vec::new();
+    let mut faces = Vec::new();
+    for axis in 0..3 {
+        for sign in [1.0, -1.0] {
+            let normal = Vector3::ith(axis, sign);
+            let (mut u, mut v) = (
+                Vector3::ith((axis + 1) % 3, 0.5),
+                Vector3::ith((axis + 2) % 3, 0.5),
+            );
+            if sign < 0.0 {
+                std::mem::swap(&mut u, &mut v);
+            }
+
+            let first = points.len();
+            for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                points.push(normal * 0.5 + u * su + v * sv);
+            }
+            faces.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+    }
+
+    GeometryBuilder::new(Array1::from_vec(points))
+        .with_faces(Array2::from_shape_vec((12, 3), faces).unwrap())
+        .build()
+}
@@ -34,7 +34,7 @@ pub fn bloei_luma16() -> Array2<u16> {
         .into_ndarray2();
 
     image.iter_mut().for_each(|v| {
-        *v /= std::u16::MAX / 5000;
+        *v /= u16::MAX / 5000;
     });
     image
 }
@@ -1,12 +1,21 @@
 pub(crate) mod access;
 
+// The sample RGB-D datasets are loaded with the JSON reader of `SlamTbDataset`.
+#[cfg(feature = "serde")]
 mod datasets;
+#[cfg(feature = "serde")]
 pub(crate) use datasets::{sample_rgbd_dataset1, sample_rgbd_frame_dataset1, TestRgbdFrameDataset};
 mod geometries;
+pub(crate) use geometries::sample_cube_geometry;
+#[cfg(feature = "viz")]
 pub(crate) use geometries::sample_teapot_geometry;
 mod images;
 pub(crate) use images::{bloei_luma16, bloei_luma8, bloei_rgb};
 mod point_clouds;
-pub(crate) use point_clouds::{sample_pcl_ds1, sample_teapot_pointcloud, TestPclDataset};
+pub(crate) use point_clouds::sample_teapot_pointcloud;
+#[cfg(feature = "serde")]
+pub(crate) use point_clouds::{sample_pcl_ds1, TestPclDataset};
+#[cfg(feature = "serde")]
 mod range_images;
+#[cfg(feature = "serde")]
 pub(crate) use range_images::{sample_range_img_ds1, sample_range_img_ds2, TestRangeImageDataset};
@@ -1,9 +1,12 @@
 use ndarray::Array1;
 use rstest::fixture;
 
-use crate::{io::read_off, pointcloud::PointCloud, transform::Transform};
+use crate::{io::read_off, pointcloud::PointCloud};
 
+#[cfg(feature = "serde")]
 use super::{sample_range_img_ds1, TestRangeImageDataset};
+#[cfg(feature = "serde")]
+use crate::transform::Transform;
 
 #[fixture]
 pub fn sample_teapot_pointcloud() -> PointCloud {
@@ -15,10 +18,12 @@ pub fn sample_teapot_pointcloud() -> PointCloud {
     PointCloud::from_geometry(geometry)
 }
 
+#[cfg(feature = "serde")]
 pub struct TestPclDataset {
     dataset: TestRangeImageDataset,
 }
 
+#[cfg(feature = "serde")]
 impl TestPclDataset {
     pub fn get(&self, index: usize) -> PointCloud {
         let range_image = self
@@ -33,6 +38,7 @@ impl TestPclDataset {
     }
 }
 
+#[cfg(feature = "serde")]
 #[fixture]
 pub fn sample_pcl_ds1() -> TestPclDataset {
     TestPclDataset {
@@ -0,0 +1,201 @@
+use nalgebra::Vector3;
+use ndarray::ArrayView1;
+
+use crate::transform::{Transform, Transformable};
+
+use super::sphere3d::Sphere3Df;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Box3Df {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Box3Df {
+    /// Creates a box that contains nothing. It is the identity for [`Box3Df::union`].
+    pub fn empty() -> Self {
+        Self {
+            min: Vector3::repeat(f32::INFINITY),
+            max: Vector3::repeat(f32::NEG_INFINITY),
+        }
+    }
+
+    /// Creates the smallest box containing the points. Empty if there are no points.
+    pub fn from_points(points: &ArrayView1<Vector3<f32>>) -> Self {
+        points.iter().fold(Self::empty(), |accum, point| Self {
+            min: accum.min.inf(point),
+            max: accum.max.sup(point),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min
+            .iter()
+            .zip(self.max.iter())
+            .any(|(min, max)| min > max)
+    }
+
+    /// Creates the smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    /// Whether the point is inside the box or on its boundary.
+    pub fn contains(&self, point: &Vector3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    /// The 8 box corners.
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vector3::new(min.x, min.y, min.z),
+            Vector3::new(max.x, min.y, min.z),
+            Vector3::new(min.x, max.y, min.z),
+            Vector3::new(max.x, max.y, min.z),
+            Vector3::new(min.x, min.y, max.z),
+            Vector3::new(max.x, min.y, max.z),
+            Vector3::new(min.x, max.y, max.z),
+            Vector3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Bounds the box after a rigid transformation. The result is axis-aligned again,
+    /// so it may be larger than the original box.
+    pub fn transform(&self, transform: &Transform) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        self.corners()
+            .iter()
+            .map(|corner| transform.transform_vector(corner))
+            .fold(Self::empty(), |accum, corner| Self {
+                min: accum.min.inf(&corner),
+                max: accum.max.sup(&corner),
+            })
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The box size along each axis.
+    pub fn extent(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// The smallest sphere containing the box.
+    pub fn to_sphere(&self) -> Sphere3Df {
+        if self.is_empty() {
+            return Sphere3Df::empty();
+        }
+        Sphere3Df {
+            center: self.center(),
+            radius: self.extent().norm() * 0.5,
+        }
+    }
+}
+
+impl Transformable<Box3Df> for Transform {
+    fn transform(&self, value: &Box3Df) -> Box3Df {
+        value.transform(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{UnitQuaternion, Vector3};
+    use ndarray::array;
+
+    use super::Box3Df;
+    use crate::transform::Transform;
+
+    #[test]
+    fn test_from_points() {
+        let bounds = Box3Df::from_points(
+            &array![
+                Vector3::new(1.0, -2.0, 0.5),
+                Vector3::new(-1.0, 3.0, 0.0),
+                Vector3::new(0.0, 0.0, 2.0)
+            ]
+            .view(),
+        );
+        assert_eq!(bounds.min, Vector3::new(-1.0, -2.0, 0.0));
+        assert_eq!(bounds.max, Vector3::new(1.0, 3.0, 2.0));
+        assert_eq!(bounds.center(), Vector3::new(0.0, 0.5, 1.0));
+        assert_eq!(bounds.extent(), Vector3::new(2.0, 5.0, 2.0));
+        assert!(bounds.contains(&Vector3::new(0.0, 3.0, 1.0)));
+        assert!(!bounds.contains(&Vector3::new(0.0, 3.5, 1.0)));
+
+        let sphere = bounds.to_sphere();
+        assert!(bounds
+            .corners()
+            .iter()
+            .all(|corner| (corner - sphere.center).norm() <= sphere.radius + 1e-5));
+
+        let empty = Box3Df::from_points(&ndarray::Array1::<Vector3<f32>>::zeros(0).view());
+        assert!(empty.is_empty());
+        assert!(empty.to_sphere().is_empty());
+    }
+
+    #[test]
+    fn test_union() {
+        let a = Box3Df {
+            min: Vector3::new(0.0, 0.0, 0.0),
+            max: Vector3::new(1.0, 1.0, 1.0),
+        };
+        let b = Box3Df {
+            min: Vector3::new(-1.0, 0.5, 2.0),
+            max: Vector3::new(0.5, 3.0, 4.0),
+        };
+
+        let union = a.union(&b);
+        assert_eq!(union.min, Vector3::new(-1.0, 0.0, 0.0));
+        assert_eq!(union.max, Vector3::new(1.0, 3.0, 4.0));
+        assert!(a.corners().iter().all(|corner| union.contains(corner)));
+        assert!(b.corners().iter().all(|corner| union.contains(corner)));
+
+        assert_eq!(a.union(&Box3Df::empty()), a);
+        assert_eq!(Box3Df::empty().union(&b), b);
+    }
+
+    #[test]
+    fn test_transform() {
+        let bounds = Box3Df {
+            min: Vector3::new(-1.0, -2.0, -3.0),
+            max: Vector3::new(1.0, 2.0, 3.0),
+        };
+        let transform = Transform::new(
+            &Vector3::new(5.0, 0.0, -1.0),
+            &UnitQuaternion::from_euler_angles(0.3, -0.2, 0.9).into_inner(),
+        );
+
+        let transformed = bounds.transform(&transform);
+        let expected = Box3Df::from_points(
+            &ndarray::Array1::from_iter(
+                bounds
+                    .corners()
+                    .iter()
+                    .map(|corner| transform.transform_vector(corner)),
+            )
+            .view(),
+        );
+        assert!((transformed.min - expected.min).norm() < 1e-5);
+        assert!((transformed.max - expected.max).norm() < 1e-5);
+        assert!(
+            (transformed.center() - transform.transform_vector(&bounds.center())).norm() < 1e-5
+        );
+
+        // Rotation by 90 degrees around Z swaps the X and Y extents.
+        let rotation = Transform::new(
+            &Vector3::zeros(),
+            &UnitQuaternion::from_euler_angles(0.0, 0.0, std::f32::consts::FRAC_PI_2).into_inner(),
+        );
+        let extent = bounds.transform(&rotation).extent();
+        assert!((extent - Vector3::new(4.0, 2.0, 6.0)).norm() < 1e-5);
+    }
+}
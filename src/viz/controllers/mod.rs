@@ -1,5 +1,10 @@
 mod virtual_camera_controller;
-pub use virtual_camera_controller::{VirtualCameraControl, WASDVirtualCameraControl};
+pub use virtual_camera_controller::{
+    CameraControlKind, VirtualCameraControl, WASDVirtualCameraControl,
+};
+
+mod orbit_camera_controller;
+pub use orbit_camera_controller::OrbitCameraControl;
 
 mod state;
 pub use state::{FrameStepInfo, SceneState};
@@ -0,0 +1,193 @@
+use nalgebra_glm::{Mat4, Vec2};
+use winit::event::{ElementState, MouseButton};
+
+use crate::viz::{sphere3d::Sphere3Df, VirtualCamera, VirtualCameraSphericalBuilder};
+
+use super::{FrameStepInfo, SceneState, VirtualCameraControl};
+
+/// Largest elevation, avoids the view vector becoming parallel to the up vector.
+const MAX_ELEVATION: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+/// Camera controller that orbits around the center of the scene.
+/// Drag with the left mouse button to rotate and scroll to zoom.
+pub struct OrbitCameraControl {
+    pub camera: VirtualCamera,
+    /// Rotation angle in radians when dragging the cursor across the whole viewport.
+    pub rotation_sensitivity: f32,
+    /// Distance scale factor for each scrolled line.
+    pub zoom_factor: f32,
+    azimuth: f32,
+    elevation: f32,
+    distance: f32,
+    builder: VirtualCameraSphericalBuilder,
+    cursor_last_position: Vec2,
+}
+
+impl OrbitCameraControl {
+    /// Creates a new orbit controller framing the whole scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene_sphere`: Bounding sphere of the scene. The camera orbits around its center.
+    /// * `fov_y`: Vertical field of view in radians.
+    pub fn new(scene_sphere: &Sphere3Df, fov_y: f32) -> Self {
+        let builder = VirtualCameraSphericalBuilder::fit(scene_sphere, fov_y).near_plane(0.05);
+        Self {
+            camera: builder.build(),
+            rotation_sensitivity: std::f32::consts::PI,
+            zoom_factor: 1.1,
+            azimuth: 0.0,
+            elevation: 0.0,
+            distance: builder.get_distance(),
+            builder,
+            cursor_last_position: Vec2::zeros(),
+        }
+    }
+
+    /// Azimuth angle in radians.
+    pub fn azimuth(&self) -> f32 {
+        self.azimuth
+    }
+
+    /// Elevation angle in radians.
+    pub fn elevation(&self) -> f32 {
+        self.elevation
+    }
+
+    /// Distance from the camera to the center.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Rotates the camera by a cursor displacement.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta`: Cursor displacement in pixels.
+    /// * `viewport_size`: Viewport width and height.
+    pub fn rotate(&mut self, delta: Vec2, viewport_size: [f32; 2]) {
+        self.azimuth += delta[0] / viewport_size[0] * self.rotation_sensitivity;
+        self.elevation = (self.elevation + delta[1] / viewport_size[1] * self.rotation_sensitivity)
+            .clamp(-MAX_ELEVATION, MAX_ELEVATION);
+        self.update_camera();
+    }
+
+    /// Moves the camera towards (positive `lines`) or away from the center.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines`: Number of scrolled lines.
+    pub fn zoom(&mut self, lines: f32) {
+        self.distance = (self.distance * self.zoom_factor.powf(-lines)).max(1e-3);
+        self.update_camera();
+    }
+
+    fn update_camera(&mut self) {
+        self.camera = self
+            .builder
+            .azimuth(self.azimuth)
+            .elevation(self.elevation)
+            .distance(self.distance)
+            .build();
+    }
+}
+
+impl VirtualCameraControl for OrbitCameraControl {
+    fn key_event(&mut self, _window_state: &FrameStepInfo, _scene_state: &SceneState) {}
+
+    fn cursor_moved(&mut self, x: f64, y: f64, window_state: &FrameStepInfo, _: &SceneState) {
+        let current_position = Vec2::new(x as f32, y as f32);
+        if let Some(ElementState::Pressed) = window_state.mouse_state.get(&MouseButton::Left) {
+            self.rotate(
+                current_position - self.cursor_last_position,
+                window_state.viewport_size,
+            );
+        }
+
+        self.cursor_last_position = current_position;
+    }
+
+    fn mouse_wheel(&mut self, delta: f32, _window_state: &FrameStepInfo, _: &SceneState) {
+        self.zoom(delta);
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        self.camera.matrix()
+    }
+
+    fn projection_matrix(&self) -> Mat4 {
+        self.camera.projection.matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use nalgebra_glm::Vec2;
+    use winit::event::{ElementState, MouseButton};
+
+    use crate::viz::{
+        controllers::{FrameStepInfo, SceneState, VirtualCameraControl},
+        sphere3d::Sphere3Df,
+    };
+
+    use super::{OrbitCameraControl, MAX_ELEVATION};
+
+    fn controller() -> OrbitCameraControl {
+        OrbitCameraControl::new(
+            &Sphere3Df {
+                center: Vector3::new(1.0, 2.0, 3.0),
+                radius: 2.0,
+            },
+            std::f32::consts::FRAC_PI_2,
+        )
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut controller = controller();
+        let initial_distance = controller.distance();
+
+        controller.rotate(Vec2::new(400.0, 0.0), [800.0, 600.0]);
+        assert!((controller.azimuth() - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(controller.elevation(), 0.0);
+
+        controller.rotate(Vec2::new(0.0, 150.0), [800.0, 600.0]);
+        assert!((controller.elevation() - std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+
+        controller.rotate(Vec2::new(0.0, 6000.0), [800.0, 600.0]);
+        assert_eq!(controller.elevation(), MAX_ELEVATION);
+
+        // Keeps orbiting around the center.
+        let eye_distance = (controller.camera.eye - Vector3::new(1.0, 2.0, 3.0)).norm();
+        assert!((eye_distance - initial_distance).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_cursor_drag() {
+        let mut controller = controller();
+        let mut window_state = FrameStepInfo::new([800.0, 600.0]);
+        let scene_state = SceneState::default();
+
+        controller.cursor_moved(100.0, 100.0, &window_state, &scene_state);
+        controller.cursor_moved(300.0, 100.0, &window_state, &scene_state);
+        assert_eq!(controller.azimuth(), 0.0);
+
+        window_state
+            .mouse_state
+            .insert(MouseButton::Left, ElementState::Pressed);
+        controller.cursor_moved(500.0, 100.0, &window_state, &scene_state);
+        assert!((controller.azimuth() - std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_zoom() {
+        let mut controller = controller();
+        let initial_distance = controller.distance();
+
+        controller.mouse_wheel(1.0, &FrameStepInfo::default(), &SceneState::default());
+        assert!(controller.distance() < initial_distance);
+        controller.zoom(-1.0);
+        assert!((controller.distance() - initial_distance).abs() < 1e-5);
+    }
+}
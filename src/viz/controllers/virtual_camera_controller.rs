@@ -14,10 +14,32 @@ pub trait VirtualCameraControl {
         window_state: &FrameStepInfo,
         scene_bounds: &SceneState,
     );
+    /// Handles mouse wheel scrolling. Does nothing by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta`: Scrolled amount in lines. Positive when scrolling up.
+    fn mouse_wheel(
+        &mut self,
+        _delta: f32,
+        _window_state: &FrameStepInfo,
+        _scene_bounds: &SceneState,
+    ) {
+    }
     fn view_matrix(&self) -> Mat4;
     fn projection_matrix(&self) -> Mat4;
 }
 
+/// Available camera controllers for the windows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraControlKind {
+    /// First person controller, see [`WASDVirtualCameraControl`].
+    #[default]
+    Wasd,
+    /// Rotates around the scene, see [`super::OrbitCameraControl`].
+    Orbit,
+}
+
 pub struct WASDVirtualCameraControl {
     pub camera: VirtualCamera,
     pub velocity: f32,
@@ -41,6 +41,8 @@ impl NormalF32 {
     }
 }
 
+/// RGB color packed as `0x00RRGGBB`. Geometries must pass their channels in RGB order,
+/// and the shaders unpack them in the same order.
 #[derive(BufferContents, Vertex)]
 #[repr(C)]
 pub struct ColorU8 {
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+use vulkano::memory::allocator::MemoryAllocator;
+
+use crate::{
+    transform::Transform,
+    viz::{
+        controllers::FrameStepInfo,
+        node::{node_ref, CommandBuffersContext, Node, NodeProperties, NodeRef},
+    },
+};
+
+use super::vklines::VkLines;
+
+/// Draws the X, Y and Z axes of a coordinate system with red, green and blue lines.
+pub struct AxisNode {
+    pub properties: NodeProperties,
+    lines: Arc<VkLines>,
+}
+
+impl AxisNode {
+    /// Creates a new axis node.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_allocator` - Vulkan's memory allocator.
+    /// * `transform` - Placement of the coordinate system in the world.
+    /// * `size` - Length of each axis.
+    pub fn new(
+        memory_allocator: &(impl MemoryAllocator + ?Sized),
+        transform: &Transform,
+        size: f32,
+    ) -> NodeRef<Self> {
+        let origin = transform.translation();
+        let segments = [
+            (Vector3::x(), Vector3::new(255, 0, 0)),
+            (Vector3::y(), Vector3::new(0, 255, 0)),
+            (Vector3::z(), Vector3::new(0, 0, 255)),
+        ]
+        .map(|(axis, color)| ([origin, transform.transform_vector(&(axis * size))], color));
+
+        let lines = VkLines::from_segments(memory_allocator, &segments);
+        node_ref(Self {
+            properties: NodeProperties {
+                bounding_sphere: lines.bounding_sphere(),
+                ..Default::default()
+            },
+            lines,
+        })
+    }
+}
+
+impl Node for AxisNode {
+    fn properties(&self) -> &NodeProperties {
+        &self.properties
+    }
+
+    fn properties_mut(&mut self) -> &mut NodeProperties {
+        &mut self.properties
+    }
+
+    fn new_instance(&self) -> NodeRef<dyn Node> {
+        node_ref(Self {
+            properties: self.properties,
+            lines: self.lines.clone(),
+        })
+    }
+
+    fn collect_command_buffers(
+        &self,
+        context: &mut CommandBuffersContext,
+        window_state: &FrameStepInfo,
+    ) {
+        if !self.properties.visible {
+            return;
+        }
+
+        self.lines.draw(context, window_state, &self.properties);
+    }
+}
+
+/// Draws a ground grid on the XZ plane, centered at the origin.
+pub struct GridNode {
+    pub properties: NodeProperties,
+    lines: Arc<VkLines>,
+}
+
+impl GridNode {
+    /// Creates a new grid node.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_allocator` - Vulkan's memory allocator.
+    /// * `size` - Length of the grid's side.
+    /// * `cells` - Number of cells along each side.
+    pub fn new(
+        memory_allocator: &(impl MemoryAllocator + ?Sized),
+        size: f32,
+        cells: usize,
+    ) -> NodeRef<Self> {
+        let lines = VkLines::from_segments(memory_allocator, &grid_segments(size, cells));
+        node_ref(Self {
+            properties: NodeProperties {
+                bounding_sphere: lines.bounding_sphere(),
+                ..Default::default()
+            },
+            lines,
+        })
+    }
+}
+
+/// Computes the lines of a grid on the XZ plane.
+fn grid_segments(size: f32, cells: usize) -> Vec<([Vector3<f32>; 2], Vector3<u8>)> {
+    let half_size = size * 0.5;
+    let color = Vector3::new(128, 128, 128);
+    (0..=cells)
+        .flat_map(|i| {
+            let offset = -half_size + size * i as f32 / cells as f32;
+            [
+                (
+                    [
+                        Vector3::new(offset, 0.0, -half_size),
+                        Vector3::new(offset, 0.0, half_size),
+                    ],
+                    color,
+                ),
+                (
+                    [
+                        Vector3::new(-half_size, 0.0, offset),
+                        Vector3::new(half_size, 0.0, offset),
+                    ],
+                    color,
+                ),
+            ]
+        })
+        .collect()
+}
+
+impl Node for GridNode {
+    fn properties(&self) -> &NodeProperties {
+        &self.properties
+    }
+
+    fn properties_mut(&mut self) -> &mut NodeProperties {
+        &mut self.properties
+    }
+
+    fn new_instance(&self) -> NodeRef<dyn Node> {
+        node_ref(Self {
+            properties: self.properties,
+            lines: self.lines.clone(),
+        })
+    }
+
+    fn collect_command_buffers(
+        &self,
+        context: &mut CommandBuffersContext,
+        window_state: &FrameStepInfo,
+    ) {
+        if !self.properties.visible {
+            return;
+        }
+
+        self.lines.draw(context, window_state, &self.properties);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use vulkano::memory::allocator::StandardMemoryAllocator;
+
+    use crate::{
+        transform::Transform,
+        viz::{unit_test::vk_manager, Manager, OffscreenRenderer},
+    };
+
+    use super::{grid_segments, AxisNode};
+
+    #[test]
+    fn test_grid_segments() {
+        let segments = grid_segments(2.0, 4);
+        assert_eq!(segments.len(), 10);
+        assert!(segments
+            .iter()
+            .flat_map(|(points, _)| points.iter())
+            .all(|p| p[1] == 0.0 && p[0].abs() <= 1.0 && p[2].abs() <= 1.0));
+    }
+
+    #[ignore]
+    #[rstest]
+    fn test_render_axis(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+        let memory_allocator = StandardMemoryAllocator::new_default(vk_manager.device.clone());
+        let node = AxisNode::new(&memory_allocator, &Transform::eye(), 0.5);
+
+        let image = renderer.render(node).to_image();
+        image.save("tests/outputs/viz-axis-node.png").unwrap();
+        assert!(image.pixels().any(|pixel| pixel.0 == [255, 0, 0, 255]));
+        assert!(image.pixels().any(|pixel| pixel.0 == [0, 255, 0, 255]));
+    }
+}
@@ -2,9 +2,18 @@ mod datatypes;
 pub use datatypes::{Array2f32, ColorU8, NormalF32, PositionF32};
 
 mod vkpointcloud;
-pub use vkpointcloud::{VkPointCloud, VkPointCloudNode};
+pub use vkpointcloud::{PointCloudShading, VkPointCloud, VkPointCloudNode};
 
 mod vkmesh;
 pub use vkmesh::{VkMesh, VkMeshNode};
 
+mod vklines;
+pub use vklines::VkLines;
+
+mod vktrajectory;
+pub use vktrajectory::TrajectoryNode;
+
+mod helper_nodes;
+pub use helper_nodes::{AxisNode, GridNode};
+
 pub mod sample_nodes;
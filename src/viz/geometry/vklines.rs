@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+use vulkano::{
+    buffer::{
+        allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo},
+        Buffer, BufferCreateInfo, BufferUsage, Subbuffer,
+    },
+    descriptor_set::{
+        allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet, WriteDescriptorSet,
+    },
+    memory::allocator::{
+        AllocationCreateInfo, MemoryAllocator, MemoryUsage, StandardMemoryAllocator,
+    },
+    pipeline::{
+        graphics::{
+            depth_stencil::DepthStencilState,
+            input_assembly::{InputAssemblyState, PrimitiveTopology},
+            vertex_input::Vertex,
+            viewport::{Viewport, ViewportState},
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::Subpass,
+};
+
+use crate::viz::{
+    controllers::FrameStepInfo,
+    node::{CommandBuffersContext, NodeProperties},
+    sphere3d::Sphere3Df,
+};
+
+use super::datatypes::{ColorU8, PositionF32};
+
+/// Line segments in GPU. Used by helper nodes like trajectories and axes.
+pub struct VkLines {
+    /// Segment end points, every two consecutive points form a segment.
+    pub points: Subbuffer<[PositionF32]>,
+    /// RGB color of each point.
+    pub colors: Subbuffer<[ColorU8]>,
+    bounding_sphere: Sphere3Df,
+    number_of_points: usize,
+}
+
+impl VkLines {
+    /// Uploads line segments into the GPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_allocator` - Vulkan's memory allocator.
+    /// * `segments` - Segments as pairs of end points and their color. Must not be empty.
+    pub fn from_segments(
+        memory_allocator: &(impl MemoryAllocator + ?Sized),
+        segments: &[([Vector3<f32>; 2], Vector3<u8>)],
+    ) -> Arc<Self> {
+        let create_info = BufferCreateInfo {
+            usage: BufferUsage::VERTEX_BUFFER,
+            ..Default::default()
+        };
+        let alloc_info = AllocationCreateInfo {
+            usage: MemoryUsage::Upload,
+            ..Default::default()
+        };
+
+        let points = segments.iter().flat_map(|(points, _)| points.iter());
+        Arc::new(Self {
+            points: Buffer::from_iter(
+                memory_allocator,
+                create_info.clone(),
+                alloc_info.clone(),
+                points.clone().map(|p| PositionF32::new(p[0], p[1], p[2])),
+            )
+            .unwrap(),
+            colors: Buffer::from_iter(
+                memory_allocator,
+                create_info,
+                alloc_info,
+                segments
+                    .iter()
+                    .flat_map(|(_, color)| [color, color])
+                    .map(|c| ColorU8::new(c[0], c[1], c[2])),
+            )
+            .unwrap(),
+            bounding_sphere: Sphere3Df::from_point_iter(points.copied()),
+            number_of_points: segments.len() * 2,
+        })
+    }
+
+    /// Bounding sphere of the segments.
+    pub fn bounding_sphere(&self) -> Sphere3Df {
+        self.bounding_sphere
+    }
+
+    /// Number of segments.
+    pub fn len(&self) -> usize {
+        self.number_of_points / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.number_of_points == 0
+    }
+
+    /// Records the commands for drawing the lines.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The node's drawing context.
+    /// * `window_state` - Current window state.
+    /// * `properties` - Properties of the node that owns the lines.
+    pub(crate) fn draw(
+        &self,
+        context: &mut CommandBuffersContext,
+        window_state: &FrameStepInfo,
+        properties: &NodeProperties,
+    ) {
+        let pipeline = context
+            .pipelines
+            .entry("VkLines".to_string())
+            .or_insert_with(|| {
+                let vs = vs::load(context.device.clone()).unwrap();
+                let fs = fs::load(context.device.clone()).unwrap();
+                let subpass = Subpass::from(context.render_pass.clone(), 0).unwrap();
+                let depth_stencil_state = if subpass.has_depth() {
+                    DepthStencilState::simple_depth_test()
+                } else {
+                    DepthStencilState::disabled()
+                };
+
+                GraphicsPipeline::start()
+                    .vertex_input_state([PositionF32::per_vertex(), ColorU8::per_vertex()])
+                    .vertex_shader(vs.entry_point("main").unwrap(), ())
+                    .input_assembly_state(
+                        InputAssemblyState::new().topology(PrimitiveTopology::LineList),
+                    )
+                    .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
+                        Viewport {
+                            origin: [0.0, 0.0],
+                            dimensions: window_state.viewport_size,
+                            depth_range: 0.0..1.0,
+                        },
+                    ]))
+                    .fragment_shader(fs.entry_point("main").unwrap(), ())
+                    .depth_stencil_state(depth_stencil_state)
+                    .render_pass(subpass)
+                    .build(context.device.clone())
+                    .unwrap()
+            });
+
+        let memory_allocator =
+            Arc::new(StandardMemoryAllocator::new_default(context.device.clone()));
+
+        let uniform_buffer = SubbufferAllocator::new(
+            memory_allocator,
+            SubbufferAllocatorCreateInfo {
+                buffer_usage: BufferUsage::UNIFORM_BUFFER,
+                ..Default::default()
+            },
+        );
+
+        let uniform_buffer_subbuffer = {
+            let view_matrix = context.view_matrix * properties.transformation;
+            let projection_worldview = context.projection_matrix * view_matrix;
+
+            let subbuffer = uniform_buffer.allocate_sized().unwrap();
+            *subbuffer.write().unwrap() = vs::Data {
+                projection_worldview: projection_worldview.into(),
+            };
+
+            subbuffer
+        };
+        let descriptor_set_allocator = StandardDescriptorSetAllocator::new(context.device.clone());
+
+        let layout = pipeline.layout().set_layouts().get(0).unwrap();
+        let descriptor_set = PersistentDescriptorSet::new(
+            &descriptor_set_allocator,
+            layout.clone(),
+            [WriteDescriptorSet::buffer(0, uniform_buffer_subbuffer)],
+        )
+        .unwrap();
+
+        context
+            .builder
+            .bind_pipeline_graphics(pipeline.clone())
+            .bind_vertex_buffers(0, (self.points.clone(), self.colors.clone()))
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                descriptor_set,
+            )
+            .draw(self.number_of_points as u32, 1, 0, 0)
+            .unwrap();
+    }
+}
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "resources/shaders/vklines/lines.vert",
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "resources/shaders/vklines/lines.frag"
+    }
+}
@@ -215,7 +215,11 @@ impl Node for VkMeshNode {
                 let fs = fs::load(context.device.clone()).unwrap();
                 GraphicsPipeline::start()
                     .render_pass(Subpass::from(context.render_pass.clone(), 0).unwrap())
-                    .vertex_input_state([PositionF32::per_vertex(), NormalF32::per_vertex()])
+                    .vertex_input_state([
+                        PositionF32::per_vertex(),
+                        NormalF32::per_vertex(),
+                        ColorU8::per_vertex(),
+                    ])
                     .input_assembly_state(
                         InputAssemblyState::new().topology(PrimitiveTopology::TriangleList),
                     )
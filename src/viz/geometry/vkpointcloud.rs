@@ -94,7 +94,7 @@ impl VkPointCloud {
                     .as_ref()
                     .unwrap()
                     .iter()
-                    .map(|v| ColorU8::new(v[2], v[1], v[0])),
+                    .map(|v| ColorU8::new(v[0], v[1], v[2])),
             )
             .unwrap(),
             number_of_points,
@@ -110,8 +110,77 @@ impl VkPointCloud {
     }
 }
 
+/// How the points of a [`VkPointCloudNode`] are colored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointCloudShading {
+    /// Uses the RGB colors of the point cloud.
+    Color,
+    /// Uses the same RGB color for all points.
+    Flat(Vector3<u8>),
+    /// Uses the absolute normal's XYZ as RGB. Useful to inspect normal estimation.
+    Normal,
+}
+
+impl PointCloudShading {
+    /// Mode identifier in the shaders.
+    fn shader_mode(&self) -> u32 {
+        match self {
+            PointCloudShading::Color => 0,
+            PointCloudShading::Flat(_) => 1,
+            PointCloudShading::Normal => 2,
+        }
+    }
+}
+
+/// Node that renders a [`VkPointCloud`] as splats.
+///
+/// # Example
+///
+/// Key bindings that change the point size and switch the shading mode while the
+/// window is shown:
+///
+/// ```no_run
+/// use align3d::viz::{
+///     geometry::{PointCloudShading, VkPointCloud, VkPointCloudNode},
+///     Manager, Window,
+/// };
+/// use winit::event::VirtualKeyCode;
+/// # fn load_point_cloud() -> align3d::pointcloud::PointCloud { unimplemented!() }
+///
+/// let mut manager = Manager::default();
+/// // A point cloud with normals and colors.
+/// let pointcloud = load_point_cloud();
+/// let node = VkPointCloudNode::new(VkPointCloud::from_pointcloud(
+///     &manager.memory_allocator,
+///     &pointcloud,
+/// ));
+/// let mut window = Window::create(&mut manager, node.clone());
+///
+/// // +/- grow or shrink the points, C, F and N switch the shading.
+/// window.on_key = Some(Box::new(move |vkeycode, _window_state| {
+///     let mut node = node.borrow_mut();
+///     match vkeycode {
+///         VirtualKeyCode::Plus => node.point_size *= 1.5,
+///         VirtualKeyCode::Minus => node.point_size /= 1.5,
+///         VirtualKeyCode::C => {
+///             node.set_shading(PointCloudShading::Color);
+///         }
+///         VirtualKeyCode::F => {
+///             node.set_shading(PointCloudShading::Flat([255, 255, 0].into()));
+///         }
+///         VirtualKeyCode::N => {
+///             node.set_shading(PointCloudShading::Normal);
+///         }
+///         _ => (),
+///     }
+/// }));
+/// window.show();
+/// ```
 pub struct VkPointCloudNode {
     pub properties: NodeProperties,
+    /// Radius of the rendered points, in world units.
+    pub point_size: f32,
+    shading: PointCloudShading,
     point_cloud: Arc<VkPointCloud>,
 }
 
@@ -128,13 +197,27 @@ impl VkPointCloudNode {
                 ),
                 ..Default::default()
             },
+            point_size: 0.0005,
+            shading: PointCloudShading::Color,
             point_cloud: point_cloud.clone(),
         }))
     }
 
+    /// Sets how the points are colored.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The shading mode.
+    pub fn set_shading(&mut self, mode: PointCloudShading) -> &mut Self {
+        self.shading = mode;
+        self
+    }
+
     pub fn new_node(&self) -> NodeRef<Self> {
         Rc::new(RefCell::new(Self {
             properties: self.properties,
+            point_size: self.point_size,
+            shading: self.shading,
             point_cloud: self.point_cloud.clone(),
         }))
     }
@@ -173,6 +256,8 @@ impl Node for VkPointCloudNode {
     fn new_instance(&self) -> NodeRef<dyn Node> {
         node_ref(VkPointCloudNode {
             properties: self.properties,
+            point_size: self.point_size,
+            shading: self.shading,
             point_cloud: self.point_cloud.clone(),
         })
     }
@@ -238,6 +323,17 @@ impl Node for VkPointCloudNode {
                 normal_worldview: get_normal_matrix(&view_matrix),
                 worldview: view_matrix.into(),
                 projection_worldview: projection_worldview.into(),
+                flat_color: match self.shading {
+                    PointCloudShading::Flat(color) => [
+                        color[0] as f32 / 255.0,
+                        color[1] as f32 / 255.0,
+                        color[2] as f32 / 255.0,
+                        1.0,
+                    ],
+                    _ => [0.0, 0.0, 0.0, 1.0],
+                },
+                point_size: self.point_size,
+                shading: self.shading.shader_mode(),
             };
 
             let subbuffer = uniform_buffer.allocate_sized().unwrap();
@@ -301,8 +397,11 @@ impl MakeNode for RangeImage {
 
 #[cfg(test)]
 mod tests {
+    use crate::io::read_off;
+    use crate::mesh::compute_normals;
     use crate::pointcloud::PointCloud;
     use crate::unit_test::sample_teapot_pointcloud;
+    use ndarray::Array1;
     use rstest::*;
     use vulkano::memory::allocator::StandardMemoryAllocator;
 
@@ -318,6 +417,91 @@ mod tests {
         (manager, renderer)
     }
 
+    /// Counts the pixels that are not the clear color.
+    fn count_drawn_pixels(image: &image::RgbaImage) -> usize {
+        image
+            .pixels()
+            .filter(|pixel| pixel.0 != [0, 0, 255, 255])
+            .count()
+    }
+
+    #[ignore]
+    #[rstest]
+    fn test_point_size(offscreen_renderer: (Manager, OffscreenRenderer)) {
+        let (manager, mut offscreen_renderer) = offscreen_renderer;
+        let mem_alloc = StandardMemoryAllocator::new_default(manager.device);
+
+        let mut pointcloud = PointCloud::zeros(25);
+        for (i, point) in pointcloud.points.iter_mut().enumerate() {
+            *point = Vector3::new((i % 5) as f32 * 0.4 - 0.8, (i / 5) as f32 * 0.4 - 0.8, 0.5);
+        }
+        pointcloud
+            .normals
+            .as_mut()
+            .unwrap()
+            .fill(Vector3::new(0.0, 0.0, 1.0));
+        pointcloud
+            .colors
+            .as_mut()
+            .unwrap()
+            .fill(Vector3::new(255, 255, 255));
+
+        let node = VkPointCloudNode::new(VkPointCloud::from_pointcloud(&mem_alloc, &pointcloud));
+        node.borrow_mut().point_size = 0.01;
+        let small = count_drawn_pixels(&offscreen_renderer.render(node.clone()).to_image());
+        node.borrow_mut().point_size = 0.05;
+        let large = count_drawn_pixels(&offscreen_renderer.render(node).to_image());
+
+        assert!(small > 0);
+        assert!(large > small, "{large} <= {small}");
+    }
+
+    #[ignore]
+    #[rstest]
+    fn test_shading(offscreen_renderer: (Manager, OffscreenRenderer)) {
+        let (manager, mut offscreen_renderer) = offscreen_renderer;
+        let mem_alloc = StandardMemoryAllocator::new_default(manager.device);
+
+        let pointcloud = {
+            let mut geometry = read_off("tests/data/teapot.off").unwrap();
+            geometry.normals = Some(compute_normals(
+                &geometry.points.view(),
+                &geometry.faces.as_ref().unwrap().view(),
+            ));
+            geometry.colors = Some(Array1::from_elem(
+                geometry.len_vertices(),
+                Vector3::new(255, 0, 0),
+            ));
+            PointCloud::from_geometry(geometry)
+        };
+
+        let node = VkPointCloudNode::new(VkPointCloud::from_pointcloud(&mem_alloc, &pointcloud));
+        {
+            // Fits the teapot inside the clip volume.
+            let mut node = node.borrow_mut();
+            let sphere = node.properties.bounding_sphere;
+            let scale = 0.4 / sphere.radius;
+            let transformation = nalgebra_glm::translation(&nalgebra_glm::vec3(0.0, 0.0, 0.5))
+                * nalgebra_glm::scaling(&nalgebra_glm::vec3(scale, scale, scale))
+                * nalgebra_glm::translation(&-sphere.center);
+            node.properties.transformation(transformation);
+            node.point_size = 0.01 / scale;
+        }
+
+        let color_image = offscreen_renderer.render(node.clone()).to_image();
+        node.borrow_mut().set_shading(PointCloudShading::Normal);
+        let normal_image = offscreen_renderer.render(node).to_image();
+        normal_image
+            .save("tests/outputs/viz-pointcloud-normal-shading.png")
+            .unwrap();
+
+        assert!(count_drawn_pixels(&color_image) > 0);
+        assert!(color_image
+            .pixels()
+            .zip(normal_image.pixels())
+            .any(|(color, normal)| color != normal));
+    }
+
     #[ignore]
     #[rstest]
     fn test_creation(
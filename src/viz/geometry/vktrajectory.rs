@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+use vulkano::memory::allocator::MemoryAllocator;
+
+use crate::{
+    trajectory::Trajectory,
+    viz::{
+        controllers::FrameStepInfo,
+        node::{node_ref, CommandBuffersContext, MakeNode, Node, NodeProperties, NodeRef},
+        Manager,
+    },
+};
+
+use super::vklines::VkLines;
+
+/// Renders a camera trajectory as a polyline connecting the camera centers, and
+/// a small frustum at each pose.
+pub struct TrajectoryNode {
+    pub properties: NodeProperties,
+    lines: Arc<VkLines>,
+}
+
+impl TrajectoryNode {
+    /// Creates a new trajectory node.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_allocator` - Vulkan's memory allocator.
+    /// * `trajectory` - The trajectory. Must not be empty.
+    /// * `color` - RGB color of the lines.
+    /// * `frustum_size` - Depth of the camera frustums, in world units.
+    pub fn new(
+        memory_allocator: &(impl MemoryAllocator + ?Sized),
+        trajectory: &Trajectory,
+        color: Vector3<u8>,
+        frustum_size: f32,
+    ) -> NodeRef<Self> {
+        let lines = VkLines::from_segments(
+            memory_allocator,
+            &trajectory_segments(trajectory, frustum_size)
+                .into_iter()
+                .map(|segment| (segment, color))
+                .collect::<Vec<_>>(),
+        );
+        node_ref(Self {
+            properties: NodeProperties {
+                bounding_sphere: lines.bounding_sphere(),
+                ..Default::default()
+            },
+            lines,
+        })
+    }
+}
+
+/// Computes the line segments of the trajectory's path and camera frustums.
+fn trajectory_segments(trajectory: &Trajectory, frustum_size: f32) -> Vec<[Vector3<f32>; 2]> {
+    let half_width = frustum_size * 0.5;
+    let half_height = frustum_size * 0.375;
+    let corners = [
+        Vector3::new(-half_width, -half_height, frustum_size),
+        Vector3::new(half_width, -half_height, frustum_size),
+        Vector3::new(half_width, half_height, frustum_size),
+        Vector3::new(-half_width, half_height, frustum_size),
+    ];
+
+    let mut segments = trajectory
+        .camera_to_world
+        .windows(2)
+        .map(|poses| [poses[0].translation(), poses[1].translation()])
+        .collect::<Vec<_>>();
+
+    for camera_to_world in trajectory.camera_to_world.iter() {
+        let center = camera_to_world.translation();
+        let corners = corners.map(|corner| camera_to_world.transform_vector(&corner));
+        for (i, corner) in corners.iter().enumerate() {
+            segments.push([center, *corner]);
+            segments.push([*corner, corners[(i + 1) % 4]]);
+        }
+    }
+    segments
+}
+
+impl Node for TrajectoryNode {
+    fn properties(&self) -> &NodeProperties {
+        &self.properties
+    }
+
+    fn properties_mut(&mut self) -> &mut NodeProperties {
+        &mut self.properties
+    }
+
+    fn new_instance(&self) -> NodeRef<dyn Node> {
+        node_ref(Self {
+            properties: self.properties,
+            lines: self.lines.clone(),
+        })
+    }
+
+    fn collect_command_buffers(
+        &self,
+        context: &mut CommandBuffersContext,
+        window_state: &FrameStepInfo,
+    ) {
+        if !self.properties.visible {
+            return;
+        }
+
+        self.lines.draw(context, window_state, &self.properties);
+    }
+}
+
+impl MakeNode for Trajectory {
+    type Node = TrajectoryNode;
+
+    fn make_node(&self, manager: &mut Manager) -> NodeRef<dyn Node> {
+        TrajectoryNode::new(
+            &manager.memory_allocator,
+            self,
+            Vector3::new(255, 255, 0),
+            0.1,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Quaternion, Vector3};
+    use rstest::rstest;
+    use vulkano::memory::allocator::StandardMemoryAllocator;
+
+    use crate::{
+        trajectory::Trajectory,
+        transform::Transform,
+        viz::{unit_test::vk_manager, Manager, OffscreenRenderer},
+    };
+
+    use super::{trajectory_segments, TrajectoryNode};
+
+    fn sample_trajectory() -> Trajectory {
+        let mut trajectory = Trajectory::default();
+        for i in 0..5 {
+            let x = -0.5 + i as f32 * 0.25;
+            trajectory.push(
+                Transform::new(&Vector3::new(x, x * 0.5, 0.5), &Quaternion::identity()),
+                i as f32,
+            );
+        }
+        trajectory
+    }
+
+    #[test]
+    fn test_trajectory_segments() {
+        let segments = trajectory_segments(&sample_trajectory(), 0.1);
+        assert_eq!(segments.len(), 4 + 5 * 8);
+        assert_eq!(segments[0][1], Vector3::new(-0.25, -0.125, 0.5));
+    }
+
+    #[ignore]
+    #[rstest]
+    fn test_render(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+        let memory_allocator = StandardMemoryAllocator::new_default(vk_manager.device.clone());
+        let node = TrajectoryNode::new(
+            &memory_allocator,
+            &sample_trajectory(),
+            Vector3::new(255, 255, 0),
+            0.1,
+        );
+
+        let image = renderer.render(node).to_image();
+        image.save("tests/outputs/viz-trajectory-node.png").unwrap();
+        assert!(image.pixels().any(|pixel| pixel.0 != [0, 0, 255, 255]));
+    }
+}
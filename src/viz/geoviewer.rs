@@ -1,13 +1,25 @@
+use std::path::Path;
+
+use image::ImageResult;
+use nalgebra::Vector3;
+use ndarray::Array1;
+
+use crate::{pointcloud::PointCloud, transform::Transform};
+
 use super::{
+    controllers::CameraControlKind,
+    geometry::{AxisNode, GridNode, PointCloudShading, VkPointCloud, VkPointCloudNode},
     node::{node_ref, MakeNode, Node, NodeRef},
     scene::Scene,
-    Manager, Window,
+    Manager, OffscreenRenderer, VirtualCamera, Window,
 };
 
 pub struct GeoViewer {
     scene: NodeRef<Scene>,
     manager: Manager,
     window: Option<Window>,
+    show_helpers: bool,
+    camera_control: CameraControlKind,
 }
 
 impl Default for GeoViewer {
@@ -22,6 +34,8 @@ impl GeoViewer {
             scene: node_ref(Scene::default()),
             manager: Manager::default(),
             window: None,
+            show_helpers: true,
+            camera_control: CameraControlKind::default(),
         }
     }
 
@@ -30,9 +44,23 @@ impl GeoViewer {
             scene: node_ref(Scene::default()),
             manager,
             window: None,
+            show_helpers: true,
+            camera_control: CameraControlKind::default(),
         }
     }
 
+    /// Sets whether to show the world axes and a ground grid. Default is true.
+    pub fn show_helpers(&mut self, value: bool) -> &mut Self {
+        self.show_helpers = value;
+        self
+    }
+
+    /// Sets the controller for moving the camera. Default is [`CameraControlKind::Wasd`].
+    pub fn camera_control(&mut self, value: CameraControlKind) -> &mut Self {
+        self.camera_control = value;
+        self
+    }
+
     pub fn add_node(&mut self, node: NodeRef<dyn Node>) {
         self.scene.borrow_mut().add(node);
     }
@@ -47,10 +75,111 @@ impl GeoViewer {
         node
     }
 
+    /// Adds a point cloud drawn with a single color, ignoring its per-vertex colors.
+    ///
+    /// # Arguments
+    ///
+    /// * `pcl`: The point cloud. It doesn't need normals or colors.
+    /// * `color`: RGB color used for all points.
+    ///
+    /// # Returns
+    ///
+    /// * The added node, so its point size or transformation can be adjusted.
+    pub fn add_point_cloud_colored(
+        &mut self,
+        pcl: &PointCloud,
+        color: [u8; 3],
+    ) -> NodeRef<VkPointCloudNode> {
+        let vk_pointcloud = if pcl.normals.is_some() && pcl.colors.is_some() {
+            VkPointCloud::from_pointcloud(&self.manager.memory_allocator, pcl)
+        } else {
+            // The Vulkan buffers need normals and colors.
+            let num_points = pcl.len();
+            let mut pcl = pcl.clone();
+            pcl.normals.get_or_insert_with(|| Array1::zeros(num_points));
+            pcl.colors
+                .get_or_insert_with(|| Array1::from_elem(num_points, Vector3::from(color)));
+            VkPointCloud::from_pointcloud(&self.manager.memory_allocator, &pcl)
+        };
+
+        let node = VkPointCloudNode::new(vk_pointcloud);
+        node.borrow_mut()
+            .set_shading(PointCloudShading::Flat(Vector3::from(color)));
+        self.scene.borrow_mut().add(node.clone());
+
+        node
+    }
+
+    /// The scene with the nodes added so far.
+    pub fn scene(&self) -> NodeRef<Scene> {
+        self.scene.clone()
+    }
+
+    /// The Vulkan manager used to create the nodes.
+    pub fn manager_mut(&mut self) -> &mut Manager {
+        &mut self.manager
+    }
+
+    /// Creates the world axes and ground grid nodes sized to the scene, if enabled.
+    fn helper_nodes(&self) -> Vec<NodeRef<dyn Node>> {
+        if !self.show_helpers {
+            return Vec::new();
+        }
+        let scene_sphere = self.scene.borrow().properties().get_bounding_sphere();
+        let size = scene_sphere.radius.max(1.0);
+        let axis: NodeRef<dyn Node> = AxisNode::new(
+            &self.manager.memory_allocator,
+            &Transform::eye(),
+            size * 0.5,
+        );
+        let grid: NodeRef<dyn Node> = GridNode::new(&self.manager.memory_allocator, size * 2.0, 10);
+        vec![axis, grid]
+    }
+
+    /// Renders the scene without a window and saves it, so results can be produced
+    /// on machines without a display.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Output image path. The format is deduced from its extension.
+    /// * `width`: Image width.
+    /// * `height`: Image height.
+    /// * `camera`: Camera that views the scene.
+    pub fn render_to_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        width: usize,
+        height: usize,
+        camera: &VirtualCamera,
+    ) -> ImageResult<()> {
+        let mut scene = Scene::default();
+        scene.add(self.scene.clone());
+        for node in self.helper_nodes() {
+            scene.add(node);
+        }
+
+        let mut renderer = OffscreenRenderer::new(&mut self.manager, width, height);
+        renderer
+            .render_with_camera(node_ref(scene), camera)
+            .to_image()
+            .save(path)
+    }
+
+    /// Shows the scene in a window until it is closed. The number keys toggle the
+    /// visibility of the added nodes.
     pub fn run(&mut self) {
+        // Helpers live outside `self.scene`, so calling `run` again doesn't duplicate
+        // them and the number keys only index the added nodes.
+        let mut root = Scene::default();
+        root.add(self.scene.clone());
+        for node in self.helper_nodes() {
+            root.add(node);
+        }
+
         self.window
-            .replace(Window::create(&mut self.manager, self.scene.clone()));
+            .replace(Window::create(&mut self.manager, node_ref(root)));
         let window = self.window.as_mut().unwrap();
+        window.camera_control = self.camera_control;
         let scene = self.scene.clone();
 
         window.on_key = Some(Box::new(move |vkeycode, _window| {
@@ -66,3 +195,59 @@ impl GeoViewer {
         window.show();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use crate::unit_test::{sample_teapot_geometry, sample_teapot_pointcloud};
+    use crate::{io::Geometry, viz::VirtualCameraSphericalBuilder};
+
+    use super::*;
+
+    #[ignore]
+    #[rstest]
+    fn test_add_point_cloud_colored(sample_teapot_pointcloud: PointCloud) {
+        let mut viewer = GeoViewer::new();
+        for (color, offset_x) in [([255, 0, 0], -0.5), ([0, 255, 0], 0.5)] {
+            let node = viewer.add_point_cloud_colored(&sample_teapot_pointcloud, color);
+            let mut node = node.borrow_mut();
+            // Fits the teapot inside half of the clip volume.
+            let sphere = node.properties.bounding_sphere;
+            let scale = 0.3 / sphere.radius;
+            let transformation = nalgebra_glm::translation(&nalgebra_glm::vec3(offset_x, 0.0, 0.5))
+                * nalgebra_glm::scaling(&nalgebra_glm::vec3(scale, scale, scale))
+                * nalgebra_glm::translation(&-sphere.center);
+            node.properties.transformation(transformation);
+            node.point_size = 0.01 / scale;
+        }
+
+        let mut renderer = OffscreenRenderer::new(viewer.manager_mut(), 640, 480);
+        let image = renderer.render(viewer.scene()).to_image();
+        image
+            .save("tests/outputs/viz-geoviewer-colored-pointclouds.png")
+            .unwrap();
+
+        assert!(image.pixels().any(|pixel| pixel.0 == [255, 0, 0, 255]));
+        assert!(image.pixels().any(|pixel| pixel.0 == [0, 255, 0, 255]));
+    }
+
+    #[rstest]
+    fn test_render_to_file(sample_teapot_geometry: Geometry) {
+        let mut viewer = GeoViewer::new();
+        viewer.show_helpers(false);
+        viewer.add(&sample_teapot_geometry);
+
+        let scene_sphere = viewer.scene().borrow().properties().get_bounding_sphere();
+        let camera = VirtualCameraSphericalBuilder::fit(&scene_sphere, std::f32::consts::FRAC_PI_2)
+            .near_plane(0.05)
+            .build();
+        let output = "tests/outputs/viz-geoviewer-render-to-file.png";
+        viewer.render_to_file(output, 640, 480, &camera).unwrap();
+
+        let image = image::open(output).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (640, 480));
+        let background = image::Rgba([0, 0, 255, 255]);
+        assert!(image.pixels().any(|pixel| *pixel != background));
+    }
+}
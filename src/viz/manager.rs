@@ -10,6 +10,8 @@ use vulkano::{
     VulkanLibrary,
 };
 
+use crate::error::A3dError;
+
 pub struct Manager {
     pub library: Arc<VulkanLibrary>,
     pub instance: Arc<Instance>,
@@ -20,8 +22,36 @@ pub struct Manager {
 }
 
 impl Default for Manager {
+    /// Creates a manager with the most capable device.
+    ///
+    /// # Panics
+    ///
+    /// If Vulkan is not available, see [`Manager::try_default`] for a fallible version.
     fn default() -> Self {
-        let library = VulkanLibrary::new().expect("Vulkan is not supported by this system");
+        Self::try_default().unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl Manager {
+    /// Creates a manager with the most capable device. Prefers discrete GPUs, then integrated,
+    /// virtual, and CPU devices.
+    ///
+    /// # Returns
+    ///
+    /// * The manager or an [`A3dError::Vulkan`] error if the Vulkan library is missing or
+    ///   no device supports the required extensions and a graphics queue.
+    pub fn try_default() -> Result<Self, A3dError> {
+        Self::try_with_device_filter(|_| true)
+    }
+
+    /// Like [`Manager::try_default`], but only considers the devices accepted by `filter`.
+    fn try_with_device_filter<F>(filter: F) -> Result<Self, A3dError>
+    where
+        F: Fn(&PhysicalDevice) -> bool,
+    {
+        let library = VulkanLibrary::new().map_err(|err| {
+            A3dError::vulkan(format!("Vulkan is not supported by this system: {err}"))
+        })?;
         let mut required_extensions = vulkano_win::required_extensions(&library);
         required_extensions.khr_external_memory_capabilities = true;
 
@@ -33,7 +63,7 @@ impl Default for Manager {
                 ..Default::default()
             },
         )
-        .expect("Failed to create Vulkan instance");
+        .map_err(|err| A3dError::vulkan(format!("Failed to create Vulkan instance: {err}")))?;
 
         let physical_device_extensions = DeviceExtensions {
             khr_swapchain: true,
@@ -42,7 +72,10 @@ impl Default for Manager {
         };
         let (physical_device, queue_family_index) = instance
             .enumerate_physical_devices()
-            .expect("Unable to enumerate physical devices")
+            .map_err(|err| {
+                A3dError::vulkan(format!("Unable to enumerate physical devices: {err}"))
+            })?
+            .filter(|p| filter(p))
             .filter(|p| {
                 p.supported_extensions()
                     .contains(&physical_device_extensions)
@@ -67,7 +100,7 @@ impl Default for Manager {
                 PhysicalDeviceType::Cpu => 3,
                 _ => 4,
             })
-            .expect("no device available");
+            .ok_or_else(|| A3dError::vulkan("No suitable device available"))?;
 
         let (device, queues) = Device::new(
             physical_device.clone(),
@@ -84,21 +117,19 @@ impl Default for Manager {
                 ..Default::default()
             },
         )
-        .expect("failed to create device");
+        .map_err(|err| A3dError::vulkan(format!("Failed to create device: {err}")))?;
         let memory_allocator = StandardMemoryAllocator::new_default(device.clone());
 
-        Self {
+        Ok(Self {
             library,
             instance,
             physical_device,
             device,
             queues: Box::new(queues),
             memory_allocator,
-        }
+        })
     }
-}
 
-impl Manager {
     pub fn device_name(&self) -> String {
         self.physical_device.properties().device_name.clone()
     }
@@ -113,4 +144,11 @@ mod tests {
         let manager = Manager::default();
         println!("Using device {}", manager.device_name());
     }
+
+    #[test]
+    pub fn test_try_without_device() {
+        // Also fails, instead of panicking, on machines without Vulkan.
+        let result = Manager::try_with_device_filter(|_| false);
+        assert!(matches!(result, Err(A3dError::Vulkan(_))));
+    }
 }
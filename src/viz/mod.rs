@@ -1,6 +1,8 @@
+mod box3d;
 pub mod geometry;
 mod manager;
 mod sphere3d;
+pub use box3d::Box3Df;
 pub use manager::Manager;
 
 mod window;
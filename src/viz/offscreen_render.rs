@@ -1,4 +1,5 @@
 use image::{ImageBuffer, Rgba, RgbaImage};
+use ndarray::{Array2, Array3};
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
 use vulkano::{
     buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
@@ -8,7 +9,7 @@ use vulkano::{
     },
     device::{Device, Queue},
     format::Format,
-    image::{view::ImageView, ImageDimensions, StorageImage},
+    image::{view::ImageView, AttachmentImage, ImageDimensions, ImageUsage, StorageImage},
     memory::allocator::{
         AllocationCreateInfo, FreeListAllocator, GenericMemoryAllocator, MemoryUsage,
         StandardMemoryAllocator,
@@ -22,10 +23,11 @@ use vulkano::{
 use super::{
     controllers::FrameStepInfo,
     node::{CommandBuffersContext, Node},
-    Manager,
+    Manager, VirtualCamera,
 };
 
-/// Renders nodes into images instead of a window.
+/// Renders nodes into images instead of a window. Like [`super::Window`], it has a depth buffer,
+/// which is also read back, see [`RenderImage::depth_ndarray`].
 pub struct OffscreenRenderer {
     pub device: Arc<Device>,
     pub pipelines: HashMap<String, Arc<GraphicsPipeline>>,
@@ -34,6 +36,7 @@ pub struct OffscreenRenderer {
     pub framebuffer: Arc<Framebuffer>,
     pub memory_allocator: GenericMemoryAllocator<Arc<FreeListAllocator>>,
     framebuffer_image: Arc<StorageImage>,
+    depth_image: Arc<AttachmentImage>,
     viewport: Viewport,
     command_buffer_allocator: StandardCommandBufferAllocator,
 }
@@ -42,6 +45,8 @@ pub struct OffscreenRenderer {
 /// which can be mapped or converted into image.
 pub struct RenderImage {
     image_buffer: Subbuffer<[u8]>,
+    depth_buffer: Option<Subbuffer<[u16]>>,
+    depth_planes: Option<(f32, f32)>,
     width: u32,
     height: u32,
 }
@@ -57,7 +62,7 @@ impl OffscreenRenderer {
     pub fn new(manager: &mut Manager, width: usize, height: usize) -> Self {
         let queue = manager.queues.next().unwrap();
         let memory_allocator = StandardMemoryAllocator::new_default(manager.device.clone());
-        let (render_pass, framebuffer_image, framebuffer) = {
+        let (render_pass, framebuffer_image, depth_image, framebuffer) = {
             let render_pass = vulkano::single_pass_renderpass!(
                 manager.device.clone(),
                 attachments: {
@@ -66,11 +71,17 @@ impl OffscreenRenderer {
                         store: Store,
                         format: Format::R8G8B8A8_UNORM,
                         samples: 1,
+                    },
+                    depth: {
+                        load: Clear,
+                        store: Store,
+                        format: Format::D16_UNORM,
+                        samples: 1,
                     }
                 },
                 pass: {
                     color: [color],
-                    depth_stencil: {}
+                    depth_stencil: {depth}
                 }
             )
             .unwrap();
@@ -87,15 +98,23 @@ impl OffscreenRenderer {
             )
             .unwrap();
             let view = ImageView::new_default(image.clone()).unwrap();
+            let depth_image = AttachmentImage::with_usage(
+                &memory_allocator,
+                [width as u32, height as u32],
+                Format::D16_UNORM,
+                ImageUsage::TRANSFER_SRC,
+            )
+            .unwrap();
+            let depth_buffer = ImageView::new_default(depth_image.clone()).unwrap();
             let framebuffer = Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
-                    attachments: vec![view],
+                    attachments: vec![view, depth_buffer],
                     ..Default::default()
                 },
             )
             .unwrap();
-            (render_pass, image, framebuffer)
+            (render_pass, image, depth_image, framebuffer)
         };
 
         Self {
@@ -105,6 +124,7 @@ impl OffscreenRenderer {
             queue,
             framebuffer,
             framebuffer_image,
+            depth_image,
             viewport: Viewport {
                 origin: [0.0, 0.0],
                 dimensions: [width as f32, height as f32],
@@ -135,11 +155,52 @@ impl OffscreenRenderer {
     ///
     /// ```
     pub fn render(&mut self, scene: Rc<RefCell<dyn Node>>) -> RenderImage {
+        self.render_with_matrices(
+            scene,
+            nalgebra_glm::Mat4::identity(),
+            nalgebra_glm::Mat4::identity(),
+            None,
+        )
+    }
+
+    /// Draws the scene into a image as seen by a camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `scene`: Target scene
+    /// * `camera`: Camera that views the scene.
+    ///
+    /// # Returns
+    ///
+    /// * A RenderImage object with the rendered scene. Its depth is metric,
+    /// see [`RenderImage::depth_ndarray`].
+    pub fn render_with_camera(
+        &mut self,
+        scene: Rc<RefCell<dyn Node>>,
+        camera: &VirtualCamera,
+    ) -> RenderImage {
+        self.render_with_matrices(
+            scene,
+            camera.matrix(),
+            camera.projection.matrix(),
+            Some((camera.projection.near, camera.projection.far)),
+        )
+    }
+
+    fn render_with_matrices(
+        &mut self,
+        scene: Rc<RefCell<dyn Node>>,
+        view_matrix: nalgebra_glm::Mat4,
+        projection_matrix: nalgebra_glm::Mat4,
+        depth_planes: Option<(f32, f32)>,
+    ) -> RenderImage {
         let (width, height) = (
             self.viewport.dimensions[0] as usize,
             self.viewport.dimensions[1] as usize,
         );
-        let image_buffer = Buffer::from_iter(
+        let mut render_image =
+            RenderImage::allocate(&self.memory_allocator, width as u32, height as u32);
+        let depth_buffer = Buffer::from_iter(
             &self.memory_allocator,
             BufferCreateInfo {
                 usage: BufferUsage::TRANSFER_DST,
@@ -149,7 +210,7 @@ impl OffscreenRenderer {
                 usage: MemoryUsage::Download,
                 ..Default::default()
             },
-            (0..height * width * 4).map(|_| 0u8),
+            (0..width * height).map(|_| 0u16),
         )
         .expect("failed to create buffer");
 
@@ -162,7 +223,7 @@ impl OffscreenRenderer {
         builder
             .begin_render_pass(
                 RenderPassBeginInfo {
-                    clear_values: vec![Some([0.0, 0.0, 1.0, 1.0].into())],
+                    clear_values: vec![Some([0.0, 0.0, 1.0, 1.0].into()), Some(1f32.into())],
                     ..RenderPassBeginInfo::framebuffer(self.framebuffer.clone())
                 },
                 SubpassContents::Inline,
@@ -171,16 +232,15 @@ impl OffscreenRenderer {
             .set_viewport(0, [self.viewport.clone()]);
 
         scene.borrow().collect_command_buffers(
-            &mut CommandBuffersContext {
-                device: self.device.clone(),
-                queue: self.queue.clone(),
-                builder: &mut builder,
-                pipelines: &mut self.pipelines,
-                render_pass: self.render_pass.clone(),
-                view_normals_matrix: nalgebra_glm::Mat3::identity(),
-                view_matrix: nalgebra_glm::Mat4::identity(),
-                projection_matrix: nalgebra_glm::Mat4::identity(),
-            },
+            &mut CommandBuffersContext::new(
+                self.device.clone(),
+                self.queue.clone(),
+                &mut builder,
+                &mut self.pipelines,
+                self.render_pass.clone(),
+                view_matrix,
+                projection_matrix,
+            ),
             &FrameStepInfo::new(self.viewport.dimensions),
         );
 
@@ -189,7 +249,12 @@ impl OffscreenRenderer {
             .unwrap()
             .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
                 self.framebuffer_image.clone(),
-                image_buffer.clone(),
+                render_image.buffer(),
+            ))
+            .unwrap()
+            .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                self.depth_image.clone(),
+                depth_buffer.clone(),
             ))
             .unwrap();
 
@@ -201,21 +266,59 @@ impl OffscreenRenderer {
             .unwrap();
         future.wait(None).unwrap();
 
-        RenderImage {
-            image_buffer,
-            width: width as u32,
-            height: height as u32,
-        }
+        render_image.depth_buffer = Some(depth_buffer);
+        render_image.depth_planes = depth_planes;
+        render_image
     }
 }
 
 impl RenderImage {
+    /// Allocates a CPU-readable buffer for receiving a RGBA image copied from the GPU.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory_allocator`: Allocator for the buffer.
+    /// * `width`: Image width.
+    /// * `height`: Image height.
+    pub(super) fn allocate(
+        memory_allocator: &StandardMemoryAllocator,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let image_buffer = Buffer::from_iter(
+            memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            (0..height * width * 4).map(|_| 0u8),
+        )
+        .expect("failed to create buffer");
+
+        Self {
+            image_buffer,
+            depth_buffer: None,
+            depth_planes: None,
+            width,
+            height,
+        }
+    }
+
+    /// The GPU buffer that should be the destination of the image copy.
+    pub(super) fn buffer(&self) -> Subbuffer<[u8]> {
+        self.image_buffer.clone()
+    }
+
     /// Returns a copy of the buffer into a RGBA Image.
     pub fn to_image(&self) -> RgbaImage {
         let image_buffer = self.image_buffer.read().unwrap();
 
         RgbaImage::from_fn(self.width, self.height, |x, y| {
-            let offset = (y * self.height + x) as usize;
+            let offset = ((y * self.width + x) * 4) as usize;
             Rgba::<u8>([
                 image_buffer[offset],
                 image_buffer[offset + 1],
@@ -225,6 +328,51 @@ impl RenderImage {
         })
     }
 
+    /// Returns a copy of the buffer as an array with shape (height, width, 4), the RGBA channels.
+    pub fn to_ndarray(&self) -> Array3<u8> {
+        let image_buffer = self.image_buffer.read().unwrap();
+        Array3::from_shape_vec(
+            (self.height as usize, self.width as usize, 4),
+            image_buffer.to_vec(),
+        )
+        .unwrap()
+    }
+
+    /// Returns a copy of the depth buffer as an array with shape (height, width).
+    /// Pixels without geometry have zero depth, like in depth images.
+    ///
+    /// If rendered with [`OffscreenRenderer::render_with_camera`], the depth is metric,
+    /// i.e., the distance along the camera's viewing direction, recovered using its
+    /// projection's near and far planes. Otherwise, it is the normalized depth in [0, 1].
+    ///
+    /// # Panics
+    ///
+    /// If the image has no depth, e.g., a window capture.
+    pub fn depth_ndarray(&self) -> Array2<f32> {
+        let depth_buffer = self
+            .depth_buffer
+            .as_ref()
+            .expect("Only offscreen renders have depth")
+            .read()
+            .unwrap();
+        let depth =
+            Array2::from_shape_fn((self.height as usize, self.width as usize), |(row, col)| {
+                depth_buffer[row * self.width as usize + col]
+            });
+
+        depth.map(|value| {
+            if *value == u16::MAX {
+                return 0.0;
+            }
+            let normalized = *value as f32 / u16::MAX as f32;
+            match self.depth_planes {
+                // Inverts the z mapping of [`super::VirtualProjection::matrix`].
+                Some((near, far)) => 2.0 * far * near / ((far + near) - normalized * (far - near)),
+                None => normalized,
+            }
+        })
+    }
+
     /// Maps without copying the buffer into an image.
     ///
     /// # Arguments
@@ -243,7 +391,25 @@ impl RenderImage {
 mod tests {
     use rstest::rstest;
 
-    use crate::viz::{geometry::sample_nodes::teapot_node, Manager};
+    use image::Rgba;
+
+    use nalgebra::Vector3;
+    use ndarray::{array, Array1};
+    use vulkano::memory::allocator::StandardMemoryAllocator;
+
+    use crate::{
+        io::GeometryBuilder,
+        pointcloud::PointCloud,
+        viz::{
+            geometry::{
+                sample_nodes::teapot_node, PointCloudShading, VkMesh, VkMeshNode, VkPointCloud,
+                VkPointCloudNode,
+            },
+            node::node_ref,
+            scene::Scene,
+            Manager, VirtualCamera,
+        },
+    };
 
     use super::OffscreenRenderer;
     use crate::viz::unit_test::vk_manager;
@@ -266,4 +432,151 @@ mod tests {
             assert_eq!(image.height(), 768);
         })
     }
+
+    #[ignore]
+    #[rstest]
+    pub fn test_to_image_pixels(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+
+        // An empty scene renders only the clear color.
+        let image = renderer.render(node_ref(Scene::default()));
+        let owned_image = image.to_image();
+        let blue = Rgba([0, 0, 255, 255]);
+        assert_eq!(*owned_image.get_pixel(0, 0), blue);
+        assert_eq!(*owned_image.get_pixel(321, 57), blue);
+        assert_eq!(*owned_image.get_pixel(639, 479), blue);
+
+        image.map(|mapped| {
+            assert_eq!(*mapped.get_pixel(321, 57), *owned_image.get_pixel(321, 57));
+        });
+    }
+
+    #[ignore]
+    #[rstest]
+    pub fn test_to_ndarray(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+
+        let image = renderer.render(teapot_node(&vk_manager));
+        let array = image.to_ndarray();
+        assert_eq!(array.dim(), (480, 640, 4));
+
+        let owned_image = image.to_image();
+        for (x, y) in [(0, 0), (321, 57), (320, 240), (639, 479)] {
+            let pixel = owned_image.get_pixel(x, y);
+            for channel in 0..4 {
+                assert_eq!(array[(y as usize, x as usize, channel)], pixel[channel]);
+            }
+        }
+    }
+
+    #[ignore]
+    #[rstest]
+    pub fn test_depth_test(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+        let memory_allocator = StandardMemoryAllocator::new_default(vk_manager.device.clone());
+
+        // Grid of points at the given depth, in clip coordinates.
+        let plane = |z: f32, color: Vector3<u8>| {
+            let mut pointcloud = PointCloud::zeros(25);
+            for (i, point) in pointcloud.points.iter_mut().enumerate() {
+                *point = Vector3::new((i % 5) as f32 * 0.2 - 0.4, (i / 5) as f32 * 0.2 - 0.4, z);
+            }
+            let node = VkPointCloudNode::new(VkPointCloud::from_pointcloud(
+                &memory_allocator,
+                &pointcloud,
+            ));
+            node.borrow_mut().point_size = 0.2;
+            node.borrow_mut()
+                .set_shading(PointCloudShading::Flat(color));
+            node
+        };
+
+        // The far plane is drawn last, without depth testing it would cover the near one.
+        let mut scene = Scene::default();
+        scene
+            .add(plane(0.25, Vector3::new(255, 0, 0)))
+            .add(plane(0.75, Vector3::new(0, 255, 0)));
+        let image = renderer.render(node_ref(scene)).to_image();
+        image.save("tests/outputs/viz-offscreen-depth.png").unwrap();
+
+        assert_eq!(*image.get_pixel(320, 240), Rgba([255, 0, 0, 255]));
+        assert!(!image.pixels().any(|pixel| pixel.0 == [0, 255, 0, 255]));
+    }
+
+    #[ignore]
+    #[rstest]
+    pub fn test_color_channel_order(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+        let memory_allocator = StandardMemoryAllocator::new_default(vk_manager.device.clone());
+        let red = Vector3::new(255, 0, 0);
+        let is_red = |pixel: &Rgba<u8>| pixel[0] > 200 && pixel[1] < 50 && pixel[2] < 50;
+
+        let mut pointcloud = PointCloud::zeros(1);
+        pointcloud.points[0] = Vector3::new(0.0, 0.0, 0.5);
+        pointcloud.colors.as_mut().unwrap()[0] = red;
+        let node = VkPointCloudNode::new(VkPointCloud::from_pointcloud(
+            &memory_allocator,
+            &pointcloud,
+        ));
+        node.borrow_mut().point_size = 0.2;
+        node.borrow_mut().set_shading(PointCloudShading::Color);
+        let image = renderer.render(node).to_image();
+        image
+            .save("tests/outputs/viz-offscreen-red-pointcloud.png")
+            .unwrap();
+        assert!(is_red(image.get_pixel(320, 240)));
+
+        // Quad facing the light, so the diffuse factor saturates.
+        let mesh = GeometryBuilder::new(array![
+            Vector3::new(-0.5, -0.5, 0.5),
+            Vector3::new(0.5, -0.5, 0.5),
+            Vector3::new(0.5, 0.5, 0.5),
+            Vector3::new(-0.5, 0.5, 0.5)
+        ])
+        .with_normals(Array1::from_elem(4, Vector3::new(0.0, 1.0, 0.0)))
+        .with_colors(Array1::from_elem(4, red))
+        .with_faces(array![[0, 1, 2], [0, 2, 3]])
+        .build();
+        let node = VkMeshNode::new(VkMesh::from_geometry(&memory_allocator, &mesh));
+        let image = renderer.render(node).to_image();
+        image
+            .save("tests/outputs/viz-offscreen-red-mesh.png")
+            .unwrap();
+        assert!(is_red(image.get_pixel(320, 240)));
+    }
+
+    #[ignore]
+    #[rstest]
+    pub fn test_depth_ndarray(mut vk_manager: Manager) {
+        let mut renderer = OffscreenRenderer::new(&mut vk_manager, 640, 480);
+        let memory_allocator = StandardMemoryAllocator::new_default(vk_manager.device.clone());
+
+        // The default camera is at z = -1 looking toward +z, so the plane is 5 units away.
+        let plane = GeometryBuilder::new(array![
+            Vector3::new(-2.0, -2.0, 4.0),
+            Vector3::new(2.0, -2.0, 4.0),
+            Vector3::new(2.0, 2.0, 4.0),
+            Vector3::new(-2.0, 2.0, 4.0)
+        ])
+        .with_normals(Array1::from_elem(4, Vector3::new(0.0, 0.0, -1.0)))
+        .with_colors(Array1::from_elem(4, Vector3::new(255, 255, 255)))
+        .with_faces(array![[0, 1, 2], [0, 2, 3]])
+        .build();
+        let node = VkMeshNode::new(VkMesh::from_geometry(&memory_allocator, &plane));
+
+        let image = renderer.render_with_camera(node, &VirtualCamera::default());
+        let depth = image.depth_ndarray();
+        assert_eq!(depth.dim(), (480, 640));
+
+        // Depth is along the viewing direction, so it's constant over the plane.
+        for (row, col) in [(240, 320), (200, 380), (280, 270)] {
+            assert!(
+                (depth[(row, col)] - 5.0).abs() < 1e-2,
+                "{}",
+                depth[(row, col)]
+            );
+        }
+        assert_eq!(depth[(0, 0)], 0.0);
+        assert_eq!(depth[(479, 639)], 0.0);
+    }
 }
@@ -1,4 +1,5 @@
-use nalgebra::Vector3;
+use nalgebra::{Vector3, Vector4};
+use nalgebra_glm::Mat4;
 use ndarray::ArrayView1;
 
 use crate::transform::{Transform, Transformable};
@@ -72,6 +73,40 @@ impl Sphere3Df {
         let radius = (self.center - center).norm() + self.radius.max(other.radius);
         Self { center, radius }
     }
+
+    /// Tests whether the sphere intersects the view frustum, so nodes outside the camera
+    /// view can be skipped while rendering.
+    ///
+    /// # Arguments
+    ///
+    /// * `view_proj` - The projection matrix times the view matrix, using OpenGL clip space.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if any part of the sphere is inside the frustum. Empty spheres return `false`.
+    pub fn intersects_frustum(&self, view_proj: &Mat4) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let row = |i: usize| -> Vector4<f32> { view_proj.row(i).transpose() };
+        let (x_row, y_row, z_row, w_row) = (row(0), row(1), row(2), row(3));
+        let center = self.center.push(1.0);
+
+        [
+            w_row + x_row,
+            w_row - x_row,
+            w_row + y_row,
+            w_row - y_row,
+            w_row + z_row,
+            w_row - z_row,
+        ]
+        .iter()
+        .all(|plane| {
+            let normal_norm = plane.xyz().norm();
+            plane.dot(&center) / normal_norm >= -self.radius
+        })
+    }
 }
 
 impl Transformable<Sphere3Df> for Transform {
@@ -91,3 +126,35 @@ impl Transformable<Sphere3Df> for Mat4x4 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::Sphere3Df;
+    use crate::viz::virtual_projection::VirtualProjection;
+
+    #[test]
+    fn test_intersects_frustum() {
+        let view = nalgebra_glm::look_at(
+            &Vector3::zeros(),
+            &Vector3::new(0.0, 0.0, -1.0),
+            &Vector3::new(0.0, 1.0, 0.0),
+        );
+        let view_proj = VirtualProjection::new(-1.0, 1.0, -1.0, 1.0, 1.0, 100.0).matrix() * view;
+        let sphere = |x: f32, y: f32, z: f32, radius: f32| Sphere3Df {
+            center: Vector3::new(x, y, z),
+            radius,
+        };
+
+        assert!(sphere(0.0, 0.0, -10.0, 1.0).intersects_frustum(&view_proj));
+        assert!(sphere(11.5, 0.0, -10.0, 2.0).intersects_frustum(&view_proj));
+        assert!(sphere(0.0, 0.0, -101.0, 2.0).intersects_frustum(&view_proj));
+
+        assert!(!sphere(0.0, 0.0, 10.0, 1.0).intersects_frustum(&view_proj));
+        assert!(!sphere(50.0, 0.0, -10.0, 1.0).intersects_frustum(&view_proj));
+        assert!(!sphere(0.0, -50.0, -10.0, 1.0).intersects_frustum(&view_proj));
+        assert!(!sphere(0.0, 0.0, -200.0, 1.0).intersects_frustum(&view_proj));
+        assert!(!Sphere3Df::empty().intersects_frustum(&view_proj));
+    }
+}
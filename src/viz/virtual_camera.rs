@@ -68,6 +68,7 @@ impl VirtualCamera {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct VirtualCameraSphericalBuilder {
     pub sphere: Sphere3Df,
     elevation: f32,
@@ -154,6 +155,10 @@ impl VirtualCameraSphericalBuilder {
         self
     }
 
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
+
     pub fn build(self) -> VirtualCamera {
         let theta = self.elevation;
         let phi = self.azimuth + std::f32::consts::PI * 1.5;
@@ -1,9 +1,13 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 
 use vulkano::{
     command_buffer::{
         allocator::StandardCommandBufferAllocator, AutoCommandBufferBuilder, CommandBufferUsage,
-        PrimaryAutoCommandBuffer, RenderPassBeginInfo, SubpassContents,
+        CopyImageToBufferInfo, PrimaryAutoCommandBuffer, RenderPassBeginInfo, SubpassContents,
     },
     device::{Device, Queue},
     format::Format,
@@ -20,16 +24,23 @@ use vulkano::{
 
 use vulkano_win::VkSurfaceBuild;
 use winit::{
-    event::{ElementState, Event, VirtualKeyCode, WindowEvent},
+    event::{ElementState, Event, MouseScrollDelta, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     platform::run_return::EventLoopExtRunReturn,
     window::{Window as WWindow, WindowBuilder},
 };
 
+use crate::error::A3dError;
+use image::ImageResult;
+
 use super::{
-    controllers::{FrameStepInfo, SceneState, VirtualCameraControl, WASDVirtualCameraControl},
+    controllers::{
+        CameraControlKind, FrameStepInfo, OrbitCameraControl, SceneState, VirtualCameraControl,
+        WASDVirtualCameraControl,
+    },
     manager::Manager,
     node::{CommandBuffersContext, NodeRef},
+    offscreen_render::RenderImage,
 };
 use super::{node::Node, virtual_camera::VirtualCameraSphericalBuilder};
 use std::collections::HashMap;
@@ -43,7 +54,12 @@ pub struct Window {
     scene: NodeRef<dyn Node>,
     command_buffer_allocator: StandardCommandBufferAllocator,
     pub on_key: Option<KeyCallback>,
+    /// Key that saves a screenshot of the current frame.
+    pub screenshot_key: VirtualKeyCode,
+    /// Which controller moves the camera.
+    pub camera_control: CameraControlKind,
     frame_counter: usize,
+    capture_path: Option<PathBuf>,
 }
 
 fn window_size_dependent_setup(
@@ -76,16 +92,40 @@ fn window_size_dependent_setup(
 }
 
 impl Window {
+    /// Creates a window that shows a scene.
+    ///
+    /// # Panics
+    ///
+    /// If the window can't be created, see [`Window::try_create`] for a fallible version.
     pub fn create(manager: &mut Manager, scene: NodeRef<dyn Node>) -> Self {
+        Self::try_create(manager, scene).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Creates a window that shows a scene.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager`: Vulkan manager, it provides the device and queue for the window.
+    /// * `scene`: The scene to show.
+    ///
+    /// # Returns
+    ///
+    /// * The window or an [`A3dError::Vulkan`] error if its surface can't be created,
+    ///   e.g., when there's no display, or the manager has no queue left.
+    pub fn try_create(manager: &mut Manager, scene: NodeRef<dyn Node>) -> Result<Self, A3dError> {
         let event_loop = EventLoop::new();
         let surface = WindowBuilder::new()
             .build_vk_surface(&event_loop, manager.instance.clone())
-            .unwrap();
+            .map_err(|err| A3dError::vulkan(format!("Failed to create window surface: {err}")))?;
+        let queue = manager
+            .queues
+            .next()
+            .ok_or_else(|| A3dError::vulkan("No queue available for the window"))?;
 
-        Self {
+        Ok(Self {
             surface,
             device: manager.device.clone(),
-            queue: manager.queues.next().unwrap(),
+            queue,
             event_loop: Some(event_loop),
             scene: scene.clone(),
             command_buffer_allocator: StandardCommandBufferAllocator::new(
@@ -93,8 +133,21 @@ impl Window {
                 Default::default(),
             ),
             on_key: None,
+            screenshot_key: VirtualKeyCode::F12,
+            camera_control: CameraControlKind::default(),
             frame_counter: 0,
-        }
+            capture_path: None,
+        })
+    }
+
+    /// Saves the next rendered frame into an image file. Saving errors are returned
+    /// by [`Window::try_show`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Output image path. The format is deduced from its extension.
+    pub fn capture_next_frame<P: AsRef<Path>>(&mut self, path: P) {
+        self.capture_path = Some(path.as_ref().to_path_buf());
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -107,6 +160,7 @@ impl Window {
         view_matrix: &nalgebra_glm::Mat4,
         projection_matrix: &nalgebra_glm::Mat4,
         window_state: &FrameStepInfo,
+        capture: Option<(Arc<SwapchainImage>, &RenderImage)>,
     ) -> PrimaryAutoCommandBuffer {
         let mut builder = AutoCommandBufferBuilder::primary(
             &self.command_buffer_allocator,
@@ -139,10 +193,29 @@ impl Window {
             window_state,
         );
         builder.end_render_pass().unwrap();
+        if let Some((image, render_image)) = capture {
+            builder
+                .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(
+                    image,
+                    render_image.buffer(),
+                ))
+                .unwrap();
+        }
         builder.build().unwrap()
     }
 
+    /// Shows the window until it is closed. Panics if a screenshot can't be saved,
+    /// see [`Window::try_show`].
     pub fn show(&mut self) {
+        self.try_show().unwrap_or_else(|err| panic!("{err}"));
+    }
+
+    /// Shows the window until it is closed.
+    ///
+    /// # Returns
+    ///
+    /// * The error of a screenshot that couldn't be saved. The window is closed when it happens.
+    pub fn try_show(&mut self) -> ImageResult<()> {
         let dimensions = {
             let window = self
                 .surface
@@ -153,7 +226,7 @@ impl Window {
             window.inner_size()
         };
 
-        let (mut swapchain, images) = {
+        let (mut swapchain, mut images) = {
             let surface_capabilities = self
                 .device
                 .physical_device()
@@ -174,7 +247,7 @@ impl Window {
                     min_image_count: surface_capabilities.min_image_count,
                     image_format,
                     image_extent: dimensions.into(),
-                    image_usage: ImageUsage::COLOR_ATTACHMENT,
+                    image_usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC,
                     composite_alpha: surface_capabilities
                         .supported_composite_alpha
                         .into_iter()
@@ -228,12 +301,18 @@ impl Window {
 
         let scene_sphere = (self.scene).borrow().properties().get_bounding_sphere();
 
-        let mut camera_control = WASDVirtualCameraControl::new(
-            VirtualCameraSphericalBuilder::fit(&scene_sphere, std::f32::consts::FRAC_PI_2)
-                .near_plane(0.05)
-                .build(),
-            0.05,
-        );
+        let mut camera_control: Box<dyn VirtualCameraControl> = match self.camera_control {
+            CameraControlKind::Wasd => Box::new(WASDVirtualCameraControl::new(
+                VirtualCameraSphericalBuilder::fit(&scene_sphere, std::f32::consts::FRAC_PI_2)
+                    .near_plane(0.05)
+                    .build(),
+                0.05,
+            )),
+            CameraControlKind::Orbit => Box::new(OrbitCameraControl::new(
+                &scene_sphere,
+                std::f32::consts::FRAC_PI_2,
+            )),
+        };
 
         let mut window_state: FrameStepInfo = FrameStepInfo {
             viewport_size: [dimensions.width as f32, dimensions.height as f32],
@@ -245,6 +324,8 @@ impl Window {
 
         let event_loop = self.event_loop.take();
         let mut instant = Instant::now();
+        let mut capture_result: ImageResult<()> = Ok(());
+        let capture_result_ref = &mut capture_result;
         event_loop
             .unwrap()
             .run_return(move |event, _, control_flow| {
@@ -279,6 +360,16 @@ impl Window {
                             &scene_state,
                         );
                     }
+                    Event::WindowEvent {
+                        event: WindowEvent::MouseWheel { delta, .. },
+                        ..
+                    } => {
+                        let lines = match delta {
+                            MouseScrollDelta::LineDelta(_, y) => y,
+                            MouseScrollDelta::PixelDelta(position) => position.y as f32 / 100.0,
+                        };
+                        camera_control.mouse_wheel(lines, &window_state, &scene_state);
+                    }
                     Event::WindowEvent {
                         event: WindowEvent::KeyboardInput { input, .. },
                         ..
@@ -288,10 +379,18 @@ impl Window {
                         }
                         camera_control.key_event(&window_state, &scene_state);
 
-                        if let (Some(on_key), Some(vkeycode), ElementState::Pressed) =
-                            (self.on_key.as_mut(), input.virtual_keycode, input.state)
+                        if let (Some(vkeycode), ElementState::Pressed) =
+                            (input.virtual_keycode, input.state)
                         {
-                            on_key(vkeycode, &window_state);
+                            if vkeycode == self.screenshot_key {
+                                self.capture_path = Some(PathBuf::from(format!(
+                                    "screenshot-{:05}.png",
+                                    self.frame_counter
+                                )));
+                            }
+                            if let Some(on_key) = self.on_key.as_mut() {
+                                on_key(vkeycode, &window_state);
+                            }
                         }
                     }
                     Event::RedrawEventsCleared => {
@@ -334,6 +433,7 @@ impl Window {
                                 render_pass.clone(),
                                 &mut viewport,
                             );
+                            images = new_images;
                             recreate_swapchain = false;
                         }
 
@@ -351,14 +451,30 @@ impl Window {
                             recreate_swapchain = true;
                         };
 
+                        // 8-bit RGBA or BGRA swapchains can be copied directly into the image.
+                        let capture = self
+                            .capture_path
+                            .take()
+                            .filter(|_| swapchain.image_format().block_size() == Some(4))
+                            .map(|path| {
+                                let [width, height] = swapchain.image_extent();
+                                (
+                                    path,
+                                    RenderImage::allocate(&memory_allocator, width, height),
+                                )
+                            });
+
                         let command_buffer = self.get_command_buffers(
                             framebuffers[image_index as usize].clone(),
                             &mut viewport,
                             &mut pipelines,
                             render_pass.clone(),
-                            &camera_control.camera.matrix(),
+                            &camera_control.view_matrix(),
                             &camera_control.projection_matrix(),
                             &window_state,
+                            capture.as_ref().map(|(_, render_image)| {
+                                (images[image_index as usize].clone(), render_image)
+                            }),
                         );
 
                         sync::now(self.device.clone())
@@ -376,11 +492,50 @@ impl Window {
                             .unwrap()
                             .wait(None)
                             .unwrap();
+
+                        if let Some((path, render_image)) = capture {
+                            let mut image = render_image.to_image();
+                            if matches!(
+                                swapchain.image_format(),
+                                Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB
+                            ) {
+                                image.pixels_mut().for_each(|pixel| pixel.0.swap(0, 2));
+                            }
+                            if let Err(err) = image.save(&path) {
+                                *capture_result_ref = Err(err);
+                                *control_flow = ControlFlow::Exit;
+                            }
+                        }
                         self.frame_counter += 1;
                     }
                     _ => (),
                 }
                 instant = Instant::now();
             });
+        capture_result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::viz::{geometry::sample_nodes::teapot_node, unit_test::vk_manager, Manager};
+
+    use super::Window;
+
+    /// Needs a display. Close the window to finish the test.
+    #[ignore]
+    #[rstest]
+    fn test_capture_next_frame(mut vk_manager: Manager) {
+        let output = "tests/outputs/window-screenshot.png";
+        let _ = std::fs::remove_file(output);
+
+        let mut window = Window::create(&mut vk_manager, teapot_node(&vk_manager));
+        window.capture_next_frame(output);
+        window.show();
+
+        let image = image::open(output).unwrap();
+        assert!(image.width() > 0 && image.height() > 0);
     }
 }
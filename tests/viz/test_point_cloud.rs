@@ -2,6 +2,7 @@ use align3d::viz::{
     geometry::{VkPointCloud, VkPointCloudNode},
     Manager, Window,
 };
+use winit::event::VirtualKeyCode;
 
 mod data;
 use data::sample_rgbd_pointcloud;
@@ -11,5 +12,17 @@ fn main() {
     let pointcloud =
         VkPointCloud::from_pointcloud(&manager.memory_allocator, &sample_rgbd_pointcloud());
 
-    Window::create(&mut manager, VkPointCloudNode::new(pointcloud)).show();
+    let node = VkPointCloudNode::new(pointcloud);
+    let mut window = Window::create(&mut manager, node.clone());
+
+    // Use +/- to grow or shrink the points.
+    window.on_key = Some(Box::new(move |vkeycode, _window_state| {
+        let mut node = node.borrow_mut();
+        match vkeycode {
+            VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => node.point_size *= 1.5,
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => node.point_size /= 1.5,
+            _ => (),
+        }
+    }));
+    window.show();
 }